@@ -17,11 +17,38 @@ struct Args {
     /// Interpret Scheme source file
     #[arg(short, long, value_name = "FILE PATH")]
     source: Option<String>,
+
+    /// Maximum depth of nested non-tail calls
+    #[arg(long, value_name = "DEPTH", default_value_t = schemius::DEFAULT_MAX_DEPTH)]
+    max_depth: usize,
+
+    /// Optimize expressions before evaluating them
+    #[arg(long)]
+    optimize: bool,
+
+    /// Significant decimal digits of inexact numbers, beyond native floats
+    #[cfg(feature = "bigfloat")]
+    #[arg(long, value_name = "DIGITS")]
+    precision: Option<usize>,
+
+    /// Arguments passed to the Scheme program (see command-line)
+    #[arg(trailing_var_arg = true, value_name = "ARGS")]
+    args: Vec<String>,
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
     let args = Args::parse();
     let mut interpreter = Interpreter::default();
+    interpreter.set_max_depth(args.max_depth);
+    interpreter.set_optimize(args.optimize);
+    #[cfg(feature = "bigfloat")]
+    interpreter.set_float_precision(args.precision);
+
+    let program = match args.source {
+        Some(ref path) => path.clone(),
+        None => std::env::args().next().unwrap_or_default(),
+    };
+    interpreter.set_command_line([program].into_iter().chain(args.args).collect());
 
     match args.eval {
         Some(expr) => interpreter.eval_expression_no_print(expr)?,
@@ -1,16 +1,19 @@
 use schemius::Interpreter;
-use std::sync::{LazyLock, Mutex};
+use std::cell::RefCell;
 use wasm_bindgen::prelude::*;
 
-static INTERPRETER: LazyLock<Mutex<Interpreter>> =
-    LazyLock::new(|| Mutex::new(Interpreter::default()));
+thread_local! {
+    static INTERPRETER: RefCell<Interpreter> = RefCell::new(Interpreter::default());
+}
 
 #[wasm_bindgen]
 pub fn evaluate(expression: &str) -> String {
-    match INTERPRETER.try_lock().unwrap().eval_expression_and_format(expression.to_string()) {
-        Ok(result) => result,
-        Err(err) => err,
-    }
+    INTERPRETER.with_borrow_mut(|interpreter| {
+        match interpreter.eval_expression_and_format(expression.to_string()) {
+            Ok(result) => result,
+            Err(err) => err.to_string(),
+        }
+    })
 }
 
 #[cfg(test)]
@@ -0,0 +1,83 @@
+//! Runs a few classic programs with each evaluation strategy, so that the bytecode virtual
+//! machine can be compared with the tree-walking evaluator.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use schemius::{Interpreter, Strategy};
+
+const FIB: &str = "
+(define (fib n)
+  (if (< n 2)
+      n
+      (+ (fib (- n 1)) (fib (- n 2)))))";
+
+const TAK: &str = "
+(define (tak x y z)
+  (if (not (< y x))
+      z
+      (tak (tak (- x 1) y z)
+           (tak (- y 1) z x)
+           (tak (- z 1) x y))))";
+
+const NQUEENS: &str = "
+(define (queens size)
+  (define (ok? row dist placed)
+    (cond ((null? placed) #t)
+          ((= (car placed) (+ row dist)) #f)
+          ((= (car placed) (- row dist)) #f)
+          ((= (car placed) row) #f)
+          (else (ok? row (+ dist 1) (cdr placed)))))
+  (define (place k placed)
+    (if (= k 0)
+        1
+        (let loop ((row 1) (count 0))
+          (if (> row size)
+              count
+              (loop (+ row 1)
+                    (if (ok? row 1 placed)
+                        (+ count (place (- k 1) (cons row placed)))
+                        count))))))
+  (place size '()))";
+
+const STRING_BUILDING: &str = "
+(define (build n)
+  (do ((k 0 (+ k 1))
+       (s \"\" (string-append s \"ab\")))
+      ((= k n) (string-length s))))";
+
+const PROGRAMS: [(&str, &str, &str, &str); 4] = [
+    ("fib", FIB, "(fib 15)", "610"),
+    ("tak", TAK, "(tak 12 8 4)", "5"),
+    ("nqueens", NQUEENS, "(queens 6)", "4"),
+    ("string-building", STRING_BUILDING, "(build 500)", "1000"),
+];
+
+const STRATEGIES: [(&str, Strategy, bool); 3] = [
+    ("bytecode", Strategy::Bytecode, false),
+    ("bytecode-optimized", Strategy::Bytecode, true),
+    ("tree-walking", Strategy::TreeWalking, false),
+];
+
+fn programs(c: &mut Criterion) {
+    for (name, definition, call, expected) in PROGRAMS {
+        let mut group = c.benchmark_group(name);
+
+        for (strategy_name, strategy, optimize) in STRATEGIES {
+            let mut interpreter = Interpreter::default();
+            interpreter.set_strategy(strategy);
+            interpreter.set_optimize(optimize);
+            interpreter.eval_expression(definition.to_string()).unwrap();
+
+            let result = interpreter.eval_expression(call.to_string()).unwrap();
+            assert_eq!(result.to_string(), expected);
+
+            group.bench_function(strategy_name, |b| {
+                b.iter(|| interpreter.eval_expression(call.to_string()).unwrap())
+            });
+        }
+
+        group.finish();
+    }
+}
+
+criterion_group!(benches, programs);
+criterion_main!(benches);
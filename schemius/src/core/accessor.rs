@@ -1,50 +1,164 @@
+use cfg_if::cfg_if;
 use core::fmt::Debug;
-use std::{
-    cell::RefCell,
-    ops::{Deref, DerefMut},
-    rc::Rc,
-    sync::{Arc, Mutex},
+use std::ops::{Deref, DerefMut};
+
+use super::{
+    error::SchemeError,
+    heap::{self, Trace},
 };
 
+cfg_if! {
+    if #[cfg(feature = "thread_safe")] {
+        use std::sync::{Arc, Mutex, PoisonError, TryLockError};
+
+        /// The accessor of every value of the interpreter, which can be shared between threads.
+        pub type DefaultAccessor<T> = ThreadSafeAccessor<T>;
+        /// Shares what cannot be changed, such as lambdas and compiled code.
+        pub type Shared<T> = Arc<T>;
+        pub type WeakShared<T> = std::sync::Weak<T>;
+    } else {
+        use std::{cell::RefCell, rc::Rc};
+
+        use super::error::ErrorKind;
+
+        /// The accessor of every value of the interpreter, which stays on the thread that
+        /// created it and so does without atomic reference counts and locks.
+        pub type DefaultAccessor<T> = BaseAccessor<T>;
+        /// Shares what cannot be changed, such as lambdas and compiled code.
+        pub type Shared<T> = Rc<T>;
+        pub type WeakShared<T> = std::rc::Weak<T>;
+    }
+}
+
 pub trait Accessor<T> {
     fn new(src: T) -> Self;
-    fn access(&self) -> impl Deref<Target = T>;
-    fn access_mut(&self) -> impl DerefMut<Target = T>;
-    fn replace(&self, src: T) -> T;
+    /// Gives access to the value, failing with `ErrorKind::InUse` if it is being changed. With
+    /// the `thread_safe` feature, this rather waits until no other access holds the value, so
+    /// accessing a value again while it is being changed on the same thread never returns.
+    fn access(&self) -> Result<impl Deref<Target = T>, SchemeError>;
+    /// Gives access to change the value, failing with `ErrorKind::InUse` if it is in use. With
+    /// the `thread_safe` feature, this waits like `access` does.
+    fn access_mut(&self) -> Result<impl DerefMut<Target = T>, SchemeError>;
+    /// Like `access`, but returns `None` instead of waiting when the value is already in use,
+    /// as happens when a self-referencing value is traversed.
+    fn try_access(&self) -> Option<impl Deref<Target = T>>;
+    /// Whether both accessors give access to the same value.
+    fn ptr_eq(&self, other: &Self) -> bool;
+    /// The address of the value, which identifies it as long as it is alive.
+    fn as_ptr(&self) -> *const ();
 }
 
+#[cfg(not(feature = "thread_safe"))]
+fn in_use() -> SchemeError {
+    SchemeError::new(ErrorKind::InUse, "value accessed while it is being changed")
+}
+
+#[cfg(not(feature = "thread_safe"))]
 #[derive(Clone, Debug)]
-pub struct BaseAccessor<T>(Rc<RefCell<T>>);
+pub struct BaseAccessor<T: Trace>(Rc<RefCell<T>>);
 
+#[cfg(feature = "thread_safe")]
 #[derive(Clone, Debug)]
-pub struct ThreadSafeAccessor<T>(Arc<Mutex<T>>);
+pub struct ThreadSafeAccessor<T: Trace>(Arc<Mutex<T>>);
+
+// The last accessor hands what the value holds to `heap::release`, as dropping it right away
+// would recurse through every value it alone holds, such as a long chain of closures
+#[cfg(not(feature = "thread_safe"))]
+impl<T: Trace> Drop for BaseAccessor<T> {
+    fn drop(&mut self) {
+        if Rc::strong_count(&self.0) == 1 {
+            if let Ok(mut value) = self.0.try_borrow_mut() {
+                heap::release(&mut *value);
+            }
+        }
+    }
+}
 
-impl<T> Accessor<T> for BaseAccessor<T> {
+#[cfg(feature = "thread_safe")]
+impl<T: Trace> Drop for ThreadSafeAccessor<T> {
+    fn drop(&mut self) {
+        if Arc::strong_count(&self.0) == 1 {
+            if let Ok(mut value) = self.0.try_lock() {
+                heap::release(&mut *value);
+            }
+        }
+    }
+}
+
+// Values are allocated on the heap, so that cycles between them can be collected
+#[cfg(not(feature = "thread_safe"))]
+impl<T: Trace + 'static> Accessor<T> for BaseAccessor<T> {
     fn new(src: T) -> Self {
-        Self(Rc::new(RefCell::new(src)))
+        let value = Rc::new(RefCell::new(src));
+        heap::track(Rc::downgrade(&value) as _);
+        Self(value)
+    }
+    fn access(&self) -> Result<impl Deref<Target = T>, SchemeError> {
+        self.0.try_borrow().map_err(|_| in_use())
     }
-    fn access(&self) -> impl Deref<Target = T> {
-        self.0.try_borrow().unwrap()
+    fn access_mut(&self) -> Result<impl DerefMut<Target = T>, SchemeError> {
+        self.0.try_borrow_mut().map_err(|_| in_use())
     }
-    fn access_mut(&self) -> impl DerefMut<Target = T> {
-        self.0.try_borrow_mut().unwrap()
+    // Borrowing mutably fails whenever the value is in use, as locking a mutex would
+    fn try_access(&self) -> Option<impl Deref<Target = T>> {
+        self.0.try_borrow_mut().ok()
     }
-    fn replace(&self, src: T) -> T {
-        std::mem::replace(&mut *self.0.try_borrow_mut().unwrap(), src)
+    fn ptr_eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+    fn as_ptr(&self) -> *const () {
+        Rc::as_ptr(&self.0) as *const ()
     }
 }
 
-impl<T> Accessor<T> for ThreadSafeAccessor<T> {
+#[cfg(feature = "thread_safe")]
+impl<T: Trace + 'static> Accessor<T> for ThreadSafeAccessor<T> {
     fn new(src: T) -> Self {
-        Self(Arc::new(Mutex::new(src)))
+        let value = Arc::new(Mutex::new(src));
+        heap::track(Arc::downgrade(&value) as _);
+        Self(value)
+    }
+    // A panic while the lock was held leaves the value usable, so poisoning is ignored, and
+    // another thread holding the lock is waited for rather than failed on
+    fn access(&self) -> Result<impl Deref<Target = T>, SchemeError> {
+        Ok(self.0.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+    fn access_mut(&self) -> Result<impl DerefMut<Target = T>, SchemeError> {
+        Ok(self.0.lock().unwrap_or_else(PoisonError::into_inner))
     }
-    fn access(&self) -> impl Deref<Target = T> {
-        self.0.try_lock().unwrap()
+    fn try_access(&self) -> Option<impl Deref<Target = T>> {
+        match self.0.try_lock() {
+            Ok(guard) => Some(guard),
+            Err(TryLockError::Poisoned(poisoned)) => Some(poisoned.into_inner()),
+            Err(TryLockError::WouldBlock) => None,
+        }
     }
-    fn access_mut(&self) -> impl DerefMut<Target = T> {
-        self.0.try_lock().unwrap()
+    fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
     }
-    fn replace(&self, src: T) -> T {
-        std::mem::replace(&mut *self.0.try_lock().unwrap(), src)
+    fn as_ptr(&self) -> *const () {
+        Arc::as_ptr(&self.0) as *const ()
+    }
+}
+
+#[cfg(all(test, not(feature = "thread_safe")))]
+mod tests {
+    use super::*;
+    use crate::core::s_expression::SExpr;
+
+    #[test]
+    fn reentrant_access_fails() {
+        let pair = SExpr::cons(SExpr::from(1), SExpr::from(3));
+        let SExpr::Pair(ref accessor) = pair else { unreachable!() };
+
+        let mut cons = accessor.access_mut().unwrap();
+        assert_eq!(accessor.access().err().map(|e| e.kind()), Some(ErrorKind::InUse));
+        assert_eq!(accessor.access_mut().err().map(|e| e.kind()), Some(ErrorKind::InUse));
+        assert_eq!(pair.to_string(), "...");
+        assert!(pair.car().is_none());
+
+        cons.car = SExpr::from(2);
+        drop(cons);
+        assert_eq!(pair.to_string(), "(2 . 3)");
     }
 }
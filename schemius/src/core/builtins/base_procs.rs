@@ -1,65 +1,355 @@
+use std::cell::RefCell;
+
 use super::{
+    apply,
+    constants::{features, libraries},
+    eval, heap,
     s_list::SList,
-    s_procedure::{ProcedureArgs, ProcedureEnv, ProcedureOutput},
-    Accessor, ListImplementation, SExpr, SchemeEnvironment, SchemeList,
+    s_number::{NativeInt, SNumber},
+    s_procedure::{Procedure, ProcedureArgs, ProcedureEnv, ProcedureOutput},
+    settings, Accessor, EnvAccessor, Environment, ErrorKind, ListImplementation, SExpr,
+    SchemeEnvironment, SchemeError, SpecialForm, Symbol,
 };
 
-pub fn r_apply(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
-    if args.s_len() != 2 {
-        return Err(format!("Exception in apply: expected 2 arguments, found {}", args.s_len()));
+/// Splits the arguments of `(apply proc arg... list)` into the procedure and the arguments it is
+/// called with.
+pub fn apply_arguments(args: ProcedureArgs) -> Result<(SExpr, ListImplementation), SchemeError> {
+    let args: Vec<SExpr> = args.into_iter().collect();
+    if args.len() < 2 {
+        return Err(SchemeError::arity("apply", "at least 2 arguments", args.len()));
     }
 
-    let proc = args.s_car().unwrap();
-    let args = args.s_cadr().unwrap();
-    let mut to_be_evaluated = ListImplementation::new();
-    to_be_evaluated.push(proc.clone());
+    let (last, leading) = args.split_last().unwrap();
+    let (procedure, leading) = leading.split_first().unwrap();
+    let mut arguments = ListImplementation::from_iter(leading.iter().cloned());
 
-    match args {
-        SExpr::List(list) => list.access().iter().for_each(|arg| to_be_evaluated.push(arg.clone())),
-        other => return Err(format!("Exception in #<apply>: {} is not a list", other)),
+    match last.as_list() {
+        Ok(list) => list.into_iter().for_each(|arg| arguments.push(arg)),
+        Err(_) => return Err(SchemeError::wrong_type("apply", last, "a list")),
     }
 
-    Ok(SExpr::List(SchemeList::new(to_be_evaluated)))
+    Ok((procedure.clone(), arguments))
+}
+
+pub fn r_apply(args: ProcedureArgs, env: ProcedureEnv) -> ProcedureOutput {
+    let (procedure, args) = apply_arguments(args)?;
+    apply(&procedure, args, env)
 }
 
 pub fn r_eval(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
-    if args.s_len() != 1 {
-        return Err(format!("Exception in eval: expected 1 argument, found {}", args.s_len()));
+    match args.s_len() {
+        1 => Ok(args.s_car().unwrap().clone()),
+        2 => match args.s_cadr().unwrap() {
+            SExpr::Environment(_) => Ok(args.s_car().unwrap().clone()),
+            other => Err(SchemeError::wrong_type("eval", other, "an environment")),
+        },
+        n => Err(SchemeError::arity("eval", "1 or 2 arguments", n)),
     }
+}
 
-    Ok(args.s_car().unwrap().clone())
+/// The bindings of the standard library `name`, unless it is not supported.
+pub fn standard_library(
+    name: &str, env: ProcedureEnv,
+) -> Result<Option<Vec<(String, SExpr)>>, SchemeError> {
+    match libraries::SUPPORTED.iter().find(|(library, _)| *library == name) {
+        Some((_, names)) => Ok(Some(library_bindings(names, env)?)),
+        None => Ok(None),
+    }
 }
 
-pub fn r_display(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
+/// The values of the builtins `names`, or of the variables defined in the root environment of
+/// `env` for those that are not builtins.
+fn library_bindings(
+    names: &[&[&str]], env: ProcedureEnv,
+) -> Result<Vec<(String, SExpr)>, SchemeError> {
+    let standard = Environment::default();
+    let root = Environment::get_root(env)?;
+    let root = root.access()?;
+
+    Ok(names
+        .iter()
+        .flat_map(|names| names.iter())
+        .filter_map(|name| {
+            let symbol = Symbol::new(name);
+            let value = standard.get(&symbol).or_else(|| root.get(&symbol))?;
+            Some((name.to_string(), value))
+        })
+        .collect())
+}
+
+/// An environment holding the bindings of the libraries `names`, evaluated with the settings of
+/// `env`.
+fn library_environment(names: &[&[&str]], env: ProcedureEnv) -> Result<ProcedureEnv, SchemeError> {
+    let mut library_env = Environment::new();
+    library_env.set_settings(settings(&env)?);
+
+    for (name, value) in library_bindings(names, env)? {
+        library_env.define(&Symbol::new(&name), &value)?;
+    }
+
+    Ok(EnvAccessor::new(library_env))
+}
+
+pub fn r_environment(args: ProcedureArgs, env: ProcedureEnv) -> ProcedureOutput {
+    let mut names: Vec<&[&str]> = vec![];
+
+    for arg in args.iter() {
+        let name = arg.to_string();
+        match libraries::SUPPORTED.iter().find(|(library, _)| *library == name) {
+            Some((_, bindings)) => names.extend(bindings.iter()),
+            None => {
+                return Err(SchemeError::new(
+                    ErrorKind::WrongType,
+                    format!("unknown library {}", name),
+                )
+                .in_procedure("environment")
+                .with_irritants([arg.clone()]))
+            }
+        }
+    }
+
+    Ok(SExpr::Environment(library_environment(&names, env)?))
+}
+
+pub fn r_interaction_environment(args: ProcedureArgs, env: ProcedureEnv) -> ProcedureOutput {
+    if !args.is_empty() {
+        return Err(SchemeError::arity("interaction-environment", "0 arguments", args.len()));
+    }
+
+    Ok(SExpr::Environment(Environment::get_root(env)?))
+}
+
+fn r5rs_version(args: &ProcedureArgs, name: &str) -> Result<(), SchemeError> {
     if args.s_len() != 1 {
-        return Err(format!("Exception in display: expected 1 argument, found {}", args.s_len()));
+        return Err(SchemeError::arity(name, "1 argument", args.s_len()));
     }
 
     match args.s_car().unwrap() {
-        SExpr::String(string) => Ok(SExpr::Symbol(string.access().to_string())), // Avoids double quotes
-        expr => Ok(SExpr::Symbol(format!("{}", expr))),
+        SExpr::Number(SNumber::Int(5)) => Ok(()),
+        other => {
+            Err(SchemeError::new(ErrorKind::OutOfRange, format!("unsupported version {}", other))
+                .in_procedure(name)
+                .with_irritants([other.clone()]))
+        }
+    }
+}
+
+pub fn r_scheme_report_environment(args: ProcedureArgs, env: ProcedureEnv) -> ProcedureOutput {
+    r5rs_version(&args, "scheme-report-environment")?;
+
+    let names = libraries::SUPPORTED.iter().find(|(library, _)| *library == "(scheme r5rs)");
+    Ok(SExpr::Environment(library_environment(names.unwrap().1, env)?))
+}
+
+pub fn r_null_environment(args: ProcedureArgs, env: ProcedureEnv) -> ProcedureOutput {
+    r5rs_version(&args, "null-environment")?;
+
+    let standard = Environment::default();
+    let keywords: Vec<&str> = standard
+        .get_bindings()
+        .into_iter()
+        .filter(|(_, value)| matches!(value, SExpr::Keyword(_)))
+        .map(|(name, _)| name.as_str())
+        .collect();
+
+    Ok(SExpr::Environment(library_environment(&[&keywords], env)?))
+}
+
+thread_local! {
+    static WINDERS: RefCell<Vec<SExpr>> = const { RefCell::new(vec![]) };
+}
+
+fn call_thunk(thunk: &SExpr, env: ProcedureEnv) -> ProcedureOutput {
+    eval(&SExpr::list([thunk.clone()]), env)
+}
+
+/// Evaluates to its first argument, the others being evaluated for their effects only.
+fn r_first(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
+    Ok(args.s_car().cloned().unwrap_or(SExpr::Unspecified))
+}
+
+fn r_push_winder(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
+    let after = args.s_car().cloned().unwrap_or(SExpr::Unspecified);
+    WINDERS.with(|winders| winders.borrow_mut().push(after));
+    Ok(SExpr::Unspecified)
+}
+
+fn r_pop_winder(_: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
+    WINDERS.with(|winders| winders.borrow_mut().pop());
+    Ok(SExpr::Unspecified)
+}
+
+/// The expression running the thunks given to `dynamic-wind`, which the evaluator runs itself
+/// so that nesting dynamic-winds does not nest evaluations on the native stack.
+pub fn r_dynamic_wind(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
+    if args.s_len() != 3 {
+        return Err(SchemeError::arity("dynamic-wind", "3 arguments", args.s_len()));
+    }
+
+    if let Some(other) = args.iter().find(|arg| !arg.is_procedure().unwrap()) {
+        return Err(SchemeError::wrong_type("dynamic-wind", other, "a procedure"));
+    }
+
+    let before = args.s_car().unwrap();
+    let thunk = args.s_cadr().unwrap();
+    let after = args.s_ref(2).unwrap();
+
+    let primitive = |primitive| SExpr::Procedure(Procedure::Primitive(primitive));
+    let call = |procedure: &SExpr| SExpr::list([procedure.clone()]);
+
+    // Arguments are evaluated in order, so `after` is called once the thunk has returned
+    let wound = SExpr::list([
+        SExpr::Keyword(SpecialForm::BEGIN),
+        call(before),
+        SExpr::list([primitive(r_push_winder), after.clone()]),
+        call(thunk),
+    ]);
+    Ok(SExpr::list([primitive(r_first), wound, call(&primitive(r_pop_winder)), call(after)]))
+}
+
+/// How many dynamic-winds are running their thunk.
+pub fn wind_depth() -> usize {
+    WINDERS.with(|winders| winders.borrow().len())
+}
+
+/// Runs the after thunks of the dynamic-winds entered since there were `depth` of them, innermost
+/// first, as `error` leaves their extent. An after thunk failing replaces the error.
+pub fn unwind_to(depth: usize, error: SchemeError, env: ProcedureEnv) -> SchemeError {
+    let mut error = error;
+
+    while wind_depth() > depth {
+        let after = WINDERS.with(|winders| winders.borrow_mut().pop()).unwrap();
+        if let Err(e) = call_thunk(&after, env.clone()) {
+            error = e;
+        }
     }
+
+    error
 }
 
-pub fn r_exit(_: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
-    std::process::exit(0)
+/// Runs the after thunks of every active dynamic-wind, innermost first.
+pub fn unwind_all(env: ProcedureEnv) {
+    while let Some(after) = WINDERS.with(|winders| winders.borrow_mut().pop()) {
+        let _ = call_thunk(&after, env.clone());
+    }
+}
+
+/// Signals an error with a message and the irritants it is about, which are written after it.
+pub fn r_error(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
+    let Some(message) = args.s_car() else {
+        return Err(SchemeError::arity("error", "at least 1 argument", 0));
+    };
+    let message = match message {
+        SExpr::String(string) => string.access()?.to_string(),
+        other => return Err(SchemeError::wrong_type("error", other, "a string")),
+    };
+
+    let irritants: Vec<SExpr> = args.iter().skip(1).cloned().collect();
+    let message =
+        irritants.iter().fold(message, |message, irritant| format!("{} {}", message, irritant));
+
+    Err(SchemeError::new(ErrorKind::User, message).with_irritants(irritants))
+}
+
+pub fn r_display(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
+    if args.s_len() != 1 {
+        return Err(SchemeError::arity("display", "1 argument", args.s_len()));
+    }
+
+    match args.s_car().unwrap() {
+        // Uninterned, so that printing does not fill the symbol table
+        SExpr::String(string) => Ok(SExpr::Symbol(Symbol::uninterned(&string.access()?))), // Avoids double quotes
+        expr => Ok(SExpr::Symbol(Symbol::uninterned(&expr.to_string()))),
+    }
 }
 
 pub fn r_environment_bindings(args: ProcedureArgs, env: ProcedureEnv) -> ProcedureOutput {
     if !args.is_empty() {
-        return Err(format!(
-            "Exception in environment-bindings: expected 0 arguments, found {}",
-            args.len()
-        ));
+        return Err(SchemeError::arity("environment-bindings", "0 arguments", args.len()));
     }
 
-    let env_guard = env.access();
+    let env_guard = env.access()?;
     let mut bindings = env_guard.get_bindings().clone();
     bindings.sort_by(|a, b| a.0.cmp(b.0));
 
-    let mut output: String = "".to_owned();
-    bindings.iter().for_each(|b| output.push_str(format!("({}, {})\n", b.0, b.1).as_str()));
-    output.remove(output.len() - 1);
+    let output: Vec<String> = bindings.iter().map(|b| format!("({}, {})", b.0, b.1)).collect();
+
+    Ok(SExpr::Symbol(Symbol::uninterned(&output.join("\n"))))
+}
+
+pub fn r_gc(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
+    if !args.is_empty() {
+        return Err(SchemeError::arity("gc", "0 arguments", args.len()));
+    }
+
+    Ok(SExpr::from(heap::collect() as NativeInt))
+}
+
+/// The number of objects of each kind alive on the heap, followed by how many collections ran
+/// and how many objects they freed, as an association list.
+pub fn r_heap_statistics(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
+    if !args.is_empty() {
+        return Err(SchemeError::arity("heap-statistics", "0 arguments", args.len()));
+    }
+
+    let statistics = heap::statistics();
+    let live = statistics.live.iter().map(|(kind, count)| (kind.name(), *count));
+    let totals = [("collections", statistics.collections), ("freed", statistics.freed)];
+
+    Ok(SExpr::list(
+        live.chain(totals)
+            .map(|(name, count)| SExpr::cons(SExpr::symbol(name), SExpr::from(count as NativeInt))),
+    ))
+}
+
+pub fn scheme_features() -> Vec<String> {
+    let mut output = vec![
+        features::R7RS,
+        features::EXACT_CLOSED,
+        features::RATIOS,
+        features::FULL_UNICODE,
+        features::SCHEMIUS,
+    ];
+
+    output.push(if cfg!(feature = "i32") {
+        features::I32
+    } else if cfg!(feature = "i128") && !cfg!(feature = "i64") {
+        features::I128
+    } else {
+        features::I64
+    });
+
+    output.push(if cfg!(feature = "f32") { features::F32 } else { features::F64 });
+
+    if cfg!(feature = "true_list") {
+        output.push(features::TRUE_LIST);
+    }
+
+    if cfg!(feature = "thread_safe") {
+        output.push(features::THREAD_SAFE);
+    }
+
+    if cfg!(feature = "bigfloat") {
+        output.push(features::BIG_FLOAT);
+    }
+
+    if cfg!(unix) {
+        output.push(features::UNIX);
+    } else if cfg!(windows) {
+        output.push(features::WINDOWS);
+    }
+
+    let mut output: Vec<String> = output.into_iter().map(String::from).collect();
+    output.push(std::env::consts::OS.to_string());
+    output.push(std::env::consts::ARCH.replace('_', "-"));
+
+    output
+}
+
+pub fn r_features(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
+    if !args.is_empty() {
+        return Err(SchemeError::arity("features", "0 arguments", args.len()));
+    }
 
-    Ok(SExpr::Symbol(output))
+    Ok(SExpr::list(scheme_features().iter().map(|feature| SExpr::symbol(feature))))
 }
@@ -1,7 +1,7 @@
 use super::{
     s_list::SList,
     s_procedure::{ProcedureArgs, ProcedureEnv, ProcedureOutput},
-    SExpr,
+    SExpr, SchemeError,
 };
 
 macro_rules! fn_is {
@@ -9,7 +9,7 @@ macro_rules! fn_is {
         $(
             pub fn $fn(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
                 if args.len() != 1 {
-                    return Err(format!("Exception in {}: expected 1 argument, found {}", $name, args.len()));
+                    return Err(SchemeError::arity($name, "1 argument", args.len()));
                 }
 
                 match args.s_car().unwrap().$source_fn() {
@@ -40,3 +40,21 @@ fn_is! {
     r_is_vector, is_vector, "vector?"
     r_is_zero, is_zero, "zero?"
 }
+
+pub fn r_not(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
+    if args.len() != 1 {
+        return Err(SchemeError::arity("not", "1 argument", args.len()));
+    }
+
+    Ok(SExpr::Boolean(matches!(args.s_car().unwrap(), SExpr::Boolean(false))))
+}
+
+/// Serves both `eq?` and `eqv?`, which only differ on values this interpreter compares the same
+/// way either way.
+pub fn r_eqv(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
+    if args.s_len() != 2 {
+        return Err(SchemeError::arity("eqv?", "2 arguments", args.s_len()));
+    }
+
+    Ok(SExpr::Boolean(args.s_car().unwrap().is_eqv(args.s_cadr().unwrap())))
+}
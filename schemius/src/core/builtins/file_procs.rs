@@ -0,0 +1,91 @@
+use std::{
+    cell::RefCell,
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    eval,
+    reader::{Datum, Reader},
+    s_list::SList,
+    s_procedure::{ProcedureArgs, ProcedureEnv, ProcedureOutput},
+    settings, Accessor, Environment, ErrorKind, SExpr, SchemeEnvironment, SchemeError,
+};
+
+thread_local! {
+    static SOURCE_FILES: RefCell<Vec<PathBuf>> = const { RefCell::new(vec![]) };
+}
+
+/// Resolves a path against the directory of the file currently being loaded, if any.
+pub fn resolve_source_path(path: &str) -> PathBuf {
+    let path = Path::new(path);
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+
+    SOURCE_FILES.with(|files| match files.borrow().last().and_then(|file| file.parent()) {
+        Some(dir) => dir.join(path),
+        None => path.to_path_buf(),
+    })
+}
+
+/// Runs `action` with `path` registered as the file currently being loaded.
+pub fn with_source_file<T>(path: &Path, action: impl FnOnce() -> T) -> T {
+    SOURCE_FILES.with(|files| files.borrow_mut().push(path.to_path_buf()));
+    let result = action();
+    SOURCE_FILES.with(|files| files.borrow_mut().pop());
+
+    result
+}
+
+/// Reads the data of a file, with the float precision of the interpreter `env` belongs to.
+pub fn read_source_file(
+    path: &Path, caller: &str, env: &ProcedureEnv, fold_case: bool,
+) -> Result<Vec<Datum>, SchemeError> {
+    let source = match read_to_string(path) {
+        Ok(source) => source,
+        Err(e) => {
+            return Err(SchemeError::new(
+                ErrorKind::Io,
+                format!("could not read file {}: {}", path.display(), e),
+            )
+            .in_procedure(caller))
+        }
+    };
+
+    let reader =
+        Reader::with_file(&source, path).with_float_precision(settings(env)?.float_precision());
+    match fold_case {
+        true => Ok(reader.with_fold_case().collect::<Result<_, _>>()?),
+        false => Ok(reader.collect::<Result<_, _>>()?),
+    }
+}
+
+/// Evaluates the contents of a file in the given environment, or else in the interaction
+/// environment.
+pub fn r_load(args: ProcedureArgs, env: ProcedureEnv) -> ProcedureOutput {
+    if args.is_empty() || args.s_len() > 2 {
+        return Err(SchemeError::arity("load", "1 or 2 arguments", args.s_len()));
+    }
+
+    let path = match args.s_car().unwrap() {
+        SExpr::String(path) => resolve_source_path(&path.access()?),
+        other => return Err(SchemeError::wrong_type("load", other, "a string")),
+    };
+
+    let load_env = match args.s_cadr() {
+        Some(SExpr::Environment(load_env)) => load_env.clone(),
+        Some(other) => return Err(SchemeError::wrong_type("load", other, "an environment")),
+        None => Environment::get_root(env)?,
+    };
+
+    let expressions = read_source_file(&path, "load", &load_env, false)?;
+
+    with_source_file(&path, || {
+        for datum in expressions.iter() {
+            eval(&datum.expr, load_env.clone()).map_err(|e| e.with_span(&datum.span))?;
+        }
+
+        Ok(SExpr::Unspecified)
+    })
+}
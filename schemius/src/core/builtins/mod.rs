@@ -1,41 +1,77 @@
-use super::{accessor::*, environment::*, evaluator::*, s_expression::*};
+use super::{
+    accessor::*, compiler, constants, environment::*, error::*, evaluator::*, heap, reader,
+    s_expression::*,
+};
 
 mod base_procs;
 mod boolean_procs;
+mod file_procs;
 mod list_procs;
 mod number_procs;
+mod process_procs;
 mod special_forms;
 mod string_procs;
+mod symbol_procs;
+mod time_procs;
 
 use base_procs::*;
 use boolean_procs::*;
+use file_procs::*;
 use list_procs::*;
 use number_procs::*;
+use process_procs::*;
 use special_forms::*;
 use string_procs::*;
+use symbol_procs::*;
+use time_procs::*;
+
+pub use base_procs::{apply_arguments, unwind_to, wind_depth};
+pub use file_procs::with_source_file;
 
 pub struct Primitive;
 pub struct SpecialForm;
 
 impl Primitive {
+    pub const COMMAND_LINE: ProcedureSignature = r_command_line;
+    pub const DYNAMIC_WIND: ProcedureSignature = r_dynamic_wind;
+    pub const EMERGENCY_EXIT: ProcedureSignature = r_emergency_exit;
+    pub const ENVIRONMENT: ProcedureSignature = r_environment;
     pub const EXIT: ProcedureSignature = r_exit;
+    pub const FEATURES: ProcedureSignature = r_features;
     pub const SUM: ProcedureSignature = r_sum;
     pub const DIFF: ProcedureSignature = r_diff;
     pub const PROD: ProcedureSignature = r_prod;
     pub const QUOT: ProcedureSignature = r_quot;
     pub const EQUAL: ProcedureSignature = r_equal;
+    pub const EQV: ProcedureSignature = r_eqv;
     pub const GT: ProcedureSignature = r_gt;
     pub const GE: ProcedureSignature = r_ge;
     pub const LT: ProcedureSignature = r_lt;
     pub const LE: ProcedureSignature = r_le;
     pub const EVAL: ProcedureSignature = r_eval;
+    pub const EXACT: ProcedureSignature = r_exact;
+    pub const INEXACT: ProcedureSignature = r_inexact;
+    pub const EXP: ProcedureSignature = r_exp;
+    pub const LOG: ProcedureSignature = r_log;
+    pub const SIN: ProcedureSignature = r_sin;
+    pub const COS: ProcedureSignature = r_cos;
+    pub const TAN: ProcedureSignature = r_tan;
+    pub const ASIN: ProcedureSignature = r_asin;
+    pub const ACOS: ProcedureSignature = r_acos;
+    pub const ATAN: ProcedureSignature = r_atan;
+    pub const SQRT: ProcedureSignature = r_sqrt;
     pub const APPLY: ProcedureSignature = r_apply;
     pub const CAR: ProcedureSignature = r_car;
     pub const CDR: ProcedureSignature = r_cdr;
     pub const CONS: ProcedureSignature = r_cons;
+    pub const CURRENT_JIFFY: ProcedureSignature = r_current_jiffy;
+    pub const CURRENT_SECOND: ProcedureSignature = r_current_second;
     pub const LIST: ProcedureSignature = r_list;
     pub const SET_CAR: ProcedureSignature = r_set_car;
+    pub const SET_CDR: ProcedureSignature = r_set_cdr;
     pub const DISPLAY: ProcedureSignature = r_display;
+    pub const ERROR: ProcedureSignature = r_error;
+    pub const INTERACTION_ENVIRONMENT: ProcedureSignature = r_interaction_environment;
     pub const IS_BOOLEAN: ProcedureSignature = r_is_boolean;
     pub const IS_CHAR: ProcedureSignature = r_is_char;
     pub const IS_COMPLEX: ProcedureSignature = r_is_complex;
@@ -54,12 +90,22 @@ impl Primitive {
     pub const IS_SYMBOL: ProcedureSignature = r_is_symbol;
     pub const IS_VECTOR: ProcedureSignature = r_is_vector;
     pub const IS_ZERO: ProcedureSignature = r_is_zero;
+    pub const NOT: ProcedureSignature = r_not;
+    pub const JIFFIES_PER_SECOND: ProcedureSignature = r_jiffies_per_second;
     pub const ENVIRONMENT_BINDINGS: ProcedureSignature = r_environment_bindings;
+    pub const GC: ProcedureSignature = r_gc;
+    pub const GENSYM: ProcedureSignature = r_gensym;
+    pub const GET_ENVIRONMENT_VARIABLE: ProcedureSignature = r_get_environment_variable;
+    pub const GET_ENVIRONMENT_VARIABLES: ProcedureSignature = r_get_environment_variables;
+    pub const HEAP_STATISTICS: ProcedureSignature = r_heap_statistics;
     pub const APPEND: ProcedureSignature = r_append;
     pub const LENGTH: ProcedureSignature = r_length;
     pub const LIST_REF: ProcedureSignature = r_list_ref;
     pub const LIST_SPLICE: ProcedureSignature = r_list_splice;
     pub const LIST_TAIL: ProcedureSignature = r_list_tail;
+    pub const LOAD: ProcedureSignature = r_load;
+    pub const NULL_ENVIRONMENT: ProcedureSignature = r_null_environment;
+    pub const SCHEME_REPORT_ENVIRONMENT: ProcedureSignature = r_scheme_report_environment;
     pub const REVERSE: ProcedureSignature = r_reverse;
     pub const MAKE_STRING: ProcedureSignature = r_make_string;
     pub const STRING: ProcedureSignature = r_string;
@@ -69,21 +115,48 @@ impl Primitive {
     pub const STRING_REF: ProcedureSignature = r_string_ref;
     pub const STRING_SET: ProcedureSignature = r_string_set;
     pub const STRING_UPCASE: ProcedureSignature = r_string_upcase;
+    pub const STRING_TO_SYMBOL: ProcedureSignature = r_string_to_symbol;
+    pub const SYMBOL_EQUAL: ProcedureSignature = r_symbol_equal;
+    pub const SYMBOL_TO_STRING: ProcedureSignature = r_symbol_to_string;
 }
 
 impl SpecialForm {
     pub const BEGIN: SpecialFormSignature = r_begin;
+    pub const CASE: SpecialFormSignature = r_case;
     pub const COND: SpecialFormSignature = r_cond;
+    pub const COND_EXPAND: SpecialFormSignature = r_cond_expand;
     pub const DEFINE: SpecialFormSignature = r_define;
+    pub const DEFINE_LIBRARY: SpecialFormSignature = r_define_library;
+    pub const DO: SpecialFormSignature = r_do;
     pub const IF: SpecialFormSignature = r_if;
+    pub const IMPORT: SpecialFormSignature = r_import;
+    pub const INCLUDE: SpecialFormSignature = r_include;
+    pub const INCLUDE_CI: SpecialFormSignature = r_include_ci;
     pub const LAMBDA: SpecialFormSignature = r_lambda;
     pub const LET: SpecialFormSignature = r_let;
     pub const LET_STAR: SpecialFormSignature = r_let_star;
-    pub const NOT: SpecialFormSignature = r_not;
+    pub const LETREC: SpecialFormSignature = r_letrec;
+    pub const LETREC_STAR: SpecialFormSignature = r_letrec_star;
     pub const QUOTE: SpecialFormSignature = r_quote;
     pub const QUASIQUOTE: SpecialFormSignature = r_quasiquote;
     pub const SET: SpecialFormSignature = r_set;
     pub const TIME: SpecialFormSignature = r_time;
+    pub const UNCHANGED: SpecialFormSignature = r_unchanged;
+    pub const UNLESS: SpecialFormSignature = r_unless;
+    pub const WHEN: SpecialFormSignature = r_when;
     pub const AND: SpecialFormSignature = r_and;
     pub const OR: SpecialFormSignature = r_or;
 }
+
+/// Reads `arg` as a position within a list or string, which must be an exact non-negative
+/// integer.
+fn index_argument(procedure: &str, arg: &SExpr) -> Result<usize, SchemeError> {
+    match arg {
+        SExpr::Number(SNumber::Int(index)) => usize::try_from(*index).map_err(|_| {
+            SchemeError::new(ErrorKind::OutOfRange, format!("invalid index {}", index))
+                .in_procedure(procedure)
+                .with_irritants([arg.clone()])
+        }),
+        other => Err(SchemeError::wrong_type(procedure, other, "an exact integer")),
+    }
+}
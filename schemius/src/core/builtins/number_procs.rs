@@ -1,7 +1,7 @@
 use super::{
     s_list::SList,
     s_procedure::{ProcedureArgs, ProcedureEnv, ProcedureOutput},
-    SExpr, SNumber,
+    settings, ErrorKind, SExpr, SNumber, SchemeError,
 };
 
 macro_rules! fn_compute_sum_prod {
@@ -16,7 +16,7 @@ macro_rules! fn_compute_sum_prod {
                     for arg in args {
                         match arg {
                             SExpr::Number(n) => res = &res $op &n,
-                            num => return Err(format!("Exception in {}: #<{}> is not a number", stringify!($op), num)),
+                            num => return Err(SchemeError::wrong_type(stringify!($op), &num, "a number")),
                         }
                     }
 
@@ -27,26 +27,41 @@ macro_rules! fn_compute_sum_prod {
     )*}
 }
 
+/// Exact numbers have no representation for the quotient of a division by an exact zero.
+fn check_divisor(operator: &str, dividend: &SNumber, divisor: &SNumber) -> Result<(), SchemeError> {
+    if operator == "/" && dividend.is_exact() && divisor.is_exact() && divisor.is_zero() {
+        return Err(SchemeError::new(ErrorKind::OutOfRange, "division by zero")
+            .in_procedure(operator)
+            .with_irritants([SExpr::Number(dividend.clone()), SExpr::Number(divisor.clone())]));
+    }
+
+    Ok(())
+}
+
 macro_rules! fn_compute_diff_quot {
     ($($fn:ident: $op:tt, $neutral:literal)*) => {
     $(
         pub fn $fn(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
             match args.len() {
-                0 => Err(String::from("Exception: too few arguments")),
+                0 => Err(SchemeError::arity(stringify!($op), "at least 1 argument", 0)),
                 _ => {
                     let mut res = match args.s_car().unwrap() {
                             SExpr::Number(num) => num.clone(),
-                            num => return Err(format!("Exception in {}: #<{}> is not a number", stringify!($op), num)),
+                            num => return Err(SchemeError::wrong_type(stringify!($op), &num, "a number")),
                     };
 
                     if args.len() > 1 {
                         for arg in &args.s_cdr().unwrap() {
                             match arg {
-                                SExpr::Number(n) => res = &res $op &n,
-                                num => return Err(format!("Exception in {}: #<{}> is not a number", stringify!($op), num)),
+                                SExpr::Number(n) => {
+                                    check_divisor(stringify!($op), &res, n)?;
+                                    res = &res $op &n
+                                }
+                                num => return Err(SchemeError::wrong_type(stringify!($op), &num, "a number")),
                             }
                         }
                     } else {
+                        check_divisor(stringify!($op), &SNumber::Int($neutral), &res)?;
                         res = SNumber::Int($neutral) $op res;
                     }
 
@@ -57,6 +72,102 @@ macro_rules! fn_compute_diff_quot {
     )*}
 }
 
+pub fn r_exact(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
+    if args.len() != 1 {
+        return Err(SchemeError::arity("exact", "1 argument", args.len()));
+    }
+
+    match args.s_car().unwrap() {
+        SExpr::Number(number) => number.exact().map(SExpr::Number).ok_or_else(|| {
+            SchemeError::new(ErrorKind::OutOfRange, "no exact representation")
+                .in_procedure("exact")
+                .with_irritants([SExpr::Number(number.clone())])
+        }),
+        other => Err(SchemeError::wrong_type("exact", other, "a number")),
+    }
+}
+
+pub fn r_inexact(args: ProcedureArgs, env: ProcedureEnv) -> ProcedureOutput {
+    if args.len() != 1 {
+        return Err(SchemeError::arity("inexact", "1 argument", args.len()));
+    }
+
+    match args.s_car().unwrap() {
+        SExpr::Number(number) => {
+            Ok(SExpr::Number(number.inexact(settings(&env)?.float_precision())))
+        }
+        other => Err(SchemeError::wrong_type("inexact", other, "a number")),
+    }
+}
+
+macro_rules! fn_elementary {
+    ($($fn:ident: $method:ident, $name:literal)*) => {
+    $(
+        pub fn $fn(args: ProcedureArgs, env: ProcedureEnv) -> ProcedureOutput {
+            if args.len() != 1 {
+                return Err(SchemeError::arity($name, "1 argument", args.len()));
+            }
+
+            match args.s_car().unwrap() {
+                SExpr::Number(number) => {
+                    Ok(SExpr::Number(number.$method(settings(&env)?.float_precision())))
+                }
+                other => Err(SchemeError::wrong_type($name, other, "a number")),
+            }
+        }
+    )*}
+}
+
+fn_elementary! {
+    r_exp: exp, "exp"
+    r_sin: sin, "sin"
+    r_cos: cos, "cos"
+    r_tan: tan, "tan"
+    r_asin: asin, "asin"
+    r_acos: acos, "acos"
+    r_sqrt: sqrt, "sqrt"
+}
+
+/// The natural logarithm, or the logarithm in the base given as second argument.
+pub fn r_log(args: ProcedureArgs, env: ProcedureEnv) -> ProcedureOutput {
+    let precision = settings(&env)?.float_precision();
+    let numbers = args
+        .iter()
+        .map(|arg| match arg {
+            SExpr::Number(number) => Ok(number.log(precision)),
+            other => Err(SchemeError::wrong_type("log", other, "a number")),
+        })
+        .collect::<Result<Vec<SNumber>, SchemeError>>()?;
+
+    match numbers.as_slice() {
+        [z] => Ok(SExpr::Number(z.clone())),
+        [z, base] => Ok(SExpr::Number(z / base)),
+        _ => Err(SchemeError::arity("log", "1 or 2 arguments", args.len())),
+    }
+}
+
+/// The arctangent of a number, or the angle of the point given by a real x-coordinate as second
+/// argument and a real y-coordinate as first.
+pub fn r_atan(args: ProcedureArgs, env: ProcedureEnv) -> ProcedureOutput {
+    let precision = settings(&env)?.float_precision();
+    match args.len() {
+        1 => match args.s_car().unwrap() {
+            SExpr::Number(z) => Ok(SExpr::Number(z.atan(precision))),
+            other => Err(SchemeError::wrong_type("atan", other, "a number")),
+        },
+        2 => match (args.s_car().unwrap(), args.s_cdr().unwrap().s_car().unwrap()) {
+            (SExpr::Number(y), SExpr::Number(x)) if y.is_real() && x.is_real() => {
+                Ok(SExpr::Number(y.atan2(x, precision)))
+            }
+            (SExpr::Number(y), other) if y.is_real() => {
+                Err(SchemeError::wrong_type("atan", other, "a real number"))
+            }
+            (other, _) => Err(SchemeError::wrong_type("atan", other, "a real number")),
+        },
+        n => Err(SchemeError::arity("atan", "1 or 2 arguments", n)),
+    }
+}
+
 fn_compute_sum_prod! {
     r_sum: +, 0
     r_prod: *, 1
@@ -68,41 +179,33 @@ fn_compute_diff_quot! {
 }
 
 macro_rules! fn_compare {
-    ($($fn:ident: $op:tt)*) => {
+    ($($fn:ident: $op:tt, $is_valid:ident, $expected:literal)*) => {
         $(
             pub fn $fn(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
                 if args.len() < 2 {
-                    return Err(format!("Exception in {}: expected at least 2 arguments, found {}", stringify!($op), args.len()));
+                    return Err(SchemeError::arity(stringify!($op), "at least 2 arguments", args.len()));
                 }
 
-                let mut result = true;
-
-                for (lhs, rhs) in args.iter().zip(args.iter().skip(1)) {
-                    match lhs {
-                        SExpr::Number(left) => {
-                            match rhs {
-                                SExpr::Number(right) => {
-                                    if !(left $op right) {
-                                        result = false;
-                                        break;
-                                    }
-                                },
-                                other => return Err(format!("Exception in {}: #<{}> is not a number", stringify!($op), other)),
-                            }
-                        },
-                        other => return Err(format!("Exception in {}: #<{}> is not a number", stringify!($op), other)),
+                // Every argument is checked, even those after a comparison that fails
+                let mut numbers = Vec::with_capacity(args.len());
+                for arg in args.iter() {
+                    match arg {
+                        SExpr::Number(number) if number.$is_valid() => numbers.push(number),
+                        other => return Err(SchemeError::wrong_type(stringify!($op), other, $expected)),
                     }
                 }
 
+                let result = numbers.iter().zip(numbers.iter().skip(1)).all(|(left, right)| left $op right);
                 Ok(SExpr::Boolean(result))
             }
         )*}
 }
 
+// Only real numbers are ordered
 fn_compare! {
-    r_equal: ==
-    r_gt: >
-    r_ge: >=
-    r_lt: <
-    r_le: <=
+    r_equal: ==, is_complex, "a number"
+    r_gt: >, is_real, "a real number"
+    r_ge: >=, is_real, "a real number"
+    r_lt: <, is_real, "a real number"
+    r_le: <=, is_real, "a real number"
 }
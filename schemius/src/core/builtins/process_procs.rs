@@ -0,0 +1,109 @@
+use std::{
+    env,
+    io::{self, Write},
+};
+
+use super::{
+    s_list::SList,
+    s_number::SNumber,
+    s_procedure::{ProcedureArgs, ProcedureEnv, ProcedureOutput},
+    settings, unwind_all, Accessor, ErrorKind, SExpr, SchemeError, SchemeString,
+};
+
+fn exit_code(args: &ProcedureArgs, name: &str) -> Result<i32, SchemeError> {
+    match args.s_len() {
+        0 => Ok(0),
+        1 => match args.s_car().unwrap() {
+            SExpr::Boolean(true) => Ok(0),
+            SExpr::Boolean(false) => Ok(1),
+            SExpr::Number(SNumber::Int(code)) => match i32::try_from(*code) {
+                Ok(code) => Ok(code),
+                Err(_) => Err(SchemeError::new(
+                    ErrorKind::OutOfRange,
+                    format!("exit code {} is out of range", code),
+                )
+                .in_procedure(name)),
+            },
+            other => Err(SchemeError::wrong_type(name, other, "a valid exit code")),
+        },
+        n => Err(SchemeError::arity(name, "at most 1 argument", n)),
+    }
+}
+
+fn flush_output() {
+    let _ = io::stdout().flush();
+    let _ = io::stderr().flush();
+}
+
+pub fn r_exit(args: ProcedureArgs, env: ProcedureEnv) -> ProcedureOutput {
+    let code = exit_code(&args, "exit")?;
+
+    unwind_all(env);
+    flush_output();
+    std::process::exit(code)
+}
+
+pub fn r_emergency_exit(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
+    let code = exit_code(&args, "emergency-exit")?;
+
+    std::process::exit(code)
+}
+
+pub fn r_command_line(args: ProcedureArgs, env: ProcedureEnv) -> ProcedureOutput {
+    if !args.is_empty() {
+        return Err(SchemeError::arity("command-line", "0 arguments", args.len()));
+    }
+
+    let command_line = match settings(&env)?.command_line {
+        Some(command_line) => command_line.to_vec(),
+        None => env::args().collect(),
+    };
+
+    Ok(SExpr::list(
+        command_line.into_iter().map(|arg| SExpr::String(SchemeString::new(arg.into()))),
+    ))
+}
+
+pub fn r_get_environment_variable(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
+    if args.s_len() != 1 {
+        return Err(SchemeError::arity("get-environment-variable", "1 argument", args.s_len()));
+    }
+
+    match args.s_car().unwrap() {
+        SExpr::String(name) => match env::var(name.access()?.as_str()) {
+            Ok(value) => Ok(SExpr::String(SchemeString::new(value.into()))),
+            Err(_) => Ok(SExpr::Boolean(false)),
+        },
+        other => Err(SchemeError::wrong_type("get-environment-variable", other, "a string")),
+    }
+}
+
+pub fn r_get_environment_variables(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
+    if !args.is_empty() {
+        return Err(SchemeError::arity("get-environment-variables", "0 arguments", args.len()));
+    }
+
+    Ok(SExpr::list(env::vars().map(|(name, value)| {
+        SExpr::cons(
+            SExpr::String(SchemeString::new(name.into())),
+            SExpr::String(SchemeString::new(value.into())),
+        )
+    })))
+}
+
+#[cfg(test)]
+mod process_procs_tests {
+    use super::{super::ListImplementation, *};
+
+    #[test]
+    fn test_exit_code() {
+        let code = |args: Vec<SExpr>| exit_code(&ListImplementation::from_iter(args), "exit");
+
+        assert_eq!(code(vec![]).ok(), Some(0));
+        assert_eq!(code(vec![SExpr::Boolean(true)]).ok(), Some(0));
+        assert_eq!(code(vec![SExpr::Boolean(false)]).ok(), Some(1));
+        assert_eq!(code(vec![SExpr::from(3)]).ok(), Some(3));
+        assert!(code(vec![SExpr::Char('a')]).is_err());
+        assert!(code(vec![SExpr::from(1), SExpr::from(2)]).is_err());
+    }
+}
@@ -1,44 +1,64 @@
-use std::time::Instant;
+use std::ptr::fn_addr_eq;
 
 use super::{
-    eval, r_eval,
+    compiler::MAX_NESTING_DEPTH,
+    constants::libraries,
+    elapsed_since, eval,
+    file_procs::{read_source_file, resolve_source_path, with_source_file},
+    r_append, r_cons, r_current_jiffy, r_list,
     s_list::SList,
-    s_procedure::{Procedure, ProcedureArgs, ProcedureEnv, ProcedureOutput, SpecialFormOutput},
-    Accessor, Environment, ListImplementation, SExpr, SchemeEnvironment, SchemeList,
+    s_number::SNumber,
+    s_procedure::{
+        Lambda, Procedure, ProcedureArgs, ProcedureEnv, ProcedureOutput, ProcedureSignature,
+        SpecialFormOutput, SpecialFormSignature,
+    },
+    scheme_features, settings, standard_library, Accessor, EnvAccessor, Environment, ErrorKind,
+    Library, ListImplementation, NativeInt, SExpr, SchemeEnvironment, SchemeError, SchemeVector,
+    SpecialForm, Symbol,
 };
 
-fn list_args(list: &ListImplementation) -> Result<Vec<String>, String> {
-    let mut args: Vec<String> = vec![];
+fn list_args(list: &ListImplementation) -> Result<Vec<Symbol>, SchemeError> {
+    let mut args: Vec<Symbol> = vec![];
 
     for item in list.iter() {
         match item {
             SExpr::Symbol(val) => args.push(val.clone()),
-            _ => return Err(String::from("Exception: found non-symbol object in list")),
+            _ => return Err(SchemeError::wrong_type("lambda", item, "a symbol")),
         }
     }
 
     Ok(args)
 }
 
+/// Forms evaluating some of their arguments are run by the evaluator itself, which never calls
+/// the functions they are bound to. These evaluate the whole form when called directly.
+///
+/// Special forms are told apart by address, so each of these functions passes its own `name`:
+/// were their bodies identical, an optimized build could merge them into one.
+fn eval_form(
+    name: &str, form: SpecialFormSignature, args: ProcedureArgs, env: ProcedureEnv,
+) -> ProcedureOutput {
+    let expression = SExpr::cons(SExpr::Keyword(form), SExpr::list(args));
+
+    eval(&expression, env).map_err(|e| match e.procedure() {
+        Some(_) => e,
+        None => e.in_procedure(name),
+    })
+}
+
 pub fn r_lambda(args: ProcedureArgs, env: ProcedureEnv) -> SpecialFormOutput {
     let length = args.s_len();
     if length < 2 {
-        return Err(format!(
-            "Exception in lambda: expected at least 2 arguments, found {}",
-            length
-        ));
+        return Err(SchemeError::arity("lambda", "at least 2 arguments", length));
     }
 
-    let arg_names = match args.s_car().unwrap() {
-        SExpr::List(ref list) => match list_args(&list.access()) {
-            Ok(names) => names,
-            Err(e) => return Err(e),
-        },
-        _ => return Err(String::from("")),
+    let arg_names = match args.s_car().unwrap().as_list() {
+        Ok(list) => list_args(&list)?,
+        Err(_) => return Err(SchemeError::wrong_type("lambda", args.s_car().unwrap(), "a list")),
     };
 
     let body = args.s_cdr().unwrap();
-    Ok(SExpr::Procedure(Procedure::Compound(arg_names, body, env.clone())))
+    Ok(SExpr::Procedure(Procedure::Compound(Lambda::new(arg_names, body), env.clone())))
 }
 
 pub fn r_define(args: ProcedureArgs, env: ProcedureEnv) -> SpecialFormOutput {
@@ -47,468 +67,759 @@ pub fn r_define(args: ProcedureArgs, env: ProcedureEnv) -> SpecialFormOutput {
         1 => Ok(SExpr::Ok),
         2.. => match args.s_car().unwrap() {
             SExpr::Symbol(name) => match eval(args.s_cadr().unwrap(), env.clone()) {
-                Ok(val) => {
-                    let value = match val {
-                        SExpr::List(list) => SExpr::List(list.clone()),
-                        other => other,
-                    };
-
-                    match env.access_mut().define(name, &value) {
-                        Ok(_) => Ok(SExpr::Ok),
-                        Err(_) => Err(format!("Exception: error defining {}", name)),
+                Ok(value) => match env.access_mut()?.define(name, &value) {
+                    Ok(_) => Ok(SExpr::Ok),
+                    Err(_) => {
+                        Err(SchemeError::syntax("define", format!("error defining {}", name)))
                     }
-                }
+                },
                 Err(e) => Err(e),
             },
-            SExpr::List(list) => {
-                if list.access().s_len() == 0 {
-                    return Err(String::from("Exception (TODO?): deal with empty lists"));
-                }
-
-                let lambda_name = list.access().s_car().unwrap().to_string();
+            SExpr::Null => Err(SchemeError::syntax("define", "cannot define an empty list")),
+            SExpr::Pair(pair) => {
+                let lambda_name = match pair.access()?.car {
+                    SExpr::Symbol(ref name) => name.clone(),
+                    ref other => Symbol::new(&other.to_string()),
+                };
                 let mut lambda_args = ListImplementation::new();
-                let mut proc_args = ListImplementation::new();
+                let proc_args = pair.access()?.cdr.clone();
                 let lambda_body = &mut args.s_cdr().unwrap();
 
-                if list.access().s_len() > 1 {
-                    for arg in &list.access().s_cdr().unwrap() {
-                        proc_args.push(arg.clone());
-                    }
-                }
-
-                lambda_args =
-                    ListImplementation::from_iter([SExpr::List(SchemeList::new(proc_args))]);
+                lambda_args = ListImplementation::from_iter([proc_args]);
                 lambda_args.append(lambda_body);
 
-                let lambda_proc = match r_lambda(lambda_args, env.clone()) {
-                    Ok(lambda) => lambda,
-                    Err(e) => return Err(e),
-                };
+                let lambda_proc = r_lambda(lambda_args, env.clone())?;
 
-                match env.access_mut().define(&lambda_name, &lambda_proc) {
+                match env.access_mut()?.define(&lambda_name, &lambda_proc) {
                     Ok(_) => Ok(SExpr::Ok),
-                    Err(_) => Err(String::from("")),
+                    Err(e) => Err(e),
                 }
             }
-            _ => Err(String::from(
-                "Exception: #<procedure define> cant take only a symbol and a list",
-            )),
+            _ => Err(SchemeError::syntax("define", "expected a symbol or a list")),
         },
-        _ => Err(String::from("Exception: #<procedure define> needs arguments")),
+        _ => Err(SchemeError::arity("define", "at least 1 argument", 0)),
     }
 }
 
 pub fn r_set(args: ProcedureArgs, env: ProcedureEnv) -> SpecialFormOutput {
-    let length = args.s_len();
-    if length != 2 {
-        return Err(format!("Exception in set!: expected 2 arguments, found {}", length));
-    }
+    eval_form("set!", SpecialForm::SET, args, env)
+}
 
-    match args.s_car().unwrap() {
-        SExpr::Symbol(name) => match eval(args.s_cadr().unwrap(), env.clone()) {
-            Ok(val) => {
-                let value = match val {
-                    SExpr::List(list) => SExpr::List(list.clone()),
-                    other => other,
-                };
+pub fn r_let(args: ProcedureArgs, env: ProcedureEnv) -> SpecialFormOutput {
+    eval_form("let", SpecialForm::LET, args, env)
+}
 
-                match env.access_mut().set(&name, &value) {
-                    Ok(_) => Ok(SExpr::Ok),
-                    Err(e) => Err(e),
-                }
-            }
-            Err(e) => Err(e),
-        },
-        other => Err(format!("Exception: {} is not a symbol", other)),
-    }
+pub fn r_let_star(args: ProcedureArgs, env: ProcedureEnv) -> SpecialFormOutput {
+    eval_form("let*", SpecialForm::LET_STAR, args, env)
 }
 
-pub fn r_let(args: ProcedureArgs, env: ProcedureEnv) -> SpecialFormOutput {
+pub fn r_letrec(args: ProcedureArgs, env: ProcedureEnv) -> SpecialFormOutput {
+    eval_form("letrec", SpecialForm::LETREC, args, env)
+}
+
+pub fn r_letrec_star(args: ProcedureArgs, env: ProcedureEnv) -> SpecialFormOutput {
+    eval_form("letrec*", SpecialForm::LETREC_STAR, args, env)
+}
+
+pub fn r_if(args: ProcedureArgs, env: ProcedureEnv) -> SpecialFormOutput {
+    eval_form("if", SpecialForm::IF, args, env)
+}
+
+pub fn r_when(args: ProcedureArgs, env: ProcedureEnv) -> SpecialFormOutput {
+    eval_form("when", SpecialForm::WHEN, args, env)
+}
+
+pub fn r_unless(args: ProcedureArgs, env: ProcedureEnv) -> SpecialFormOutput {
+    eval_form("unless", SpecialForm::UNLESS, args, env)
+}
+
+pub fn r_case(args: ProcedureArgs, env: ProcedureEnv) -> SpecialFormOutput {
+    eval_form("case", SpecialForm::CASE, args, env)
+}
+
+pub fn r_do(args: ProcedureArgs, env: ProcedureEnv) -> SpecialFormOutput {
+    eval_form("do", SpecialForm::DO, args, env)
+}
+
+pub fn r_begin(args: ProcedureArgs, env: ProcedureEnv) -> SpecialFormOutput {
+    eval_form("begin", SpecialForm::BEGIN, args, env)
+}
+
+pub fn r_quote(args: ProcedureArgs, _: ProcedureEnv) -> SpecialFormOutput {
     let length = args.s_len();
-    if length < 2 {
-        return Err(format!(
-            "Exception in let: expected at least 2 arguments, found {}",
-            args.s_len()
-        ));
+    if length != 1 {
+        return Err(SchemeError::arity("'", "1 argument", length));
     }
 
-    let let_env = Environment::new_child(env.clone());
-
-    match args.s_car().unwrap() {
-        SExpr::List(list) => {
-            for binding in list.access().iter() {
-                match binding {
-                    SExpr::List(binding) => {
-                        let borrowed_binding = binding.access();
-                        match borrowed_binding.s_car().unwrap() {
-                            SExpr::Symbol(symbol) => {
-                                match eval(&borrowed_binding.s_ref(1).unwrap(), env.clone()) {
-                                    Ok(expr) => {
-                                        let_env.access_mut().define(&symbol, &expr).unwrap()
-                                    }
-                                    Err(e) => return Err(e),
-                                }
-                            }
-                            other => {
-                                return Err(format!("Exception in let: {} is not a symbol", other))
-                            }
-                        }
-                    }
-                    other => return Err(format!("Exception in let: {} is not a list", other)),
-                }
-            }
+    Ok(args.s_car().unwrap().clone())
+}
+
+/// The expression building `template`, or `None` if nothing in it is unquoted so that it can be
+/// quoted as it is. Nested `quasiquote` forms quasiquote their operands one `level` deeper, which
+/// only expressions unquoted as many times are evaluated at.
+///
+/// Templates are rewritten into calls to `cons` and `append`, so that building them takes no
+/// more native stack than any other call, however deeply the expressions unquoted within recurse.
+/// The parts of a template nested deeper than the compiler recurses, which `depth` counts, are
+/// only rewritten once evaluated.
+fn quasiquote(template: &SExpr, level: usize, depth: usize) -> Result<Option<SExpr>, SchemeError> {
+    let head = match template {
+        SExpr::Pair(_) | SExpr::Vector(_) if depth == MAX_NESTING_DEPTH => {
+            let level = SExpr::from(level as NativeInt);
+            let resumed = SExpr::Keyword(r_quasiquote_nested);
+            return Ok(Some(SExpr::list([resumed, level, template.clone()])));
         }
-        other => return Err(format!("Exception in let: {} is not a list", other)),
-    }
+        SExpr::Pair(_) => template.car().unwrap(),
+        SExpr::Vector(vector) => {
+            let items = SExpr::list(vector.access()?.iter().cloned());
+            let list = quasiquote_list(&items, level, depth + 1)?;
+            return Ok(list.map(|list| SExpr::list([primitive(r_list_to_vector), list])));
+        }
+        _ => return Ok(None),
+    };
 
-    let mut res = SExpr::Unspecified;
+    let Some(operand) = nested_operand(template)? else {
+        if level == 0 && head.is_unquote()? {
+            return Err(SchemeError::syntax(",", "expected a single expression to unquote"));
+        }
+        return quasiquote_list(template, level, depth);
+    };
 
-    for body_item in &args.s_cdr().unwrap() {
-        match eval(body_item, let_env.clone()) {
-            Ok(something) => res = something,
-            Err(e) => return Err(e),
+    match (level, head.is_unquote()?) {
+        (0, true) if head.is_unquote_splicing()? => {
+            Err(SchemeError::syntax(",@", "cannot splice outside of a list"))
+        }
+        (0, true) => Ok(Some(operand)),
+        (_, unquote) => {
+            let level = if unquote { level - 1 } else { level + 1 };
+            let operand = quasiquote(&operand, level, depth + 1)?;
+            Ok(operand.map(|operand| SExpr::list([primitive(r_list), quoted(&head), operand])))
         }
     }
-
-    Ok(res)
 }
 
-pub fn r_let_star(args: ProcedureArgs, env: ProcedureEnv) -> SpecialFormOutput {
-    let length = args.s_len();
-    if length < 2 {
-        return Err(format!("Exception in let: expected at least 2 arguments, found {}", length));
+/// The expression building the list `template`, as `quasiquote` does.
+fn quasiquote_list(
+    template: &SExpr, level: usize, depth: usize,
+) -> Result<Option<SExpr>, SchemeError> {
+    // A tail such as `(1 . ,x)`, read as `(1 unquote x)`, is quasiquoted as a whole
+    let mut items = vec![];
+    let mut rest = template.clone();
+    while let SExpr::Pair(_) = rest {
+        if !items.is_empty() && nested_operand(&rest)?.is_some() {
+            break;
+        }
+        items.push(rest.car().unwrap());
+        rest = rest.cdr().unwrap();
     }
 
-    let mut inner_env = env;
-
-    match args.s_car().unwrap() {
-        SExpr::List(list) => {
-            for binding in list.access().iter() {
-                match binding {
-                    SExpr::List(binding) => {
-                        let borrowed_binding = binding.access();
-                        match &borrowed_binding.s_car().unwrap() {
-                            SExpr::Symbol(symbol) => {
-                                match eval(&borrowed_binding.s_ref(1).unwrap(), inner_env.clone()) {
-                                    Ok(expr) => {
-                                        inner_env = Environment::new_child(inner_env.clone());
-                                        inner_env = Environment::new_child(inner_env.clone());
-                                        inner_env.access_mut().define(&symbol, &expr).unwrap();
-                                    }
-                                    Err(e) => return Err(e),
-                                }
-                            }
-                            other => {
-                                return Err(format!("Exception in let: {} is not a symbol", other))
-                            }
-                        }
-                    }
-                    other => return Err(format!("Exception in let: {} is not a list", other)),
-                }
+    let tail = quasiquote(&rest, level, depth)?;
+    let mut unquoted = tail.is_some();
+    let mut expansion = tail.unwrap_or_else(|| quoted(&rest));
+
+    for (i, item) in items.iter().enumerate().rev() {
+        let spliced = match (level, item.car()) {
+            (0, Some(head)) if head.is_unquote_splicing()? => nested_operand(item)?,
+            _ => None,
+        };
+
+        expansion = match spliced {
+            // The list spliced last ends the list built, which need not copy it
+            Some(list) if i + 1 == items.len() && matches!(rest, SExpr::Null) => {
+                unquoted = true;
+                SExpr::list([primitive(r_spliced), list])
             }
-        }
-        other => return Err(format!("Exception in let: {} is not a list", other)),
+            Some(list) => {
+                unquoted = true;
+                SExpr::list([primitive(r_append), list, expansion])
+            }
+            None => {
+                let built = quasiquote(item, level, depth + 1)?;
+                unquoted |= built.is_some();
+                SExpr::list([primitive(r_cons), built.unwrap_or_else(|| quoted(item)), expansion])
+            }
+        };
     }
 
-    let mut res = SExpr::Unspecified;
+    Ok(unquoted.then_some(expansion))
+}
 
-    for body_item in &args.s_cdr().unwrap() {
-        match eval(body_item, inner_env.clone()) {
-            Ok(something) => res = something,
-            Err(e) => return Err(e),
-        }
+/// The operand of `form` if it is an `unquote`, `unquote-splicing` or `quasiquote` form of a
+/// single expression.
+fn nested_operand(form: &SExpr) -> Result<Option<SExpr>, SchemeError> {
+    let head = form.car().unwrap();
+    if !head.is_unquote()? && !head.is_quasiquote()? {
+        return Ok(None);
     }
 
-    Ok(res)
+    match form.cdr().unwrap().as_list() {
+        Ok(operands) if operands.s_len() == 1 => Ok(operands.s_car().cloned()),
+        _ => Ok(None),
+    }
 }
 
-pub fn r_if(args: ProcedureArgs, env: ProcedureEnv) -> SpecialFormOutput {
-    let length = args.s_len();
-    if length != 2 && length != 3 {
-        return Err(format!("Exception in if: expected two or three arguments, found {}", length));
+fn quoted(datum: &SExpr) -> SExpr {
+    SExpr::list([SExpr::Keyword(SpecialForm::QUOTE), datum.clone()])
+}
+
+fn primitive(primitive: ProcedureSignature) -> SExpr {
+    SExpr::Procedure(Procedure::Primitive(primitive))
+}
+
+/// The list spliced last into a `quasiquote` template, as it is. Only its first pair is checked,
+/// as walking it all would make building lists recursively take quadratic time.
+fn r_spliced(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
+    match args.s_car().cloned().unwrap_or(SExpr::Null) {
+        list @ (SExpr::Pair(_) | SExpr::Null) => Ok(list),
+        other => Err(SchemeError::wrong_type(",@", &other, "a list")),
     }
+}
 
-    match eval(args.s_car().unwrap(), env.clone()) {
-        Ok(condition) => match condition {
-            SExpr::Boolean(false) => match length {
-                2 => Ok(SExpr::Ok),
-                3 => Ok(args.s_ref(2).unwrap().clone()),
-                _ => Err(String::from("Exception: wrong number of arguments for if")),
-            },
-            _ => Ok(args.s_cadr().unwrap().clone()),
-        },
-        Err(e) => Err(e),
+/// The vector of the items of a list, which the vectors of `quasiquote` templates are built from.
+fn r_list_to_vector(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
+    let list = args.s_car().cloned().unwrap_or(SExpr::Null);
+    match list.as_list() {
+        Ok(items) => Ok(SExpr::Vector(SchemeVector::new(items.into_iter().collect()))),
+        Err(_) => Err(SchemeError::wrong_type("`", &list, "a list")),
     }
 }
 
-pub fn r_not(args: ProcedureArgs, env: ProcedureEnv) -> SpecialFormOutput {
+pub fn r_quasiquote(args: ProcedureArgs, _: ProcedureEnv) -> SpecialFormOutput {
     let length = args.s_len();
     if length != 1 {
-        return Err(format!("Exception in not: expected one argument, found {}", length));
+        return Err(SchemeError::arity("`", "1 argument", length));
     }
 
-    match eval(args.s_car().unwrap(), env.clone()) {
-        Ok(test) => match test {
-            SExpr::Boolean(result) => Ok(SExpr::Boolean(!result)),
-            _ => Ok(SExpr::Boolean(false)),
-        },
-        Err(e) => Err(e),
-    }
+    let template = args.s_car().unwrap();
+    Ok(quasiquote(template, 0, 0)?.unwrap_or_else(|| quoted(template)))
 }
 
-pub fn r_begin(args: ProcedureArgs, env: ProcedureEnv) -> SpecialFormOutput {
-    if args.is_empty() {
-        return Err(format!(
-            "Exception in begin: expected at least 1 argument, found {}",
-            args.s_len()
-        ));
-    }
+/// Resumes `quasiquote` on a template nested too deeply to be rewritten along with the rest,
+/// given the level it is quasiquoted at.
+fn r_quasiquote_nested(args: ProcedureArgs, _: ProcedureEnv) -> SpecialFormOutput {
+    let (Some(level), Some(template)) = (args.s_car(), args.s_cadr()) else {
+        return Err(SchemeError::arity("`", "a level and a template", args.s_len()));
+    };
 
-    let tail = args.last().unwrap();
-    let mut head = args.clone();
-    head.pop();
+    let level = level.as_int()? as usize;
+    Ok(quasiquote(template, level, 0)?.unwrap_or_else(|| quoted(template)))
+}
 
-    for v in head.iter() {
-        match eval(v, env.clone()) {
-            Ok(_) => {}
-            Err(e) => return Err(e),
-        };
+pub fn r_cond(args: ProcedureArgs, env: ProcedureEnv) -> SpecialFormOutput {
+    eval_form("cond", SpecialForm::COND, args, env)
+}
+
+/// Whether the feature requirement of a `cond-expand` clause is fulfilled, given how many
+/// requirements enclose it. These are nested no deeper than the compiler recurses.
+fn is_requirement_fulfilled(requirement: &SExpr, depth: usize) -> Result<bool, SchemeError> {
+    if depth == MAX_NESTING_DEPTH {
+        return Err(SchemeError::syntax("cond-expand", "feature requirement nested too deeply"));
     }
 
-    Ok(tail.clone())
+    match requirement {
+        SExpr::Symbol(feature) => Ok(scheme_features().iter().any(|f| *feature == f.as_str())),
+        SExpr::Pair(_) => {
+            let list = requirement.as_list()?;
+            let operator = match list.s_car() {
+                Some(SExpr::Symbol(operator)) => operator.as_str(),
+                _ => {
+                    return Err(SchemeError::syntax(
+                        "cond-expand",
+                        format!("invalid feature requirement {}", requirement),
+                    ))
+                }
+            };
+
+            match operator {
+                "and" => {
+                    for item in list.iter().skip(1) {
+                        if !is_requirement_fulfilled(item, depth + 1)? {
+                            return Ok(false);
+                        }
+                    }
+                    Ok(true)
+                }
+                "or" => {
+                    for item in list.iter().skip(1) {
+                        if is_requirement_fulfilled(item, depth + 1)? {
+                            return Ok(true);
+                        }
+                    }
+                    Ok(false)
+                }
+                "not" if list.s_len() == 2 => {
+                    Ok(!is_requirement_fulfilled(list.s_cadr().unwrap(), depth + 1)?)
+                }
+                "library" if list.s_len() == 2 => {
+                    let name = list.s_cadr().unwrap().to_string();
+                    Ok(libraries::SUPPORTED.iter().any(|(library, _)| *library == name))
+                }
+                _ => Err(SchemeError::syntax(
+                    "cond-expand",
+                    format!("invalid feature requirement {}", requirement),
+                )),
+            }
+        }
+        other => Err(SchemeError::syntax(
+            "cond-expand",
+            format!("invalid feature requirement {}", other),
+        )),
+    }
 }
 
-pub fn r_quote(args: ProcedureArgs, _: ProcedureEnv) -> SpecialFormOutput {
-    let length = args.s_len();
-    if length != 1 {
-        return Err(format!("Exception in ': expected 1 argument, found {}", length));
+pub fn r_cond_expand(args: ProcedureArgs, _: ProcedureEnv) -> SpecialFormOutput {
+    for clause in args.iter() {
+        match clause.as_list() {
+            Ok(clause) => {
+                let requirement = match clause.s_car() {
+                    Some(requirement) => requirement,
+                    None => return Err(SchemeError::syntax("cond-expand", "empty clause")),
+                };
+
+                if requirement.symbol_is("else")? || is_requirement_fulfilled(requirement, 0)? {
+                    if clause.s_len() == 1 {
+                        return Ok(SExpr::Unspecified);
+                    }
+
+                    let body = clause.iter().skip(1).cloned();
+                    return Ok(SExpr::cons(SExpr::Keyword(SpecialForm::BEGIN), SExpr::list(body)));
+                }
+            }
+            Err(_) => return Err(SchemeError::wrong_type("cond-expand", clause, "a list")),
+        }
     }
 
-    Ok(args.s_car().unwrap().clone())
+    Ok(SExpr::Unspecified)
 }
 
-fn r_unquote(args: ProcedureArgs, env: ProcedureEnv) -> SpecialFormOutput {
-    let length = args.s_len();
-    if length != 1 {
-        return Err(format!("Exception in ,: expected 1 argument, found {}", length));
+/// Prints how long the expression timed by `time` took, given the jiffy it started at, and
+/// evaluates to its value.
+fn r_elapsed(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
+    let (Some(SExpr::Number(then)), Some(value)) = (args.s_car(), args.s_cadr()) else {
+        return Err(SchemeError::arity("time", "a jiffy and a value", args.s_len()));
+    };
+
+    if let Some(elapsed) = elapsed_since(then) {
+        println!("Elapsed time: {:?}", elapsed);
     }
 
-    Ok(eval(args.s_car().unwrap(), env)?)
+    Ok(value.clone())
 }
 
-pub fn r_quasiquote(args: ProcedureArgs, env: ProcedureEnv) -> SpecialFormOutput {
+/// The expression printing how long the evaluation of the argument takes, which the evaluator
+/// runs itself so that the argument is evaluated like any other expression.
+pub fn r_time(args: ProcedureArgs, _: ProcedureEnv) -> SpecialFormOutput {
     let length = args.s_len();
     if length != 1 {
-        return Err(format!("Exception in `: expected 1 argument, found {}", length));
+        return Err(SchemeError::arity("time", "1 argument", length));
     }
 
-    let arg = args.s_car().unwrap();
+    let start = SExpr::list([primitive(r_current_jiffy)]);
+    Ok(SExpr::list([primitive(r_elapsed), start, args.s_car().unwrap().clone()]))
+}
+
+/// Tells whether the variables of the root environment bound to a primitive were bound to
+/// something else as many times as the argument says, which code the optimizer rewrote to call
+/// primitives directly checks. The form has no name, only optimized code refers to it.
+pub fn r_unchanged(args: ProcedureArgs, env: ProcedureEnv) -> SpecialFormOutput {
+    let rebound = env.access()?.rebound();
+    Ok(SExpr::Boolean(matches!(
+        args.s_car(),
+        Some(SExpr::Number(SNumber::Int(n))) if usize::try_from(*n) == Ok(rebound)
+    )))
+}
+
+pub fn r_and(args: ProcedureArgs, env: ProcedureEnv) -> ProcedureOutput {
+    eval_form("and", SpecialForm::AND, args, env)
+}
+
+pub fn r_or(args: ProcedureArgs, env: ProcedureEnv) -> ProcedureOutput {
+    eval_form("or", SpecialForm::OR, args, env)
+}
 
-    if !args.s_car().unwrap().is_list().unwrap() {
-        return Err("Exception in `: expected a list".to_string());
+/// Splices the files included by the `include` and `include-ci` forms within `expression`,
+/// leaving quoted data alone. Forms nested deeper than the compiler recurses, which `depth`
+/// counts, include their files once evaluated instead.
+fn expand_includes(expression: &SExpr, env: &ProcedureEnv, depth: usize) -> SpecialFormOutput {
+    let Ok(list) = expression.as_list() else {
+        return Ok(expression.clone());
+    };
+    if depth == MAX_NESTING_DEPTH {
+        return Ok(expression.clone());
     }
 
-    match arg {
-        SExpr::List(list) => {
-            let mut new_list = ListImplementation::new();
-            let current_list = list.access();
-
-            for item in current_list.iter() {
-                match item {
-                    SExpr::List(inner_list) => {
-                        if inner_list.access().s_len() > 0 {
-                            if inner_list.access().s_car().unwrap().is_unquote()? {
-                                let unquoted = r_unquote(
-                                    ListImplementation::from_iter([inner_list
-                                        .access()
-                                        .s_cadr()
-                                        .unwrap()
-                                        .clone()]),
-                                    env.clone(),
-                                )?;
-
-                                if inner_list.access().s_car().unwrap().is_unquote_pure()? {
-                                    new_list.push(unquoted);
-                                } else if inner_list
-                                    .access()
-                                    .s_car()
-                                    .unwrap()
-                                    .is_unquote_splicing()?
-                                {
-                                    match unquoted {
-                                        SExpr::List(list) => {
-                                            let borrowed_list = list.access();
-                                            for item in borrowed_list.iter() {
-                                                new_list.push(item.clone());
-                                            }
-                                        }
-                                        _ => new_list.push(unquoted),
-                                    }
-                                }
-                            } else if inner_list.access().s_car().unwrap().is_quasiquote()? {
-                                new_list.push(SExpr::List(inner_list.clone()));
-                            } else {
-                                new_list.push(
-                                    r_quasiquote(
-                                        ListImplementation::from_iter([SExpr::List(
-                                            inner_list.clone(),
-                                        )]),
-                                        env.clone(),
-                                    )
-                                    .unwrap(),
-                                );
-                            }
-                        } else {
-                            new_list.push(SExpr::List(inner_list.clone()));
-                        }
-                    }
-                    _ => new_list.push(item.clone()),
-                }
-            }
+    let form = match list.s_car() {
+        Some(SExpr::Symbol(name)) => env.access()?.get(name),
+        Some(keyword @ SExpr::Keyword(_)) => Some(keyword.clone()),
+        _ => None,
+    };
 
-            Ok(SExpr::List(SchemeList::new(new_list)))
+    match form {
+        Some(SExpr::Keyword(form))
+            if fn_addr_eq(form, SpecialForm::INCLUDE)
+                || fn_addr_eq(form, SpecialForm::INCLUDE_CI) =>
+        {
+            form(list.iter().skip(1).cloned().collect(), env.clone())
+        }
+        Some(SExpr::Keyword(form))
+            if fn_addr_eq(form, SpecialForm::QUOTE)
+                || fn_addr_eq(form, SpecialForm::QUASIQUOTE) =>
+        {
+            Ok(expression.clone())
         }
-        _ => Ok(arg.clone()),
+        _ => Ok(SExpr::list(
+            list.iter()
+                .map(|item| expand_includes(item, env, depth + 1))
+                .collect::<Result<Vec<_>, _>>()?,
+        )),
     }
 }
 
-pub fn r_cond(args: ProcedureArgs, env: ProcedureEnv) -> SpecialFormOutput {
+/// Reads the files named by `args` into a `begin` form of their contents, which takes the place
+/// of the form including them. Files included by these are spliced in turn, relative to the
+/// directory of the file including them.
+fn include_files(
+    args: ProcedureArgs, env: ProcedureEnv, name: &str, fold: bool,
+) -> SpecialFormOutput {
     if args.is_empty() {
-        return Err(format!(
-            "Exception in cond: expected at least 1 argument, found {}",
-            args.s_len()
-        ));
+        return Err(SchemeError::arity(name, "at least 1 argument", 0));
     }
 
-    let length = args.s_len();
-    let have_else_clause = length > 3
-        && match args.s_ref(length - 2).unwrap() {
-            SExpr::Symbol(clause) => *clause == "else",
-            _ => false,
+    let mut body = vec![];
+
+    for arg in args.iter() {
+        let path = match arg {
+            SExpr::String(path) => resolve_source_path(&path.access()?),
+            other => return Err(SchemeError::wrong_type(name, other, "a string")),
         };
 
-    let iterator =
-        if have_else_clause { args.clone().extract_range(0, length - 2) } else { args.clone() };
+        let expressions = read_source_file(&path, name, &env, fold)?;
 
-    for block in iterator {
-        match block {
-            SExpr::List(list) => {
-                if list.access().s_len() != 2 {
-                    return Err(String::from(
-                        "Exception: malformed args provided to #<procedure cond>",
-                    ));
-                }
-                let first = eval(&list.access().s_car().unwrap(), env.clone());
-                match first {
-                    Ok(condition) => match condition {
-                        SExpr::Boolean(val) => match val {
-                            true => return Ok(list.access().s_ref(1).unwrap().clone()),
-                            false => continue,
-                        },
-                        _ => {
-                            return Err(String::from(
-                                "Exception: malformed condition provided to #<procedure cond>",
-                            ))
-                        }
-                    },
-                    Err(e) => return Err(e),
-                }
+        with_source_file(&path, || {
+            for datum in expressions.iter() {
+                body.push(
+                    expand_includes(&datum.expr, &env, 0).map_err(|e| e.with_span(&datum.span))?,
+                );
             }
-            _ => return Err(String::from("Exception: #<procedure cond> args must be lists")),
-        }
+
+            Ok::<_, SchemeError>(())
+        })?;
     }
 
-    if have_else_clause {
-        Ok(args.last().unwrap().clone())
-    } else {
-        Ok(SExpr::Ok)
+    if body.is_empty() {
+        body.push(SExpr::Unspecified);
     }
+
+    Ok(SExpr::cons(SExpr::Keyword(SpecialForm::BEGIN), SExpr::list(body)))
+}
+
+pub fn r_include(args: ProcedureArgs, env: ProcedureEnv) -> SpecialFormOutput {
+    include_files(args, env, "include", false)
 }
 
-pub fn r_time(args: ProcedureArgs, env: ProcedureEnv) -> ProcedureOutput {
-    let then = Instant::now();
+pub fn r_include_ci(args: ProcedureArgs, env: ProcedureEnv) -> SpecialFormOutput {
+    include_files(args, env, "include-ci", true)
+}
+
+/// The name of a library, a list of identifiers and exact non-negative integers, in the form
+/// libraries are looked up by.
+fn library_name(form: &str, name: &SExpr) -> Result<String, SchemeError> {
+    let parts = match name.as_list() {
+        Ok(parts) if !parts.is_empty() => parts,
+        _ => return Err(SchemeError::wrong_type(form, name, "a library name")),
+    };
 
-    match r_eval(args, env.clone()) {
-        Ok(_) => {
-            let elapsed = then.elapsed();
-            Ok(SExpr::Symbol(format!("{:?}", elapsed)))
+    for part in parts.iter() {
+        match part {
+            SExpr::Symbol(_) => {}
+            SExpr::Number(SNumber::Int(number)) if *number >= 0 => {}
+            _ => return Err(SchemeError::wrong_type(form, name, "a library name")),
         }
-        Err(e) => Err(e),
     }
+
+    Ok(name.to_string())
 }
 
-pub fn r_and(args: ProcedureArgs, env: ProcedureEnv) -> ProcedureOutput {
-    let n_args = args.s_len();
-    if n_args == 0 {
-        return Ok(SExpr::Boolean(true));
+fn identifiers<'a>(
+    form: &str, items: impl Iterator<Item = &'a SExpr>,
+) -> Result<Vec<String>, SchemeError> {
+    items
+        .map(|item| match item {
+            SExpr::Symbol(name) => Ok(name.to_string()),
+            other => Err(SchemeError::wrong_type(form, other, "an identifier")),
+        })
+        .collect()
+}
+
+/// The bindings of the library `name`, looked up among the libraries defined in the root
+/// environment of `env` and then among the standard ones.
+fn library_bindings(set: &SExpr, env: ProcedureEnv) -> Result<Vec<(String, SExpr)>, SchemeError> {
+    let name = library_name("import", set)?;
+
+    let library = Environment::get_root(env.clone())?.access()?.get_library(&name);
+    if let Some(library) = library {
+        let library_env = library.env.access()?;
+        return library
+            .exports
+            .iter()
+            .map(|(internal, external)| match library_env.get(&Symbol::new(internal)) {
+                Some(value) => Ok((external.clone(), value)),
+                None => Err(SchemeError::unbound_variable(internal)),
+            })
+            .collect();
     }
 
-    for arg in args.iter().take(n_args - 1) {
-        let evaluated = eval(arg, env.clone());
-        if evaluated.is_err() {
-            return evaluated;
-        } else {
-            let result = evaluated.unwrap();
-            if let SExpr::Boolean(false) = result {
-                return Ok(result);
+    standard_library(&name, env)?.ok_or_else(|| {
+        SchemeError::new(ErrorKind::WrongType, format!("unknown library {}", name))
+            .in_procedure("import")
+            .with_irritants([set.clone()])
+    })
+}
+
+/// The bindings imported by the import set `set`, by the names they are imported as.
+fn import_set(set: &SExpr, env: ProcedureEnv) -> Result<Vec<(String, SExpr)>, SchemeError> {
+    let list = match set.as_list() {
+        Ok(list) if !list.is_empty() => list,
+        _ => return Err(SchemeError::syntax("import", format!("invalid import set {}", set))),
+    };
+
+    let modifier = match list.s_car() {
+        Some(SExpr::Symbol(modifier)) => modifier.to_string(),
+        _ => String::new(),
+    };
+
+    let (inner, rest) = match list.s_cadr() {
+        Some(inner @ SExpr::Pair(_)) => (inner, list.iter().skip(2)),
+        _ => return library_bindings(set, env),
+    };
+
+    match modifier.as_str() {
+        "only" | "except" => {
+            let names = identifiers("import", rest)?;
+            let mut bindings = import_set(inner, env)?;
+
+            if let Some(name) = names.iter().find(|name| !bindings.iter().any(|(n, _)| n == *name))
+            {
+                return Err(SchemeError::syntax(
+                    "import",
+                    format!("{} is not exported by {}", name, inner),
+                ));
             }
+
+            let only = modifier == "only";
+            bindings.retain(|(name, _)| names.contains(name) == only);
+            Ok(bindings)
+        }
+        "prefix" if list.s_len() == 3 => match list.s_ref(2).unwrap() {
+            SExpr::Symbol(prefix) => Ok(import_set(inner, env)?
+                .into_iter()
+                .map(|(name, value)| (format!("{}{}", prefix.as_str(), name), value))
+                .collect()),
+            other => Err(SchemeError::wrong_type("import", other, "an identifier")),
+        },
+        "rename" => {
+            let mut renames = vec![];
+            for rename in rest {
+                match identifiers("import", rename.as_list().unwrap_or_default().iter()) {
+                    Ok(names) if names.len() == 2 => {
+                        renames.push((names[0].clone(), names[1].clone()))
+                    }
+                    _ => {
+                        return Err(SchemeError::syntax(
+                            "import",
+                            format!("invalid renaming {}", rename),
+                        ))
+                    }
+                }
+            }
+
+            let mut bindings = import_set(inner, env)?;
+            for (from, to) in renames {
+                match bindings.iter_mut().find(|(name, _)| *name == from) {
+                    Some(binding) => binding.0 = to,
+                    None => {
+                        return Err(SchemeError::syntax(
+                            "import",
+                            format!("{} is not exported by {}", from, inner),
+                        ))
+                    }
+                }
+            }
+
+            Ok(bindings)
+        }
+        _ => library_bindings(set, env),
+    }
+}
+
+/// Defines in `into` the bindings imported by each import set of `sets`, libraries being
+/// looked up from `env`.
+fn import<'a>(
+    sets: impl Iterator<Item = &'a SExpr>, env: ProcedureEnv, into: &ProcedureEnv,
+) -> Result<(), SchemeError> {
+    for set in sets {
+        for (name, value) in import_set(set, env.clone())? {
+            into.access_mut()?.define(&Symbol::new(&name), &value)?;
         }
     }
 
-    Ok(args.last().unwrap().clone())
+    Ok(())
 }
 
-pub fn r_or(args: ProcedureArgs, env: ProcedureEnv) -> ProcedureOutput {
-    let n_args = args.s_len();
-    if n_args == 0 {
-        return Ok(SExpr::Boolean(true));
+pub fn r_import(args: ProcedureArgs, env: ProcedureEnv) -> ProcedureOutput {
+    if args.is_empty() {
+        return Err(SchemeError::arity("import", "at least 1 argument", 0));
     }
 
-    for arg in args.iter().take(n_args - 1) {
-        let evaluated = eval(arg, env.clone());
-        if evaluated.is_err() {
-            return evaluated;
-        } else {
-            let result = evaluated.unwrap();
-            if let SExpr::Boolean(false) = result {
-                continue;
-            } else {
-                return Ok(arg.clone());
+    import(args.iter(), env.clone(), &env)?;
+    Ok(SExpr::Unspecified)
+}
+
+/// Runs the declarations of a library being defined within `env`, whose body runs in
+/// `library_env`, adding the names it exports to `exports`.
+fn library_declarations(
+    declarations: &[SExpr], env: &ProcedureEnv, library_env: &ProcedureEnv,
+    exports: &mut Vec<(String, String)>,
+) -> Result<(), SchemeError> {
+    for declaration in declarations {
+        let invalid = || {
+            SchemeError::syntax("define-library", format!("invalid declaration {}", declaration))
+        };
+
+        let list = declaration.as_list().map_err(|_| invalid())?;
+        let keyword = match list.s_car() {
+            Some(SExpr::Symbol(keyword)) => keyword.to_string(),
+            _ => return Err(invalid()),
+        };
+        let rest = list.iter().skip(1);
+
+        match keyword.as_str() {
+            "export" => {
+                for spec in rest {
+                    let export = match spec {
+                        SExpr::Symbol(name) => (name.to_string(), name.to_string()),
+                        SExpr::Pair(_) => {
+                            match identifiers("define-library", spec.as_list()?.iter()) {
+                                Ok(names) if names.len() == 3 && names[0] == "rename" => {
+                                    (names[1].clone(), names[2].clone())
+                                }
+                                _ => return Err(invalid()),
+                            }
+                        }
+                        _ => return Err(invalid()),
+                    };
+                    exports.push(export);
+                }
+            }
+            "import" => import(rest, env.clone(), library_env)?,
+            "begin" => {
+                for expression in rest {
+                    eval(expression, library_env.clone())?;
+                }
+            }
+            "include" | "include-ci" => {
+                let form = match keyword.as_str() {
+                    "include" => SpecialForm::INCLUDE,
+                    _ => SpecialForm::INCLUDE_CI,
+                };
+                let expression = SExpr::cons(SExpr::Keyword(form), SExpr::list(rest.cloned()));
+                eval(&expression, library_env.clone())?;
+            }
+            "include-library-declarations" => {
+                for arg in rest {
+                    let path = match arg {
+                        SExpr::String(path) => resolve_source_path(&path.access()?),
+                        other => {
+                            return Err(SchemeError::wrong_type(
+                                "define-library",
+                                other,
+                                "a string",
+                            ))
+                        }
+                    };
+
+                    let data = read_source_file(&path, "define-library", env, false)?;
+                    with_source_file(&path, || {
+                        let declarations: Vec<SExpr> =
+                            data.iter().map(|datum| datum.expr.clone()).collect();
+                        library_declarations(&declarations, env, library_env, exports)
+                    })?;
+                }
+            }
+            "cond-expand" => {
+                for clause in rest {
+                    let clause = clause.as_list().map_err(|_| invalid())?;
+                    let Some(requirement) = clause.s_car() else {
+                        return Err(SchemeError::syntax("cond-expand", "empty clause"));
+                    };
+
+                    if requirement.symbol_is("else")? || is_requirement_fulfilled(requirement, 0)? {
+                        let declarations: Vec<SExpr> = clause.iter().skip(1).cloned().collect();
+                        library_declarations(&declarations, env, library_env, exports)?;
+                        break;
+                    }
+                }
             }
+            _ => return Err(invalid()),
         }
     }
 
-    Ok(args.last().unwrap().clone())
+    Ok(())
+}
+
+pub fn r_define_library(args: ProcedureArgs, env: ProcedureEnv) -> ProcedureOutput {
+    let Some(name) = args.s_car() else {
+        return Err(SchemeError::arity("define-library", "at least 1 argument", 0));
+    };
+
+    let name = library_name("define-library", name)?;
+    let mut library_env = Environment::new();
+    library_env.set_settings(settings(&env)?);
+    let library_env = EnvAccessor::new(library_env);
+    let mut exports = vec![];
+    let declarations: Vec<SExpr> = args.iter().skip(1).cloned().collect();
+    library_declarations(&declarations, &env, &library_env, &mut exports)?;
+
+    let library = Library { env: library_env, exports };
+    Environment::get_root(env)?.access_mut()?.define_library(&name, library);
+
+    Ok(SExpr::Unspecified)
 }
 
 #[cfg(test)]
 mod special_forms_tests {
-    use crate::core::builtins::EnvAccessor;
+    use crate::core::builtins::{EnvAccessor, Environment};
 
     use super::*;
 
     #[test]
     fn test_special_form_unquote() {
         let env = EnvAccessor::new(Environment::new());
-        let list = SExpr::List(SchemeList::new(ListImplementation::from_iter([
-            SExpr::from(1),
-            SExpr::from(2),
-            SExpr::from(3),
-        ])));
+        let list = SExpr::list([SExpr::from(1), SExpr::from(2), SExpr::from(3)]);
         let list_name = "l";
 
-        let def = env.access_mut().define(list_name, &list);
+        let def = env.access_mut().unwrap().define(&Symbol::new(list_name), &list);
         assert!(def.is_ok());
 
-        let args = ListImplementation::from_iter([SExpr::Symbol(list_name.to_string())]);
-        let res = r_unquote(args, env);
+        let unquote = SExpr::list([SExpr::symbol("unquote"), SExpr::symbol(list_name)]);
+        let args = ListImplementation::from_iter([unquote]);
+        let res = r_quasiquote(args, env.clone()).and_then(|expansion| eval(&expansion, env));
 
         assert!(res.is_ok());
         let res = res.unwrap().as_list().unwrap();
         let list = list.as_list().unwrap();
 
-        assert_eq!(res.s_ref(0).unwrap().as_int(), list.s_ref(0).unwrap().as_int());
-        assert_eq!(res.s_ref(1).unwrap().as_int(), list.s_ref(1).unwrap().as_int());
-        assert_eq!(res.s_ref(2).unwrap().as_int(), list.s_ref(2).unwrap().as_int());
+        assert_eq!(
+            res.s_ref(0).unwrap().as_int().unwrap(),
+            list.s_ref(0).unwrap().as_int().unwrap()
+        );
+        assert_eq!(
+            res.s_ref(1).unwrap().as_int().unwrap(),
+            list.s_ref(1).unwrap().as_int().unwrap()
+        );
+        assert_eq!(
+            res.s_ref(2).unwrap().as_int().unwrap(),
+            list.s_ref(2).unwrap().as_int().unwrap()
+        );
     }
 }
@@ -1,27 +1,29 @@
 use super::{
+    index_argument,
     s_list::SList,
     s_number::NativeInt,
     s_procedure::{ProcedureArgs, ProcedureEnv, ProcedureOutput},
-    Accessor, SExpr, SchemeNumber, SchemeString,
+    Accessor, ErrorKind, SExpr, SchemeError, SchemeNumber, SchemeString,
 };
 
 pub fn r_string(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
     if args.iter().any(|arg| !arg.is_char().unwrap()) {
-        return Err("Exception in string: one or more arguments are not characters".to_string());
+        return Err(SchemeError::new(
+            ErrorKind::WrongType,
+            "one or more arguments are not characters",
+        )
+        .in_procedure("string"));
     }
 
     match args.s_len() {
-        0 => Err(format!(
-            "Exception in string: expected at least 1 argument, found {}",
-            args.s_len()
-        )),
-        1 => Ok(SExpr::String(SchemeString::new(args.s_car().unwrap().to_string()))),
+        0 => Err(SchemeError::arity("string", "at least 1 argument", args.s_len())),
+        1 => Ok(SExpr::String(SchemeString::new(args.s_car().unwrap().to_string().into()))),
         2.. => {
             let mut output = String::new();
             for arg in args {
                 output.push(arg.as_char().unwrap());
             }
-            Ok(SExpr::String(SchemeString::new(output)))
+            Ok(SExpr::String(SchemeString::new(output.into())))
         }
     }
 }
@@ -29,35 +31,30 @@ pub fn r_string(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
 pub fn r_make_string(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
     let length = args.s_len();
     if length != 1 && length != 2 {
-        return Err(format!(
-            "Exception in make-string: expected 1 or 2 arguments, found {}",
-            length
-        ));
+        return Err(SchemeError::arity("make-string", "1 or 2 arguments", length));
     }
 
-    match args.s_car().unwrap() {
-        SExpr::Number(n) => {
-            let n = n.to_int().unwrap();
-            let mut output = String::new();
-            let character = if length == 2 {
-                match args.s_cadr().unwrap() {
-                    SExpr::Char(c) => *c,
-                    other => {
-                        return Err(format!("Exception in make-string: {} is not a char", other))
-                    }
-                }
-            } else {
-                ' '
-            };
-
-            for _ in 0..n {
-                output.push(character);
-            }
-
-            Ok(SExpr::String(SchemeString::new(output)))
+    let n = index_argument("make-string", args.s_car().unwrap())?;
+    let character = if length == 2 {
+        match args.s_cadr().unwrap() {
+            SExpr::Char(c) => *c,
+            other => return Err(SchemeError::wrong_type("make-string", other, "a char")),
         }
-        other => Err(format!("Exception in make-string: {} is not a number", other)),
+    } else {
+        ' '
+    };
+
+    let mut output = String::new();
+    if n.checked_mul(character.len_utf8()).is_none_or(|size| output.try_reserve(size).is_err()) {
+        return Err(SchemeError::new(
+            ErrorKind::OutOfRange,
+            format!("cannot allocate a string of length {}", n),
+        )
+        .in_procedure("make-string"));
     }
+
+    output.extend(std::iter::repeat_n(character, n));
+    Ok(SExpr::String(SchemeString::new(output.into())))
 }
 
 pub fn r_string_append(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
@@ -65,118 +62,111 @@ pub fn r_string_append(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput
 
     for arg in args {
         match arg {
-            SExpr::String(string) => output.push_str(string.access().as_str()),
-            other => return Err(format!("Exception in string-append: {} is not a string", other)),
+            SExpr::String(string) => output.push_str(string.access()?.as_str()),
+            other => return Err(SchemeError::wrong_type("string-append", &other, "a string")),
         }
     }
 
-    Ok(SExpr::String(SchemeString::new(output)))
+    Ok(SExpr::String(SchemeString::new(output.into())))
 }
 
 pub fn r_string_ref(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
     let length = args.s_len();
     if length != 2 {
-        return Err(format!("Exception in string-ref: expected 2 arguments, found {}", length));
+        return Err(SchemeError::arity("string-ref", "2 arguments", length));
     }
 
     match args.s_car().unwrap() {
-        SExpr::String(string) => match args.s_cadr().unwrap() {
-            SExpr::Number(index) => {
-                let index = index.to_int().unwrap() as usize;
-                let is_in_range = index < string.access().len();
-
-                if is_in_range {
-                    let character = string.access().chars().nth(index).unwrap();
-                    Ok(SExpr::Char(character))
-                } else {
-                    Err("Exception in string-ref: index out of range".to_string())
-                }
+        SExpr::String(string) => {
+            let index = index_argument("string-ref", args.s_cadr().unwrap())?;
+            let character = string.access()?.chars().nth(index);
+
+            match character {
+                Some(character) => Ok(SExpr::Char(character)),
+                None => Err(SchemeError::new(ErrorKind::OutOfRange, "index out of range")
+                    .in_procedure("string-ref")),
             }
-            other => Err(format!("Exception in string-ref: {} is not a valid index", other)),
-        },
-        other => Err(format!("Exception in string-ref: {} is not a string", other)),
+        }
+        other => Err(SchemeError::wrong_type("string-ref", other, "a string")),
     }
 }
 
 pub fn r_string_set(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
     let length = args.s_len();
     if length != 3 {
-        return Err(format!(
-            "Exception in string-set!: expected 3 arguments, found {}",
-            args.len()
-        ));
+        return Err(SchemeError::arity("string-set!", "3 arguments", args.len()));
     }
 
     match args.s_car().unwrap() {
-        SExpr::String(string) => match args.s_cadr().unwrap() {
-            SExpr::Number(index) => {
-                let index = index.to_int().unwrap() as usize;
-                let is_in_range = index < string.access().len();
-
-                if is_in_range {
-                    match &args.s_ref(2).unwrap() {
-                        SExpr::Char(character) => {
-                            let replacement = character.to_string();
-                            string
-                                .access_mut()
-                                .replace_range(index..index + 1, replacement.as_str());
-
-                            let output = string.access().clone();
-                            Ok(SExpr::String(SchemeString::new(output)))
-                        }
-                        other => Err(format!("Exception in string-set!: {} is not a char", other)),
-                    }
-                } else {
-                    Err("Exception in string-set!: index out of range".to_string())
+        SExpr::String(string) if !string.access()?.is_mutable() => {
+            Err(SchemeError::wrong_type("string-set!", args.s_car().unwrap(), "a mutable string"))
+        }
+        SExpr::String(string) => {
+            let index = index_argument("string-set!", args.s_cadr().unwrap())?;
+            let character = match args.s_ref(2).unwrap() {
+                SExpr::Char(character) => *character,
+                other => return Err(SchemeError::wrong_type("string-set!", other, "a char")),
+            };
+
+            // The index counts characters, which may span several bytes
+            let range =
+                string.access()?.char_indices().nth(index).map(|(i, c)| i..i + c.len_utf8());
+            match range {
+                Some(range) => {
+                    string.access_mut()?.replace_range(range, character.to_string().as_str());
+
+                    let output = string.access()?.clone();
+                    Ok(SExpr::String(SchemeString::new(output)))
                 }
+                None => Err(SchemeError::new(ErrorKind::OutOfRange, "index out of range")
+                    .in_procedure("string-set!")),
             }
-            other => Err(format!("Exception in string-set!: {} is not a valid index", other)),
-        },
-        other => Err(format!("Exception in string-set!: {} is not a string", other)),
+        }
+        other => Err(SchemeError::wrong_type("string-set!", other, "a string")),
     }
 }
 
 pub fn r_string_upcase(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
     let length = args.s_len();
     if length != 1 {
-        return Err(format!("Exception in string-upcase: expected 1 argument, found {}", length));
+        return Err(SchemeError::arity("string-upcase", "1 argument", length));
     }
 
     match args.s_car().unwrap() {
         SExpr::String(string) => {
-            let output = string.access().to_uppercase();
-            Ok(SExpr::String(SchemeString::new(output)))
+            let output = string.access()?.to_uppercase();
+            Ok(SExpr::String(SchemeString::new(output.into())))
         }
-        other => Err(format!("Exception in string-upcase: {} is not a string", other)),
+        other => Err(SchemeError::wrong_type("string-upcase", other, "a string")),
     }
 }
 
 pub fn r_string_downcase(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
     let length = args.s_len();
     if length != 1 {
-        return Err(format!("Exception in string-downcase: expected 1 argument, found {}", length));
+        return Err(SchemeError::arity("string-downcase", "1 argument", length));
     }
 
     match args.s_car().unwrap() {
         SExpr::String(string) => {
-            let output = string.access().to_lowercase();
-            Ok(SExpr::String(SchemeString::new(output)))
+            let output = string.access()?.to_lowercase();
+            Ok(SExpr::String(SchemeString::new(output.into())))
         }
-        other => Err(format!("Exception in string-downcase: {} is not a string", other)),
+        other => Err(SchemeError::wrong_type("string-downcase", other, "a string")),
     }
 }
 
 pub fn r_string_length(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
     let length = args.s_len();
     if length != 1 {
-        return Err(format!("Exception in string-length: expected 1 argument, found {}", length));
+        return Err(SchemeError::arity("string-length", "1 argument", length));
     }
 
     match args.s_car().unwrap() {
         SExpr::String(string) => {
-            let length = string.access().len();
+            let length = string.access()?.len();
             Ok(SExpr::Number(SchemeNumber::Int(length as NativeInt)))
         }
-        other => Err(format!("Exception in string-length: {} is not a string", other)),
+        other => Err(SchemeError::wrong_type("string-length", other, "a string")),
     }
 }
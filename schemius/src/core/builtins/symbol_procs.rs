@@ -0,0 +1,60 @@
+use super::{
+    s_list::SList,
+    s_procedure::{ProcedureArgs, ProcedureEnv, ProcedureOutput},
+    Accessor, SExpr, SString, SchemeError, SchemeString, Symbol,
+};
+
+pub fn r_string_to_symbol(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
+    if args.s_len() != 1 {
+        return Err(SchemeError::arity("string->symbol", "1 argument", args.s_len()));
+    }
+
+    match args.s_car().unwrap() {
+        SExpr::String(string) => Ok(SExpr::symbol(&string.access()?)),
+        other => Err(SchemeError::wrong_type("string->symbol", other, "a string")),
+    }
+}
+
+pub fn r_symbol_to_string(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
+    if args.s_len() != 1 {
+        return Err(SchemeError::arity("symbol->string", "1 argument", args.s_len()));
+    }
+
+    match args.s_car().unwrap() {
+        SExpr::Symbol(symbol) => {
+            Ok(SExpr::String(SchemeString::new(SString::immutable(symbol.as_str()))))
+        }
+        other => Err(SchemeError::wrong_type("symbol->string", other, "a symbol")),
+    }
+}
+
+pub fn r_symbol_equal(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
+    if args.s_len() < 2 {
+        return Err(SchemeError::arity("symbol=?", "at least 2 arguments", args.s_len()));
+    }
+
+    let mut symbols = vec![];
+    for arg in args.iter() {
+        match arg {
+            SExpr::Symbol(symbol) => symbols.push(symbol),
+            other => return Err(SchemeError::wrong_type("symbol=?", other, "a symbol")),
+        }
+    }
+
+    Ok(SExpr::Boolean(symbols.windows(2).all(|pair| pair[0] == pair[1])))
+}
+
+/// Makes a fresh uninterned symbol, named after an optional prefix given as a string or symbol.
+pub fn r_gensym(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
+    let prefix = match args.s_len() {
+        0 => "g".to_string(),
+        1 => match args.s_car().unwrap() {
+            SExpr::String(string) => string.access()?.to_string(),
+            SExpr::Symbol(symbol) => symbol.to_string(),
+            other => return Err(SchemeError::wrong_type("gensym", other, "a string or symbol")),
+        },
+        n => return Err(SchemeError::arity("gensym", "0 or 1 arguments", n)),
+    };
+
+    Ok(SExpr::Symbol(Symbol::generate(&prefix)))
+}
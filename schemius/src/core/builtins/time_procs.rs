@@ -0,0 +1,61 @@
+use std::{
+    sync::LazyLock,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use super::{
+    s_number::{NativeBigInt, NativeFloat, NativeInt, SNumber},
+    s_procedure::{ProcedureArgs, ProcedureEnv, ProcedureOutput},
+    ErrorKind, SExpr, SchemeError,
+};
+
+/// Seconds TAI was ahead of UTC after the last leap second (2017-01-01).
+const TAI_UTC_OFFSET: f64 = 37.0;
+const JIFFIES_PER_SECOND: NativeInt = 1_000_000;
+
+static JIFFY_EPOCH: LazyLock<Instant> = LazyLock::new(Instant::now);
+
+pub fn r_current_second(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
+    if !args.is_empty() {
+        return Err(SchemeError::arity("current-second", "0 arguments", args.len()));
+    }
+
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(elapsed) => Ok(SExpr::from((elapsed.as_secs_f64() + TAI_UTC_OFFSET) as NativeFloat)),
+        Err(e) => {
+            Err(SchemeError::new(ErrorKind::Io, e.to_string()).in_procedure("current-second"))
+        }
+    }
+}
+
+pub fn r_current_jiffy(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
+    if !args.is_empty() {
+        return Err(SchemeError::arity("current-jiffy", "0 arguments", args.len()));
+    }
+
+    let jiffies = JIFFY_EPOCH.elapsed().as_micros();
+
+    match NativeInt::try_from(jiffies) {
+        Ok(jiffies) => Ok(SExpr::from(jiffies)),
+        Err(_) => Ok(SExpr::from(NativeBigInt::from(jiffies))),
+    }
+}
+
+/// The time elapsed since `jiffy`, a value of `current-jiffy`.
+pub fn elapsed_since(jiffy: &SNumber) -> Option<Duration> {
+    let then = match jiffy {
+        SNumber::Int(jiffy) => u64::try_from(*jiffy).ok()?,
+        SNumber::BigInt(jiffy) => u64::try_from(jiffy).ok()?,
+        _ => return None,
+    };
+
+    JIFFY_EPOCH.elapsed().checked_sub(Duration::from_micros(then))
+}
+
+pub fn r_jiffies_per_second(args: ProcedureArgs, _: ProcedureEnv) -> ProcedureOutput {
+    if !args.is_empty() {
+        return Err(SchemeError::arity("jiffies-per-second", "0 arguments", args.len()));
+    }
+
+    Ok(SExpr::from(JIFFIES_PER_SECOND))
+}
@@ -0,0 +1,996 @@
+use std::ptr::fn_addr_eq;
+use std::sync::Arc;
+
+use super::{
+    accessor::{Accessor, Shared},
+    builtins::SpecialForm,
+    environment::SchemeEnvironment,
+    error::SchemeError,
+    evaluator::{do_variable, is_keyword, let_bindings},
+    reader::Span,
+    s_expression::*,
+};
+
+/// How many expressions deep the compiler recurses. Those nested deeper are compiled when they
+/// are evaluated, each part of an expression taking no more native stack than this.
+pub const MAX_NESTING_DEPTH: usize = 256;
+
+/// Compiled code, shared by every closure and frame running it.
+pub type Code = Shared<Chunk>;
+
+/// An instruction of the virtual machine, whose operands index the tables of its chunk.
+/// Instructions push and pop values on a stack shared by all the frames of a run.
+#[derive(Clone, Copy, Debug)]
+pub enum Instruction {
+    /// Pushes a constant.
+    Constant(u32),
+    /// Pushes the value held at the given depth and index in the frames of the enclosing scopes.
+    Local(u32, u32),
+    /// Pushes the value of the variable with the given name, which is not held by the frames of
+    /// the given number of enclosing scopes.
+    Free(u32, u32),
+    /// Pops a value, defines a variable with it and pushes the result of `define`.
+    Define(u32),
+    /// Pops a value, assigns it to a local variable and pushes the result of `set!`.
+    SetLocal(u32, u32),
+    /// Pops a value, assigns it to a variable and pushes the result of `set!`.
+    Set(u32),
+    /// Pops a value and holds it at the given index in the frame of the innermost scope.
+    Bind(u32),
+    /// Pushes a closure of a lambda over the current environment.
+    Closure(u32),
+    Pop,
+    Dup,
+    /// Pops a value and inserts it below the given number of values.
+    Insert(u32),
+    Jump(u32),
+    /// Pops a value and jumps if it is false.
+    JumpIfFalse(u32),
+    /// Pops a value and jumps if it is true.
+    JumpIfTrue(u32),
+    /// Jumps keeping the value on top if it is false, popping it otherwise.
+    JumpIfFalseOrPop(u32),
+    /// Jumps keeping the value on top if it is true, popping it otherwise.
+    JumpIfTrueOrPop(u32),
+    /// Jumps unless the value on top is `eqv?` to an element of the list in a constant.
+    JumpUnlessMember(u32, u32),
+    /// Calls the procedure found below the given number of arguments, with the name it was
+    /// called by if any.
+    Call(u32, Option<u32>),
+    /// Calls a procedure in place of the current frame.
+    TailCall(u32, Option<u32>),
+    /// Pops the value of the current frame and returns it to the frame that called it.
+    Return,
+    /// Makes the current environment the parent of a new innermost scope, whose frame holds the
+    /// variables listed by the given scope of the chunk.
+    EnterScope(u32),
+    /// Goes back to the environment enclosing the given number of innermost scopes.
+    LeaveScope(u32),
+    /// Calls a special form with the list of operands in a constant, pushing its result.
+    Form(u32, u32),
+    /// Pops an expression and evaluates it in the current environment.
+    Evaluate,
+    /// Pops an expression and evaluates it in place of the current frame.
+    TailEvaluate,
+    /// Raises an error, for malformed expressions that are only errors once evaluated.
+    Raise(u32),
+    /// Pushes whether the variables of the root environment bound to a primitive were bound to
+    /// something else the given number of times.
+    Unchanged(u32),
+}
+
+/// The instructions of a body along with the values they refer to.
+#[derive(Debug, Default)]
+pub struct Chunk {
+    pub instructions: Vec<Instruction>,
+    pub constants: Vec<SExpr>,
+    pub names: Vec<Symbol>,
+    pub lambdas: Vec<Shared<Lambda>>,
+    pub forms: Vec<SpecialFormSignature>,
+    pub errors: Vec<SchemeError>,
+    /// The variables held by the frames of the scopes entered by the instructions.
+    pub scopes: Vec<Arc<[Symbol]>>,
+    /// The variables held by the frame of a call, starting with the parameters.
+    pub locals: Arc<[Symbol]>,
+    /// Where the calls emitted at the given instructions were read from, in instruction order.
+    pub spans: Vec<(u32, Arc<Span>)>,
+}
+
+impl Chunk {
+    /// Where the call emitted at instruction `pc` was read from, if it was read.
+    pub fn call_span(&self, pc: usize) -> Option<Span> {
+        let index = self.spans.binary_search_by_key(&(pc as u32), |(at, _)| *at).ok()?;
+        Some(self.spans[index].1.as_ref().clone())
+    }
+}
+
+/// Compiles `expression` to code returning its value. Special forms are recognized by looking
+/// their keywords up in `env`.
+pub fn compile(expression: &SExpr, env: &ProcedureEnv) -> Code {
+    let mut compiler = Compiler::new(env, vec![], 0);
+    compiler.expression(expression, true);
+    Shared::new(compiler.chunk)
+}
+
+/// Compiles the body of `lambda`, whose closures are created in `env`.
+pub fn compile_lambda(lambda: &Lambda, env: &ProcedureEnv) -> Code {
+    compile_body(env, vec![], lambda.params.clone(), &lambda.body, 0)
+}
+
+/// Compiles the body of a procedure taking `params`, whose closures are created within
+/// `scopes`, nested `depth` expressions deep.
+fn compile_body(
+    env: &ProcedureEnv, mut scopes: Vec<Vec<Symbol>>, params: Vec<Symbol>,
+    body: &ListImplementation, depth: usize,
+) -> Code {
+    scopes.push(params);
+
+    let mut compiler = Compiler::new(env, scopes, depth);
+    compiler.body(&Vec::from_iter(body.iter().cloned()), true);
+
+    compiler.chunk.locals = compiler.scopes.pop().unwrap().into();
+    Shared::new(compiler.chunk)
+}
+
+/// Forms whose result is their value rather than an expression to evaluate.
+pub fn returns_value(form: SpecialFormSignature) -> bool {
+    [
+        SpecialForm::DEFINE,
+        SpecialForm::DEFINE_LIBRARY,
+        SpecialForm::IMPORT,
+        SpecialForm::QUOTE,
+        SpecialForm::UNCHANGED,
+    ]
+    .into_iter()
+    .any(|other| fn_addr_eq(form, other))
+}
+
+/// Whether `form` includes files, which are spliced in its place when it is compiled.
+fn is_include(form: SpecialFormSignature) -> bool {
+    fn_addr_eq(form, SpecialForm::INCLUDE) || fn_addr_eq(form, SpecialForm::INCLUDE_CI)
+}
+
+/// The symbols of a proper list of parameters.
+fn parameters(params: &SExpr) -> Option<Vec<Symbol>> {
+    params
+        .as_list()
+        .ok()?
+        .into_iter()
+        .map(|param| match param {
+            SExpr::Symbol(name) => Some(name.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The names bound by the definitions at the top of `body`.
+pub fn definitions(body: &[SExpr]) -> Vec<Symbol> {
+    body.iter()
+        .filter_map(|expression| match expression.as_list() {
+            Ok(form) if is_keyword(form.s_car(), "define") => match form.s_cadr() {
+                Some(SExpr::Symbol(name)) => Some(name.clone()),
+                Some(signature @ SExpr::Pair(_)) => match signature.car() {
+                    Some(SExpr::Symbol(name)) => Some(name),
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+struct Compiler<'a> {
+    /// Where names not bound by the code being compiled are looked up.
+    env: &'a ProcedureEnv,
+    /// The variables held by the frames of the enclosing lambdas and scopes, innermost last.
+    /// These shadow any keyword of the same name.
+    scopes: Vec<Vec<Symbol>>,
+    chunk: Chunk,
+    /// How many expressions enclose the one being compiled.
+    depth: usize,
+}
+
+impl<'a> Compiler<'a> {
+    fn new(env: &'a ProcedureEnv, scopes: Vec<Vec<Symbol>>, depth: usize) -> Self {
+        Self { env, scopes, chunk: Chunk::default(), depth }
+    }
+
+    fn emit(&mut self, instruction: Instruction) -> usize {
+        self.chunk.instructions.push(instruction);
+        self.chunk.instructions.len() - 1
+    }
+
+    /// Points the jump emitted at `at` to the next instruction.
+    fn patch(&mut self, at: usize) {
+        let target = self.chunk.instructions.len() as u32;
+        self.chunk.instructions[at] = match self.chunk.instructions[at] {
+            Instruction::Jump(_) => Instruction::Jump(target),
+            Instruction::JumpIfFalse(_) => Instruction::JumpIfFalse(target),
+            Instruction::JumpIfTrue(_) => Instruction::JumpIfTrue(target),
+            Instruction::JumpIfFalseOrPop(_) => Instruction::JumpIfFalseOrPop(target),
+            Instruction::JumpIfTrueOrPop(_) => Instruction::JumpIfTrueOrPop(target),
+            Instruction::JumpUnlessMember(data, _) => Instruction::JumpUnlessMember(data, target),
+            other => other,
+        };
+    }
+
+    fn constant(&mut self, value: SExpr) -> u32 {
+        self.chunk.constants.push(value);
+        self.chunk.constants.len() as u32 - 1
+    }
+
+    fn name(&mut self, name: &Symbol) -> u32 {
+        match self.chunk.names.iter().position(|other| other == name) {
+            Some(index) => index as u32,
+            None => {
+                self.chunk.names.push(name.clone());
+                self.chunk.names.len() as u32 - 1
+            }
+        }
+    }
+
+    fn push_constant(&mut self, value: SExpr, tail: bool) {
+        let constant = self.constant(value);
+        self.emit(Instruction::Constant(constant));
+        self.ret(tail);
+    }
+
+    /// Returns the value on top from the frame if it is that of an expression in tail position.
+    fn ret(&mut self, tail: bool) {
+        if tail {
+            self.emit(Instruction::Return);
+        }
+    }
+
+    fn raise(&mut self, error: SchemeError) {
+        self.chunk.errors.push(error);
+        let error = self.chunk.errors.len() as u32 - 1;
+        self.emit(Instruction::Raise(error));
+    }
+
+    /// The depth and index of the frame holding the variable `name`, unless no enclosing scope
+    /// binds it.
+    fn resolve(&self, name: &Symbol) -> Option<(u32, u32)> {
+        self.scopes.iter().rev().enumerate().find_map(|(depth, scope)| {
+            let index = scope.iter().position(|bound| bound == name)?;
+            Some((depth as u32, index as u32))
+        })
+    }
+
+    /// The special form `name` stands for, unless a variable of the same name shadows it.
+    fn keyword(&self, name: &Symbol) -> Option<SpecialFormSignature> {
+        if self.resolve(name).is_some() {
+            return None;
+        }
+
+        match self.env.access().ok()?.get(name) {
+            Some(SExpr::Keyword(form)) => Some(form),
+            _ => None,
+        }
+    }
+
+    fn call(&mut self, argc: usize, name: Option<&Symbol>, tail: bool) -> usize {
+        let name = name.map(|name| self.name(name));
+        match tail {
+            true => self.emit(Instruction::TailCall(argc as u32, name)),
+            false => self.emit(Instruction::Call(argc as u32, name)),
+        }
+    }
+
+    /// Compiles `expression`, whose value is returned from the frame if it is in `tail`
+    /// position and pushed otherwise.
+    fn expression(&mut self, expression: &SExpr, tail: bool) {
+        if self.depth == MAX_NESTING_DEPTH && matches!(expression, SExpr::Pair(_)) {
+            let constant = self.constant(expression.clone());
+            self.emit(Instruction::Constant(constant));
+            match tail {
+                true => _ = self.emit(Instruction::TailEvaluate),
+                false => _ = self.emit(Instruction::Evaluate),
+            }
+            return;
+        }
+
+        self.depth += 1;
+        self.nested_expression(expression, tail);
+        self.depth -= 1;
+    }
+
+    fn nested_expression(&mut self, expression: &SExpr, tail: bool) {
+        match expression {
+            SExpr::Symbol(name) => {
+                match self.resolve(name) {
+                    Some((depth, index)) => self.emit(Instruction::Local(depth, index)),
+                    None => {
+                        let name = self.name(name);
+                        self.emit(Instruction::Free(name, self.scopes.len() as u32))
+                    }
+                };
+                self.ret(tail);
+            }
+            SExpr::Null => self.raise(SchemeError::syntax("eval", "empty combination ()")),
+            SExpr::Pair(pair) => {
+                let (operator, args) = match pair.access() {
+                    Ok(pair) => (pair.car.clone(), pair.cdr.as_list()),
+                    Err(e) => return self.raise(e),
+                };
+
+                let Ok(args) = args else {
+                    let message = format!("improper combination {}", expression);
+                    return self.raise(SchemeError::syntax("eval", message));
+                };
+
+                let form = match operator {
+                    SExpr::Symbol(ref name) => self.keyword(name),
+                    SExpr::Keyword(form) => Some(form),
+                    _ => None,
+                };
+
+                match form {
+                    Some(form) => self.special_form(form, args, tail),
+                    None => self.application(&operator, args, expression.span(), tail),
+                }
+            }
+            other => self.push_constant(other.clone(), tail),
+        }
+    }
+
+    fn application(
+        &mut self, operator: &SExpr, args: ListImplementation, span: Option<Arc<Span>>, tail: bool,
+    ) {
+        self.expression(operator, false);
+        for arg in args.iter() {
+            self.expression(arg, false);
+        }
+
+        let name = match operator {
+            SExpr::Symbol(name) => Some(name),
+            _ => None,
+        };
+        let at = self.call(args.s_len(), name, tail);
+        if let Some(span) = span {
+            self.chunk.spans.push((at as u32, span));
+        }
+    }
+
+    /// Compiles `body` in order, the last expression being in tail position.
+    fn sequence(&mut self, body: &[SExpr], tail: bool) {
+        let Some((last, leading)) = body.split_last() else {
+            return self.raise(SchemeError::arity("begin", "at least 1 argument", 0));
+        };
+
+        for expression in leading {
+            self.expression(expression, false);
+            self.emit(Instruction::Pop);
+        }
+        self.expression(last, tail);
+    }
+
+    /// Compiles the body of a lambda or of a let form, whose internal definitions are held by
+    /// the frame of the innermost scope.
+    fn body(&mut self, body: &[SExpr], tail: bool) {
+        let body = self.splice_includes(body);
+
+        if let Some(scope) = self.scopes.last_mut() {
+            for name in definitions(&body) {
+                if !scope.contains(&name) {
+                    scope.push(name);
+                }
+            }
+        }
+
+        self.sequence(&body, tail);
+    }
+
+    /// Replaces the `include` forms of `body` by the contents of the files they include, so
+    /// that the definitions these hold are internal ones. Forms failing to include their files
+    /// are left to raise the error when compiled.
+    fn splice_includes(&self, body: &[SExpr]) -> Vec<SExpr> {
+        let mut spliced = vec![];
+
+        for expression in body {
+            let form = match expression.car() {
+                Some(SExpr::Symbol(name)) => self.keyword(&name),
+                Some(SExpr::Keyword(form)) => Some(form),
+                _ => None,
+            };
+
+            let contents = form
+                .filter(|form| is_include(*form))
+                .and_then(|form| form(expression.cdr()?.as_list().ok()?, self.env.clone()).ok())
+                .and_then(|contents| contents.cdr()?.as_list().ok());
+
+            match contents {
+                Some(contents) => spliced.extend(contents),
+                None => spliced.push(expression.clone()),
+            }
+        }
+
+        spliced
+    }
+
+    /// Compiles a scope whose frame holds `names`, with `contents` compiled while they are
+    /// visible. `contents` is given the scope to enter, whose frame also holds the variables
+    /// defined within it.
+    fn scoped(&mut self, names: Vec<Symbol>, contents: impl FnOnce(&mut Self, u32)) {
+        self.chunk.scopes.push(Arc::new([]));
+        let scope = self.chunk.scopes.len() - 1;
+
+        self.scopes.push(names);
+        contents(self, scope as u32);
+        self.chunk.scopes[scope] = self.scopes.pop().unwrap().into();
+    }
+
+    fn lambda(&mut self, params: Vec<Symbol>, body: ListImplementation) -> u32 {
+        let code = compile_body(self.env, self.scopes.clone(), params.clone(), &body, self.depth);
+        self.chunk.lambdas.push(Lambda::compiled(params, body, code));
+        self.chunk.lambdas.len() as u32 - 1
+    }
+
+    fn special_form(&mut self, form: SpecialFormSignature, args: ListImplementation, tail: bool) {
+        let args: Vec<SExpr> = args.into_iter().collect();
+
+        if fn_addr_eq(form, SpecialForm::QUOTE) && args.len() == 1 {
+            self.push_constant(args[0].clone(), tail)
+        } else if fn_addr_eq(form, SpecialForm::IF) {
+            self.compile_if(&args, tail)
+        } else if fn_addr_eq(form, SpecialForm::BEGIN) {
+            self.sequence(&args, tail)
+        } else if is_include(form) || fn_addr_eq(form, SpecialForm::QUASIQUOTE) {
+            // Both are rewritten into the expression compiled in their place
+            match form(ListImplementation::from_iter(args), self.env.clone()) {
+                Ok(expression) => self.expression(&expression, tail),
+                Err(e) => self.raise(e),
+            }
+        } else if fn_addr_eq(form, SpecialForm::DEFINE) {
+            if !self.compile_define(&args, tail) {
+                self.call_form(form, args, tail)
+            }
+        } else if fn_addr_eq(form, SpecialForm::LAMBDA) {
+            if !self.compile_lambda(&args, tail) {
+                self.call_form(form, args, tail)
+            }
+        } else if fn_addr_eq(form, SpecialForm::SET) {
+            self.compile_set(&args, tail)
+        } else if fn_addr_eq(form, SpecialForm::LET) {
+            match args.first() {
+                Some(SExpr::Symbol(name)) => self.compile_named_let(name.clone(), &args, tail),
+                _ => self.compile_let("let", &args, tail),
+            }
+        } else if fn_addr_eq(form, SpecialForm::LET_STAR) {
+            self.compile_let("let*", &args, tail)
+        } else if fn_addr_eq(form, SpecialForm::LETREC) {
+            self.compile_let("letrec", &args, tail)
+        } else if fn_addr_eq(form, SpecialForm::LETREC_STAR) {
+            self.compile_let("letrec*", &args, tail)
+        } else if fn_addr_eq(form, SpecialForm::COND) {
+            self.compile_cond(&args, tail)
+        } else if fn_addr_eq(form, SpecialForm::CASE) {
+            self.compile_case(&args, tail)
+        } else if fn_addr_eq(form, SpecialForm::WHEN) {
+            self.compile_when("when", &args, false, tail)
+        } else if fn_addr_eq(form, SpecialForm::UNLESS) {
+            self.compile_when("unless", &args, true, tail)
+        } else if fn_addr_eq(form, SpecialForm::AND) {
+            self.compile_operands(&args, Instruction::JumpIfFalseOrPop(0), tail)
+        } else if fn_addr_eq(form, SpecialForm::OR) {
+            self.compile_operands(&args, Instruction::JumpIfTrueOrPop(0), tail)
+        } else if fn_addr_eq(form, SpecialForm::DO) {
+            self.compile_do(&args, tail)
+        } else if fn_addr_eq(form, SpecialForm::UNCHANGED) {
+            match args.as_slice() {
+                [SExpr::Number(SNumber::Int(rebound))] if u32::try_from(*rebound).is_ok() => {
+                    self.emit(Instruction::Unchanged(*rebound as u32));
+                    self.ret(tail)
+                }
+                _ => self.call_form(form, args, tail),
+            }
+        } else {
+            self.call_form(form, args, tail)
+        }
+    }
+
+    /// Compiles a call to the function of a form, whose result is evaluated unless it is a
+    /// value.
+    fn call_form(&mut self, form: SpecialFormSignature, args: Vec<SExpr>, tail: bool) {
+        self.chunk.forms.push(form);
+        let form_index = self.chunk.forms.len() as u32 - 1;
+        let operands = self.constant(SExpr::list(args));
+        self.emit(Instruction::Form(form_index, operands));
+
+        match returns_value(form) {
+            true => self.ret(tail),
+            false if tail => _ = self.emit(Instruction::TailEvaluate),
+            false => _ = self.emit(Instruction::Evaluate),
+        }
+    }
+
+    fn compile_if(&mut self, args: &[SExpr], tail: bool) {
+        if args.len() != 2 && args.len() != 3 {
+            return self.raise(SchemeError::arity("if", "two or three arguments", args.len()));
+        }
+
+        self.expression(&args[0], false);
+        let otherwise = self.emit(Instruction::JumpIfFalse(0));
+        self.expression(&args[1], tail);
+        let end = (!tail).then(|| self.emit(Instruction::Jump(0)));
+
+        self.patch(otherwise);
+        match args.get(2) {
+            Some(alternative) => self.expression(alternative, tail),
+            None => self.push_constant(SExpr::Ok, tail),
+        }
+        end.into_iter().for_each(|end| self.patch(end));
+    }
+
+    /// Compiles the definitions of a variable and of a procedure, leaving any other shape to
+    /// `define` itself.
+    fn compile_define(&mut self, args: &[SExpr], tail: bool) -> bool {
+        let name = match args {
+            [SExpr::Symbol(name), value, ..] => {
+                self.expression(value, false);
+                name.clone()
+            }
+            [signature @ SExpr::Pair(_), body @ ..] if !body.is_empty() => {
+                let (Some(SExpr::Symbol(name)), Some(params)) =
+                    (signature.car(), signature.cdr().as_ref().and_then(parameters))
+                else {
+                    return false;
+                };
+
+                let lambda =
+                    self.lambda(params, ListImplementation::from_iter(body.iter().cloned()));
+                self.emit(Instruction::Closure(lambda));
+                name
+            }
+            _ => return false,
+        };
+
+        self.define(&name);
+        self.ret(tail);
+        true
+    }
+
+    /// Defines `name` with the value on top, in the frame of the innermost scope if any.
+    fn define(&mut self, name: &Symbol) {
+        let Some(scope) = self.scopes.last_mut() else {
+            let name = self.name(name);
+            self.emit(Instruction::Define(name));
+            return;
+        };
+
+        let index = match scope.iter().position(|bound| bound == name) {
+            Some(index) => index,
+            None => {
+                scope.push(name.clone());
+                scope.len() - 1
+            }
+        };
+        self.emit(Instruction::Bind(index as u32));
+        self.push_constant(SExpr::Ok, false);
+    }
+
+    /// Compiles a lambda expression, leaving malformed ones for `lambda` to report.
+    fn compile_lambda(&mut self, args: &[SExpr], tail: bool) -> bool {
+        let (Some(params), [_, body @ ..]) = (args.first().and_then(parameters), args) else {
+            return false;
+        };
+        if body.is_empty() {
+            return false;
+        }
+
+        let lambda = self.lambda(params, ListImplementation::from_iter(body.iter().cloned()));
+        self.emit(Instruction::Closure(lambda));
+        self.ret(tail);
+        true
+    }
+
+    fn compile_set(&mut self, args: &[SExpr], tail: bool) {
+        match args {
+            [SExpr::Symbol(name), value] => {
+                self.expression(value, false);
+                match self.resolve(name) {
+                    Some((depth, index)) => self.emit(Instruction::SetLocal(depth, index)),
+                    None => {
+                        let name = self.name(name);
+                        self.emit(Instruction::Set(name))
+                    }
+                };
+                self.ret(tail);
+            }
+            [other, _] => self.raise(SchemeError::wrong_type("set!", other, "a symbol")),
+            _ => self.raise(SchemeError::arity("set!", "2 arguments", args.len())),
+        }
+    }
+
+    /// Compiles `let`, `let*`, `letrec` and `letrec*`, whose bodies are all in tail position.
+    fn compile_let(&mut self, form: &str, args: &[SExpr], tail: bool) {
+        if args.len() < 2 {
+            return self.raise(SchemeError::arity(form, "at least 2 arguments", args.len()));
+        }
+
+        let bindings = match let_bindings(form, &args[0]) {
+            Ok(bindings) => bindings,
+            Err(e) => return self.raise(e),
+        };
+        let body = &args[1..];
+
+        match form {
+            "let*" => {
+                self.sequential_bindings(&bindings, body, tail);
+                self.leave_scopes(bindings.len(), tail);
+            }
+            "letrec" | "letrec*" => {
+                let (names, inits): (Vec<Symbol>, Vec<SExpr>) = bindings.into_iter().unzip();
+
+                self.scoped(names, |compiler, scope| {
+                    compiler.emit(Instruction::EnterScope(scope));
+                    for index in 0..inits.len() {
+                        compiler.push_constant(SExpr::Unspecified, false);
+                        compiler.emit(Instruction::Bind(index as u32));
+                    }
+
+                    // Initializers are evaluated where the bound names are already visible
+                    for (index, init) in inits.iter().enumerate() {
+                        compiler.expression(init, false);
+                        compiler.emit(Instruction::Bind(index as u32));
+                    }
+
+                    compiler.body(body, tail);
+                });
+                self.leave_scopes(1, tail);
+            }
+            _ => {
+                let (names, inits): (Vec<Symbol>, Vec<SExpr>) = bindings.into_iter().unzip();
+
+                for init in inits.iter() {
+                    self.expression(init, false);
+                }
+
+                self.scoped(names, |compiler, scope| {
+                    compiler.emit(Instruction::EnterScope(scope));
+                    compiler.bind_reversed(inits.len());
+                    compiler.body(body, tail);
+                });
+                self.leave_scopes(1, tail);
+            }
+        }
+    }
+
+    /// Compiles the bindings of `let*` from the first one, each in a scope of its own nested in
+    /// that of the previous one.
+    fn sequential_bindings(&mut self, bindings: &[(Symbol, SExpr)], body: &[SExpr], tail: bool) {
+        let Some(((name, init), rest)) = bindings.split_first() else {
+            return self.body(body, tail);
+        };
+
+        self.expression(init, false);
+        self.scoped(vec![name.clone()], |compiler, scope| {
+            compiler.emit(Instruction::EnterScope(scope));
+            compiler.emit(Instruction::Bind(0));
+            compiler.sequential_bindings(rest, body, tail);
+        });
+    }
+
+    /// Holds the `count` values on top in the frame of the innermost scope, the topmost one
+    /// last.
+    fn bind_reversed(&mut self, count: usize) {
+        for index in (0..count).rev() {
+            self.emit(Instruction::Bind(index as u32));
+        }
+    }
+
+    /// Leaves `count` scopes once their body has a value, which code in tail position never
+    /// needs to as returning leaves them all.
+    fn leave_scopes(&mut self, count: usize, tail: bool) {
+        if !tail && count > 0 {
+            self.emit(Instruction::LeaveScope(count as u32));
+        }
+    }
+
+    /// Compiles `(let name bindings body...)` as a call to a procedure bound to `name` within
+    /// its own body.
+    fn compile_named_let(&mut self, name: Symbol, args: &[SExpr], tail: bool) {
+        if args.len() < 3 {
+            return self.raise(SchemeError::arity("let", "at least 3 arguments", args.len()));
+        }
+
+        let bindings = match let_bindings("let", &args[1]) {
+            Ok(bindings) => bindings,
+            Err(e) => return self.raise(e),
+        };
+        let (params, inits): (Vec<Symbol>, Vec<SExpr>) = bindings.into_iter().unzip();
+
+        for init in inits.iter() {
+            self.expression(init, false);
+        }
+
+        self.scoped(vec![name.clone()], |compiler, scope| {
+            compiler.emit(Instruction::EnterScope(scope));
+            let lambda =
+                compiler.lambda(params, ListImplementation::from_iter(args[2..].iter().cloned()));
+            compiler.emit(Instruction::Closure(lambda));
+            compiler.emit(Instruction::Bind(0));
+            compiler.emit(Instruction::Local(0, 0));
+            compiler.emit(Instruction::Insert(inits.len() as u32));
+            compiler.call(inits.len(), Some(&name), tail);
+        });
+        self.leave_scopes(1, tail);
+    }
+
+    fn compile_cond(&mut self, args: &[SExpr], tail: bool) {
+        if args.is_empty() {
+            return self.raise(SchemeError::arity("cond", "at least 1 argument", 0));
+        }
+
+        let mut ends = vec![];
+        self.cond_clauses(args, tail, &mut ends);
+        ends.into_iter().for_each(|end| self.patch(end));
+    }
+
+    /// Compiles the clauses of `cond`, collecting the jumps leaving a selected clause in `ends`.
+    fn cond_clauses(&mut self, args: &[SExpr], tail: bool, ends: &mut Vec<usize>) {
+        // Besides `(else expression...)` clauses, a trailing `else expression` is accepted
+        let length = args.len();
+        let (clauses, otherwise) = match length > 3 && is_keyword(args.get(length - 2), "else") {
+            true => (&args[..length - 2], args.last()),
+            false => (args, None),
+        };
+
+        for clause in clauses {
+            let clause: Vec<SExpr> = match clause.as_list() {
+                Ok(clause) if clause.s_len() > 0 => clause.into_iter().collect(),
+                _ => return self.raise(SchemeError::syntax("cond", "clauses must be lists")),
+            };
+
+            let (test, body) = clause.split_first().unwrap();
+            if is_keyword(Some(test), "else") && !body.is_empty() {
+                return self.sequence(body, tail);
+            }
+
+            self.expression(test, false);
+            if body.is_empty() || is_keyword(body.first(), "=>") {
+                // The value of the test is the result of the clause or the receiver's argument
+                self.emit(Instruction::Dup);
+                let next = self.emit(Instruction::JumpIfFalse(0));
+                self.clause_value("cond", body, tail);
+                ends.extend((!tail).then(|| self.emit(Instruction::Jump(0))));
+                self.patch(next);
+                self.emit(Instruction::Pop);
+            } else {
+                let next = self.emit(Instruction::JumpIfFalse(0));
+                self.sequence(body, tail);
+                ends.extend((!tail).then(|| self.emit(Instruction::Jump(0))));
+                self.patch(next);
+            }
+        }
+
+        match otherwise {
+            Some(otherwise) => self.expression(otherwise, tail),
+            None => self.push_constant(SExpr::Ok, tail),
+        }
+    }
+
+    /// Compiles a clause of `cond` or `case` whose selecting value is on top, being passed to
+    /// the receiver of a `=>` clause and being the result of a clause without expressions.
+    fn clause_value(&mut self, form: &str, body: &[SExpr], tail: bool) {
+        match body {
+            [] => self.ret(tail),
+            [_, receiver] => {
+                self.expression(receiver, false);
+                self.emit(Instruction::Insert(1));
+                self.call(1, None, tail);
+            }
+            _ => self.raise(SchemeError::syntax(form, "malformed => clause")),
+        }
+    }
+
+    fn compile_case(&mut self, args: &[SExpr], tail: bool) {
+        if args.len() < 2 {
+            return self.raise(SchemeError::arity("case", "at least 2 arguments", args.len()));
+        }
+
+        self.expression(&args[0], false);
+
+        let mut ends = vec![];
+        self.case_clauses(&args[1..], tail, &mut ends);
+        ends.into_iter().for_each(|end| self.patch(end));
+    }
+
+    /// Compiles the clauses of `case`, whose key is on top, collecting the jumps leaving a
+    /// selected clause in `ends`.
+    fn case_clauses(&mut self, clauses: &[SExpr], tail: bool, ends: &mut Vec<usize>) {
+        for clause in clauses {
+            let clause: Vec<SExpr> = match clause {
+                SExpr::Pair(_) => match clause.as_list() {
+                    Ok(clause) => clause.into_iter().collect(),
+                    Err(e) => return self.raise(e),
+                },
+                other => return self.raise(SchemeError::wrong_type("case", other, "a clause")),
+            };
+
+            let (data, body) = clause.split_first().unwrap();
+            let next = match data {
+                SExpr::Pair(_) | SExpr::Null => {
+                    if let Err(e) = data.as_list() {
+                        return self.raise(e);
+                    }
+                    let data = self.constant(data.clone());
+                    Some(self.emit(Instruction::JumpUnlessMember(data, 0)))
+                }
+                SExpr::Symbol(symbol) if symbol == "else" => None,
+                _ => return self.raise(SchemeError::syntax("case", "malformed clause")),
+            };
+
+            if body.is_empty() || is_keyword(body.first(), "=>") {
+                self.clause_value("case", body, tail);
+            } else {
+                self.emit(Instruction::Pop);
+                self.sequence(body, tail);
+            }
+
+            match next {
+                Some(next) => {
+                    ends.extend((!tail).then(|| self.emit(Instruction::Jump(0))));
+                    self.patch(next);
+                }
+                None => return,
+            }
+        }
+
+        self.emit(Instruction::Pop);
+        self.push_constant(SExpr::Unspecified, tail);
+    }
+
+    /// Compiles `when`, or `unless` if `negated`.
+    fn compile_when(&mut self, form: &str, args: &[SExpr], negated: bool, tail: bool) {
+        if args.len() < 2 {
+            return self.raise(SchemeError::arity(form, "at least 2 arguments", args.len()));
+        }
+
+        self.expression(&args[0], false);
+        let otherwise = match negated {
+            true => self.emit(Instruction::JumpIfTrue(0)),
+            false => self.emit(Instruction::JumpIfFalse(0)),
+        };
+        self.sequence(&args[1..], tail);
+        let end = (!tail).then(|| self.emit(Instruction::Jump(0)));
+
+        self.patch(otherwise);
+        self.push_constant(SExpr::Unspecified, tail);
+        end.into_iter().for_each(|end| self.patch(end));
+    }
+
+    /// Compiles the operands of `and` or `or`, whose value is that of the first operand making
+    /// `jump` jump, the last one being in tail position.
+    fn compile_operands(&mut self, args: &[SExpr], jump: Instruction, tail: bool) {
+        let Some((last, leading)) = args.split_last() else {
+            return self.push_constant(SExpr::Boolean(true), tail);
+        };
+
+        let mut ends = vec![];
+        for arg in leading {
+            self.expression(arg, false);
+            ends.push(self.emit(jump));
+        }
+        self.expression(last, tail);
+
+        ends.into_iter().for_each(|end| self.patch(end));
+        if !leading.is_empty() {
+            self.ret(tail);
+        }
+    }
+
+    fn compile_do(&mut self, args: &[SExpr], tail: bool) {
+        let [variables, exit, commands @ ..] = args else {
+            return self.raise(SchemeError::arity("do", "at least 2 arguments", args.len()));
+        };
+
+        let variables = match variables.as_list() {
+            Ok(list) => match list.iter().map(do_variable).collect::<Result<Vec<_>, _>>() {
+                Ok(variables) => variables,
+                Err(e) => return self.raise(e),
+            },
+            Err(_) => return self.raise(SchemeError::wrong_type("do", variables, "a list")),
+        };
+
+        let exit: Vec<SExpr> = match exit {
+            SExpr::Pair(_) if exit.list_length().is_some() => exit.pairs().collect(),
+            other => {
+                let error = SchemeError::wrong_type("do", other, "a (test result...) clause");
+                return self.raise(error);
+            }
+        };
+
+        for (_, init, _) in variables.iter() {
+            self.expression(init, false);
+        }
+
+        // Each iteration binds the variables afresh in a scope of its own
+        let names: Vec<Symbol> = variables.iter().map(|(name, _, _)| name.clone()).collect();
+        self.scoped(names, |compiler, scope| {
+            compiler.emit(Instruction::EnterScope(scope));
+            compiler.bind_reversed(variables.len());
+
+            let start = compiler.chunk.instructions.len() as u32;
+            compiler.expression(&exit[0], false);
+            let iterate = compiler.emit(Instruction::JumpIfFalse(0));
+
+            match exit.len() {
+                1 => compiler.push_constant(SExpr::Unspecified, tail),
+                _ => compiler.sequence(&exit[1..], tail),
+            }
+            compiler.leave_scopes(1, tail);
+            let end = (!tail).then(|| compiler.emit(Instruction::Jump(0)));
+
+            compiler.patch(iterate);
+            for command in commands {
+                compiler.expression(command, false);
+                compiler.emit(Instruction::Pop);
+            }
+
+            for (index, (_, _, step)) in variables.iter().enumerate() {
+                match step {
+                    Some(step) => compiler.expression(step, false),
+                    None => _ = compiler.emit(Instruction::Local(0, index as u32)),
+                }
+            }
+
+            compiler.emit(Instruction::LeaveScope(1));
+            compiler.emit(Instruction::EnterScope(scope));
+            compiler.bind_reversed(variables.len());
+            compiler.emit(Instruction::Jump(start));
+
+            end.into_iter().for_each(|end| compiler.patch(end));
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::{
+        environment::{EnvAccessor, Environment},
+        reader::read_all,
+    };
+
+    use super::*;
+
+    fn compiled(source: &str) -> Code {
+        let expression = read_all(source).unwrap().remove(0);
+        compile(&expression, &EnvAccessor::new(Environment::default()))
+    }
+
+    #[test]
+    fn compiler_tail_call() {
+        let code = compiled("(lambda (n) (if (= n 0) 0 (f (- n 1))))");
+        let body = code.lambdas[0].code.get().unwrap();
+
+        assert!(matches!(body.instructions.last(), Some(Instruction::TailCall(1, Some(_)))));
+        assert!(body.instructions.iter().any(|i| matches!(i, Instruction::Call(2, Some(_)))));
+    }
+
+    #[test]
+    fn compiler_shadowed_keyword() {
+        let code = compiled("(lambda (if) (if 1 2 3))");
+        let body = code.lambdas[0].code.get().unwrap();
+
+        assert!(matches!(body.instructions.last(), Some(Instruction::TailCall(3, Some(_)))));
+    }
+
+    #[test]
+    fn compiler_lexical_addresses() {
+        let code = compiled("(lambda (a) (lambda (b) (+ a b)))");
+        let outer = code.lambdas[0].code.get().unwrap();
+        let inner = outer.lambdas[0].code.get().unwrap();
+
+        assert!(matches!(
+            inner.instructions[..],
+            [
+                Instruction::Free(_, 2),
+                Instruction::Local(1, 0),
+                Instruction::Local(0, 0),
+                Instruction::TailCall(2, Some(_))
+            ]
+        ));
+    }
+}
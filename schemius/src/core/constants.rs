@@ -11,16 +11,25 @@ pub mod tokens {
     pub const QUASIQUOTE: &str = "`";
     pub const QUASIQUOTE_EXPLICIT: &str = "quasiquote";
     pub const DOT: &str = ".";
+    pub const ELLIPSIS: &str = "...";
     pub const UNQUOTE: &str = ",";
     pub const UNQUOTE_EXPLICIT: &str = "unquote";
     pub const UNQUOTE_SPLICING: &str = ",@";
     pub const UNQUOTE_SPLICING_EXPLICIT: &str = "unquote-splicing";
     pub const TRUE: &str = "#t";
+    pub const TRUE_EXPLICIT: &str = "#true";
     pub const FALSE: &str = "#f";
+    pub const FALSE_EXPLICIT: &str = "#false";
     pub const PREFIX_CHAR: &str = "#\\";
     pub const PREFIX_STRING: &str = "\"";
     pub const SUFFIX_STRING: &str = "\"";
     pub const PREFIX_COMMENT: &str = ";";
+    pub const PREFIX_DATUM_COMMENT: &str = "#;";
+    pub const PREFIX_BLOCK_COMMENT: &str = "#|";
+    pub const SUFFIX_BLOCK_COMMENT: &str = "|#";
+    pub const PREFIX_DIRECTIVE: &str = "#!";
+    pub const FOLD_CASE: &str = "fold-case";
+    pub const NO_FOLD_CASE: &str = "no-fold-case";
     pub const PREFIX_BINARY: &str = "#b";
     pub const PREFIX_OCTAL: &str = "#o";
     pub const PREFIX_HEX: &str = "#x";
@@ -33,6 +42,207 @@ pub mod tokens {
     pub const NEGATIVE_NAN: &str = "-nan.0";
 }
 
+pub mod chars {
+    pub const NAMES: &[(&str, char)] = &[
+        ("alarm", '\u{7}'),
+        ("backspace", '\u{8}'),
+        ("delete", '\u{7f}'),
+        ("escape", '\u{1b}'),
+        ("newline", '\n'),
+        ("null", '\0'),
+        ("return", '\r'),
+        ("space", ' '),
+        ("tab", '\t'),
+    ];
+}
+
+pub mod features {
+    pub const R7RS: &str = "r7rs";
+    pub const EXACT_CLOSED: &str = "exact-closed";
+    pub const RATIOS: &str = "ratios";
+    pub const FULL_UNICODE: &str = "full-unicode";
+    pub const SCHEMIUS: &str = "schemius";
+    pub const I32: &str = "i32";
+    pub const I64: &str = "i64";
+    pub const I128: &str = "i128";
+    pub const F32: &str = "f32";
+    pub const F64: &str = "f64";
+    pub const TRUE_LIST: &str = "true-list";
+    pub const THREAD_SAFE: &str = "thread-safe";
+    pub const BIG_FLOAT: &str = "big-float";
+    pub const UNIX: &str = "unix";
+    pub const WINDOWS: &str = "windows";
+}
+
+pub mod libraries {
+    pub const SCHEME_BASE: &[&str] = &[
+        "*",
+        "+",
+        "-",
+        "/",
+        "<",
+        "<=",
+        "=",
+        ">",
+        ">=",
+        "and",
+        "append",
+        "apply",
+        "begin",
+        "boolean?",
+        "caar",
+        "cadr",
+        "car",
+        "case",
+        "cdar",
+        "cddr",
+        "cdr",
+        "char?",
+        "complex?",
+        "cond",
+        "cond-expand",
+        "cons",
+        "define",
+        "do",
+        "dynamic-wind",
+        "eq?",
+        "eqv?",
+        "error",
+        "exact",
+        "exact?",
+        "exact-integer?",
+        "features",
+        "if",
+        "include",
+        "include-ci",
+        "inexact",
+        "inexact?",
+        "integer?",
+        "lambda",
+        "length",
+        "let",
+        "let*",
+        "letrec",
+        "letrec*",
+        "list",
+        "list?",
+        "list-ref",
+        "list-tail",
+        "make-string",
+        "not",
+        "null?",
+        "number?",
+        "or",
+        "pair?",
+        "procedure?",
+        "quasiquote",
+        "quote",
+        "rational?",
+        "real?",
+        "reverse",
+        "set!",
+        "set-car!",
+        "set-cdr!",
+        "string",
+        "string?",
+        "string-append",
+        "string-length",
+        "string-ref",
+        "string-set!",
+        "string->symbol",
+        "symbol?",
+        "symbol=?",
+        "symbol->string",
+        "unless",
+        "vector?",
+        "when",
+        "zero?",
+    ];
+    pub const SCHEME_CHAR: &[&str] = &["string-downcase", "string-upcase"];
+    pub const SCHEME_CXR: &[&str] = &[
+        "caaar", "caadr", "cadar", "caddr", "cdaar", "cdadr", "cddar", "cdddr", "caaaar", "caaadr",
+        "caadar", "caaddr", "cadaar", "cadadr", "caddar", "cadddr", "cdaaar", "cdaadr", "cdadar",
+        "cdaddr", "cddaar", "cddadr", "cdddar", "cddddr",
+    ];
+    pub const SCHEME_EVAL: &[&str] = &["environment", "eval"];
+    pub const SCHEME_INEXACT: &[&str] = &[
+        "acos",
+        "asin",
+        "atan",
+        "cos",
+        "exp",
+        "finite?",
+        "infinite?",
+        "log",
+        "nan?",
+        "sin",
+        "sqrt",
+        "tan",
+    ];
+    pub const SCHEME_LOAD: &[&str] = &["load"];
+    pub const SCHEME_PROCESS_CONTEXT: &[&str] = &[
+        "command-line",
+        "emergency-exit",
+        "exit",
+        "get-environment-variable",
+        "get-environment-variables",
+    ];
+    pub const SCHEME_R5RS: &[&str] = &["null-environment", "scheme-report-environment"];
+    pub const SCHEME_REPL: &[&str] = &["interaction-environment"];
+    pub const SCHEME_TIME: &[&str] = &["current-jiffy", "current-second", "jiffies-per-second"];
+    pub const SCHEME_WRITE: &[&str] = &["display"];
+    pub const SCHEMIUS: &[&str] = &[
+        "add1",
+        "avogadro",
+        "boltzmann",
+        "e",
+        "environment-bindings",
+        "euler",
+        "gc",
+        "generate-uninterned-symbol",
+        "gensym",
+        "golden-ratio",
+        "gravitational-constant",
+        "h",
+        "heap-statistics",
+        "list-splice",
+        "pi",
+        "planck",
+        "sub1",
+        "time",
+        "π",
+        "λ",
+    ];
+
+    pub const SUPPORTED: &[(&str, &[&[&str]])] = &[
+        ("(scheme base)", &[SCHEME_BASE]),
+        ("(scheme char)", &[SCHEME_CHAR]),
+        ("(scheme cxr)", &[SCHEME_CXR]),
+        ("(scheme eval)", &[SCHEME_EVAL]),
+        ("(scheme inexact)", &[SCHEME_INEXACT]),
+        ("(scheme load)", &[SCHEME_LOAD]),
+        ("(scheme process-context)", &[SCHEME_PROCESS_CONTEXT]),
+        ("(scheme repl)", &[SCHEME_REPL]),
+        ("(scheme time)", &[SCHEME_TIME]),
+        ("(scheme write)", &[SCHEME_WRITE]),
+        (
+            "(scheme r5rs)",
+            &[
+                SCHEME_BASE,
+                SCHEME_CHAR,
+                SCHEME_CXR,
+                SCHEME_EVAL,
+                SCHEME_INEXACT,
+                SCHEME_LOAD,
+                SCHEME_R5RS,
+                SCHEME_REPL,
+                SCHEME_WRITE,
+            ],
+        ),
+        ("(schemius)", &[SCHEMIUS]),
+    ];
+}
+
 pub mod numbers {
     pub const AVOGADRO: f64 = 6.0221515e23;
     pub const BOLTZMANN: f64 = 1.380650e23;
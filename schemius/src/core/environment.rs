@@ -1,89 +1,320 @@
 use std::collections::HashMap;
+use std::ptr::fn_addr_eq;
+use std::sync::Arc;
 
 use super::{
     accessor::*,
     builtins::*,
-    s_expression::{s_procedure::*, NumericalConstant, SExpr},
+    error::SchemeError,
+    evaluator::Settings,
+    heap::{trace_value, Kind, Trace},
+    s_expression::{s_procedure::*, NumericalConstant, SExpr, Symbol},
 };
 
-pub type EnvAccessor<EnvironmentTrait> = ThreadSafeAccessor<EnvironmentTrait>;
+pub type EnvAccessor<EnvironmentTrait> = DefaultAccessor<EnvironmentTrait>;
 
 pub trait SchemeEnvironment: Clone
 where
-    Self: Sized,
+    Self: Sized + Trace,
 {
     fn new() -> Self;
     fn new_child(parent: EnvAccessor<Self>) -> EnvAccessor<Self>;
-    fn define(&mut self, key: &str, value: &SExpr) -> Result<(), String>;
-    fn set(&mut self, key: &str, value: &SExpr) -> Result<(), String>;
-    fn get(&self, key: &str) -> Option<SExpr>;
-    fn get_bindings(&self) -> Vec<(&String, &SExpr)>;
-    fn get_root(env: ProcedureEnv) -> ProcedureEnv;
+    /// A frame within `parent` holding the variables `names`, the first ones bound to `values`
+    /// and the others not bound yet.
+    fn new_frame(
+        parent: EnvAccessor<Self>, names: Arc<[Symbol]>, values: impl IntoIterator<Item = SExpr>,
+    ) -> EnvAccessor<Self>;
+    fn define(&mut self, key: &Symbol, value: &SExpr) -> Result<(), SchemeError>;
+    fn set(&mut self, key: &Symbol, value: &SExpr) -> Result<(), SchemeError>;
+    fn get(&self, key: &Symbol) -> Option<SExpr>;
+    /// The value of the variable `key`, which is known not to be held by the frames of the
+    /// `depth` innermost environments.
+    fn get_free(&self, key: &Symbol, depth: usize) -> Option<SExpr>;
+    /// The value held by the frame of the environment `depth` levels up at `index`.
+    fn get_local(&self, depth: usize, index: usize) -> Result<SExpr, SchemeError>;
+    fn set_local(&mut self, depth: usize, index: usize, value: SExpr) -> Result<(), SchemeError>;
+    fn get_bindings(&self) -> Vec<(&Symbol, &SExpr)>;
+    fn get_root(env: ProcedureEnv) -> Result<ProcedureEnv, SchemeError>;
+    fn define_library(&mut self, name: &str, library: Library);
+    fn get_library(&self, name: &str) -> Option<Library>;
+    /// The settings of the interpreter, which are held by its root environment.
+    fn settings(&self) -> Settings;
+    fn set_settings(&mut self, settings: Settings);
+    /// How many times a variable of the root environment bound to a primitive was bound to
+    /// something else, after which code the optimizer rewrote to call the primitive directly is
+    /// evaluated as written.
+    fn rebound(&self) -> usize;
 }
 
+/// A library defined by `define-library`: the environment its body ran in and the names it
+/// exports, each as the internal name bound in that environment and the external name it is
+/// imported by.
+#[derive(Clone, Debug)]
+pub struct Library {
+    pub env: ProcedureEnv,
+    pub exports: Vec<(String, String)>,
+}
+
+/// A set of bindings within an optional parent. Variables resolved by the compiler are held in
+/// a frame, where they are accessed by index, while any other is kept in a table. Variables are
+/// named by interned symbols, which are compared by address rather than by spelling.
 #[derive(Clone, Debug)]
 pub struct Environment {
     parent: Option<EnvAccessor<Environment>>,
-    table: HashMap<String, SExpr>,
+    table: HashMap<Symbol, SExpr>,
+    names: Arc<[Symbol]>,
+    frame: Vec<Option<SExpr>>,
+    /// The libraries defined within a root environment, by name.
+    libraries: HashMap<String, Library>,
+    settings: Settings,
+    rebound: usize,
+}
+
+impl Environment {
+    fn slot(&self, key: &Symbol) -> Option<usize> {
+        self.names.iter().position(|name| name == key)
+    }
+
+    /// Binds `key` in the table, counting it if it was bound to another primitive before.
+    fn bind(&mut self, key: &Symbol, value: &SExpr) {
+        let previous = self.table.insert(key.clone(), value.clone());
+        if let Some(SExpr::Procedure(Procedure::Primitive(previous))) = previous {
+            match value {
+                SExpr::Procedure(Procedure::Primitive(primitive))
+                    if fn_addr_eq(previous, *primitive) => {}
+                _ => self.rebound += 1,
+            }
+        }
+    }
+
+    /// The value bound to `key` in this environment alone.
+    fn lookup(&self, key: &Symbol) -> Option<SExpr> {
+        match self.slot(key).and_then(|index| self.frame[index].clone()) {
+            Some(value) => Some(value),
+            None => self.table.get(key).cloned(),
+        }
+    }
+
+    /// The first value `visit` finds in this environment and then in each of its ancestors.
+    /// Scopes may be nested however deeply, so the chain is walked without recursing.
+    fn find<T>(
+        &self, mut visit: impl FnMut(&Environment) -> Option<T>,
+    ) -> Result<Option<T>, SchemeError> {
+        if let Some(found) = visit(self) {
+            return Ok(Some(found));
+        }
+
+        let mut parent = self.parent.clone();
+        while let Some(env) = parent {
+            let env = env.access()?;
+            if let Some(found) = visit(&env) {
+                return Ok(Some(found));
+            }
+            parent = env.parent.clone();
+        }
+
+        Ok(None)
+    }
+
+    /// Like `find`, but lets `visit` change the environments it is given.
+    fn find_mut<T>(
+        &mut self, mut visit: impl FnMut(&mut Environment) -> Option<T>,
+    ) -> Result<Option<T>, SchemeError> {
+        if let Some(found) = visit(self) {
+            return Ok(Some(found));
+        }
+
+        let mut parent = self.parent.clone();
+        while let Some(env) = parent {
+            let mut env = env.access_mut()?;
+            if let Some(found) = visit(&mut env) {
+                return Ok(Some(found));
+            }
+            parent = env.parent.clone();
+        }
+
+        Ok(None)
+    }
 }
 
 impl SchemeEnvironment for Environment {
     fn new() -> Environment {
-        Environment { parent: None, table: HashMap::new() }
+        Environment {
+            parent: None,
+            table: HashMap::new(),
+            names: Arc::new([]),
+            frame: vec![],
+            libraries: HashMap::new(),
+            settings: Settings::default(),
+            rebound: 0,
+        }
     }
 
     fn new_child(parent: EnvAccessor<Self>) -> EnvAccessor<Self> {
-        let env = Environment { parent: Some(parent), table: HashMap::new() };
-        EnvAccessor::new(env)
+        EnvAccessor::new(Environment { parent: Some(parent), ..Environment::new() })
+    }
+
+    fn new_frame(
+        parent: EnvAccessor<Self>, names: Arc<[Symbol]>, values: impl IntoIterator<Item = SExpr>,
+    ) -> EnvAccessor<Self> {
+        let mut frame: Vec<Option<SExpr>> = values.into_iter().map(Some).collect();
+        frame.resize(names.len(), None);
+
+        EnvAccessor::new(Environment { parent: Some(parent), names, frame, ..Environment::new() })
     }
 
-    fn define(&mut self, key: &str, value: &SExpr) -> Result<(), String> {
-        self.table.insert(key.to_string(), value.clone());
+    fn define(&mut self, key: &Symbol, value: &SExpr) -> Result<(), SchemeError> {
+        match self.slot(key) {
+            Some(index) => self.frame[index] = Some(value.clone()),
+            None => self.bind(key, value),
+        }
 
         Ok(())
     }
 
-    fn set(&mut self, key: &str, value: &SExpr) -> Result<(), String> {
-        if self.table.contains_key(key) {
-            self.table.insert(key.to_string(), value.clone());
+    fn set(&mut self, key: &Symbol, value: &SExpr) -> Result<(), SchemeError> {
+        let found = self.find_mut(|env| {
+            if let Some(index) = env.slot(key) {
+                if env.frame[index].is_some() {
+                    env.frame[index] = Some(value.clone());
+                    return Some(());
+                }
+            }
+
+            env.table.contains_key(key).then(|| env.bind(key, value))
+        })?;
 
-            Ok(())
-        } else {
-            match self.parent {
-                Some(ref parent) => parent.access_mut().set(key, value),
-                None => Err(format!("Exception: {} is not bound", key)),
+        found.ok_or_else(|| SchemeError::unbound_variable(key))
+    }
+
+    // A parent in use is being changed by a definition, which cannot look a variable up, so
+    // it is only skipped in case that ever changes
+    fn get(&self, key: &Symbol) -> Option<SExpr> {
+        self.find(|env| env.lookup(key)).ok().flatten()
+    }
+
+    fn get_free(&self, key: &Symbol, depth: usize) -> Option<SExpr> {
+        let mut depth = depth;
+        let found = self.find(|env| match depth.checked_sub(1) {
+            Some(remaining) => {
+                depth = remaining;
+                // Most frames have no table at all, which is cheaper to tell than hashing the key
+                (!env.table.is_empty()).then(|| env.table.get(key).cloned()).flatten()
+            }
+            None => env.lookup(key),
+        });
+
+        found.ok().flatten()
+    }
+
+    fn get_local(&self, depth: usize, index: usize) -> Result<SExpr, SchemeError> {
+        let mut depth = depth;
+        let found = self.find(|env| match depth.checked_sub(1) {
+            Some(remaining) => {
+                depth = remaining;
+                None
             }
+            None => Some(
+                env.frame[index]
+                    .clone()
+                    .ok_or_else(|| SchemeError::unbound_variable(&env.names[index])),
+            ),
+        })?;
+
+        match found {
+            Some(value) => value,
+            None => unreachable!("variables are only resolved to existing frames"),
         }
     }
 
-    fn get(&self, key: &str) -> Option<SExpr> {
-        match self.table.get(key) {
-            Some(val) => Some(val.clone()),
-            None => match self.parent {
-                Some(ref parent) => parent.access().get(key),
-                None => None,
-            },
+    fn set_local(&mut self, depth: usize, index: usize, value: SExpr) -> Result<(), SchemeError> {
+        let mut depth = depth;
+        let mut value = Some(value);
+        let found = self.find_mut(|env| match depth.checked_sub(1) {
+            Some(remaining) => {
+                depth = remaining;
+                None
+            }
+            None => {
+                env.frame[index] = value.take();
+                Some(())
+            }
+        })?;
+
+        match found {
+            Some(()) => Ok(()),
+            None => unreachable!("variables are only resolved to existing frames"),
         }
     }
 
-    fn get_bindings(&self) -> Vec<(&String, &SExpr)> {
-        let symbols: Vec<(&String, &SExpr)> = self.table.iter().map(|e| (e.0, e.1)).collect();
+    fn get_bindings(&self) -> Vec<(&Symbol, &SExpr)> {
+        let locals = self.names.iter().zip(self.frame.iter());
+        let locals = locals.filter_map(|(name, value)| Some((name, value.as_ref()?)));
+        let symbols: Vec<(&Symbol, &SExpr)> = self.table.iter().chain(locals).collect();
 
         symbols
     }
 
-    fn get_root(env: ProcedureEnv) -> ProcedureEnv {
-        match &env.access().parent {
-            Some(frame) => Environment::get_root(frame.clone()),
-            None => env.clone(),
+    fn get_root(env: ProcedureEnv) -> Result<ProcedureEnv, SchemeError> {
+        let mut env = env;
+        loop {
+            let parent = env.access()?.parent.clone();
+            match parent {
+                Some(frame) => env = frame,
+                None => return Ok(env),
+            }
         }
     }
+
+    fn define_library(&mut self, name: &str, library: Library) {
+        self.libraries.insert(name.to_string(), library);
+    }
+
+    fn get_library(&self, name: &str) -> Option<Library> {
+        self.libraries.get(name).cloned()
+    }
+
+    fn settings(&self) -> Settings {
+        self.settings.clone()
+    }
+
+    fn set_settings(&mut self, settings: Settings) {
+        self.settings = settings;
+    }
+
+    // A parent being changed never counts as unchanged, which only costs optimized code
+    fn rebound(&self) -> usize {
+        let root = self.find(|env| env.parent.is_none().then_some(env.rebound));
+        root.ok().flatten().unwrap_or(usize::MAX)
+    }
+}
+
+impl Trace for Environment {
+    const KIND: Kind = Kind::Environment;
+
+    fn trace(&self, visit: &mut dyn FnMut(*const ())) {
+        if let Some(ref parent) = self.parent {
+            visit(parent.as_ptr());
+        }
+        self.libraries.values().for_each(|library| visit(library.env.as_ptr()));
+        self.table
+            .values()
+            .chain(self.frame.iter().flatten())
+            .for_each(|value| trace_value(value, visit));
+    }
+
+    fn clear(&mut self, garbage: &mut Vec<SExpr>) {
+        garbage.extend(self.parent.take().map(SExpr::Environment));
+        garbage.extend(self.table.drain().map(|(_, value)| value));
+        garbage.extend(self.frame.drain(..).flatten());
+        garbage.extend(self.libraries.drain().map(|(_, library)| SExpr::Environment(library.env)));
+    }
 }
 
 macro_rules! bind_numerical_constants {
     ($env:expr, { $($name:expr => $value:ident)* }) => {
         $(
-            $env.define($name, &SExpr::Number(NumericalConstant::$value)).unwrap();
+            $env.define(&Symbol::new($name), &SExpr::Number(NumericalConstant::$value)).unwrap();
         )*
     };
 }
@@ -91,7 +322,7 @@ macro_rules! bind_numerical_constants {
 macro_rules! bind_primitives {
     ($env:expr, { $($name:expr => $value:ident)* }) => {
         $(
-            $env.define($name, &SExpr::Procedure(Procedure::Primitive(Primitive::$value))).unwrap();
+            $env.define(&Symbol::new($name), &SExpr::Procedure(Procedure::Primitive(Primitive::$value))).unwrap();
         )*
     };
 }
@@ -99,7 +330,7 @@ macro_rules! bind_primitives {
 macro_rules! bind_special_forms  {
     ($env:expr, { $($name:expr => $value:ident)* }) => {
         $(
-            $env.define($name, &SExpr::Procedure(Procedure::SpecialForm(SpecialForm::$value))).unwrap();
+            $env.define(&Symbol::new($name), &SExpr::Keyword(SpecialForm::$value)).unwrap();
         )*
     };
 }
@@ -125,12 +356,24 @@ impl Default for Environment {
             "*" => PROD
             "/" => QUOT
             "=" => EQUAL
+            "eq?" => EQV
+            "eqv?" => EQV
             ">" => GT
             ">=" => GE
             "<" => LT
             "<=" => LE
             "exit" => EXIT
+            "emergency-exit" => EMERGENCY_EXIT
+            "command-line" => COMMAND_LINE
+            "dynamic-wind" => DYNAMIC_WIND
+            "get-environment-variable" => GET_ENVIRONMENT_VARIABLE
+            "get-environment-variables" => GET_ENVIRONMENT_VARIABLES
+            "features" => FEATURES
             "eval" => EVAL
+            "environment" => ENVIRONMENT
+            "interaction-environment" => INTERACTION_ENVIRONMENT
+            "scheme-report-environment" => SCHEME_REPORT_ENVIRONMENT
+            "null-environment" => NULL_ENVIRONMENT
             "apply" => APPLY
             "car" => CAR
             "cdr" => CDR
@@ -138,14 +381,37 @@ impl Default for Environment {
             "cons" => CONS
             "list" => LIST
             "set-car!" => SET_CAR
+            "set-cdr!" => SET_CDR
             "display" => DISPLAY
+            "error" => ERROR
             "char?" => IS_CHAR
             "symbol?" => IS_SYMBOL
+            "symbol=?" => SYMBOL_EQUAL
+            "symbol->string" => SYMBOL_TO_STRING
+            "string->symbol" => STRING_TO_SYMBOL
+            "gensym" => GENSYM
+            "gc" => GC
+            "heap-statistics" => HEAP_STATISTICS
+            "generate-uninterned-symbol" => GENSYM
             "string?" => IS_STRING
             "boolean?" => IS_BOOLEAN
+            "not" => NOT
             "nan?" => IS_NAN
             "number?" => IS_NUMBER
             "exact?" => IS_EXACT
+            "exact" => EXACT
+            "inexact" => INEXACT
+            "exact->inexact" => INEXACT
+            "inexact->exact" => EXACT
+            "exp" => EXP
+            "log" => LOG
+            "sin" => SIN
+            "cos" => COS
+            "tan" => TAN
+            "asin" => ASIN
+            "acos" => ACOS
+            "atan" => ATAN
+            "sqrt" => SQRT
             "infinite?" => IS_INFINITE
             "integer?" => IS_INTEGER
             "rational?" => IS_RATIONAL
@@ -161,6 +427,7 @@ impl Default for Environment {
             "list-ref" => LIST_REF
             "list-splice" => LIST_SPLICE
             "list-tail" => LIST_TAIL
+            "load" => LOAD
             "reverse" => REVERSE
             "string" => STRING
             "make-string" => MAKE_STRING
@@ -171,6 +438,9 @@ impl Default for Environment {
             "string-upcase" => STRING_UPCASE
             "string-downcase" => STRING_DOWNCASE
             "zero?" => IS_ZERO
+            "current-second" => CURRENT_SECOND
+            "current-jiffy" => CURRENT_JIFFY
+            "jiffies-per-second" => JIFFIES_PER_SECOND
         });
         bind_special_forms!(new_env,
         {
@@ -184,13 +454,23 @@ impl Default for Environment {
             "lambda" => LAMBDA
             "let" => LET
             "let*" => LET_STAR
+            "letrec" => LETREC
+            "letrec*" => LETREC_STAR
             "define" => DEFINE
+            "define-library" => DEFINE_LIBRARY
+            "import" => IMPORT
             "set!" => SET
             "if" => IF
+            "when" => WHEN
+            "unless" => UNLESS
+            "include" => INCLUDE
+            "include-ci" => INCLUDE_CI
             "time" => TIME
             "begin" => BEGIN
-            "not" => NOT
             "cond" => COND
+            "cond-expand" => COND_EXPAND
+            "case" => CASE
+            "do" => DO
         });
 
         new_env
@@ -0,0 +1,277 @@
+use std::fmt;
+
+use super::{reader::ReadError, s_expression::SExpr};
+
+pub use super::reader::{Position, Span};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// A symbol was evaluated in an environment where it has no binding.
+    UnboundVariable,
+    /// An argument has the wrong type, such as `(car 5)`.
+    WrongType,
+    /// A procedure or special form was called with the wrong number of arguments.
+    Arity,
+    /// An index or numeric argument lies outside the range accepted by a procedure.
+    OutOfRange,
+    /// Malformed source text or special form.
+    Syntax,
+    Io,
+    /// Evaluation nested deeper than the maximum depth allowed by the evaluator.
+    RecursionLimit,
+    /// A value was accessed while being changed, as when changing it ends up reading it again.
+    InUse,
+    /// An error signalled by Scheme code, for example through `error` or `raise`.
+    User,
+}
+
+#[derive(Clone, Debug)]
+struct ErrorData {
+    kind: ErrorKind,
+    message: String,
+    irritants: Vec<SExpr>,
+    procedure: Option<String>,
+    span: Option<Span>,
+    trace: Vec<Frame>,
+    elided_frames: usize,
+}
+
+/// Frames recorded past this many are only counted, keeping traces of deep recursions bounded.
+pub const MAX_TRACE_FRAMES: usize = 32;
+
+/// A procedure call that was active when an error was raised.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    pub procedure: String,
+    pub span: Option<Span>,
+    /// How many tail calls were made since the frame was entered; only the last one is kept.
+    pub tail_calls: usize,
+}
+
+impl Frame {
+    pub fn new(procedure: impl Into<String>) -> Self {
+        Self { procedure: procedure.into(), span: None, tail_calls: 0 }
+    }
+}
+
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "in {}", self.procedure)?;
+
+        if let Some(ref span) = self.span {
+            write!(f, " (at {})", span)?;
+        }
+
+        match self.tail_calls {
+            0 => Ok(()),
+            1 => write!(f, " [1 tail call elided]"),
+            n => write!(f, " [{} tail calls elided]", n),
+        }
+    }
+}
+
+/// An error raised while reading or evaluating Scheme code.
+#[derive(Clone)]
+pub struct SchemeError(Box<ErrorData>);
+
+impl SchemeError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self(Box::new(ErrorData {
+            kind,
+            message: message.into(),
+            irritants: vec![],
+            procedure: None,
+            span: None,
+            trace: vec![],
+            elided_frames: 0,
+        }))
+    }
+
+    pub fn arity(procedure: &str, expected: &str, found: usize) -> Self {
+        Self::new(ErrorKind::Arity, format!("expected {}, found {}", expected, found))
+            .in_procedure(procedure)
+    }
+
+    /// An error for `irritant` not being of the `expected` type, e.g. "a list".
+    pub fn wrong_type(procedure: &str, irritant: &SExpr, expected: &str) -> Self {
+        Self::new(ErrorKind::WrongType, format!("{} is not {}", irritant, expected))
+            .in_procedure(procedure)
+            .with_irritants([irritant.clone()])
+    }
+
+    pub fn unbound_variable(name: &str) -> Self {
+        Self::new(ErrorKind::UnboundVariable, format!("could not find a value bound to <{}>", name))
+            .with_irritants([SExpr::symbol(name)])
+    }
+
+    pub fn syntax(procedure: &str, message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Syntax, message).in_procedure(procedure)
+    }
+
+    pub fn in_procedure(mut self, procedure: &str) -> Self {
+        self.0.procedure = Some(procedure.to_string());
+        self
+    }
+
+    pub fn with_irritants(mut self, irritants: impl IntoIterator<Item = SExpr>) -> Self {
+        self.0.irritants.extend(irritants);
+        self
+    }
+
+    /// Attaches a source location, unless a more precise one is already known.
+    /// The outermost frame, being the call made by the datum at `span`, gets it as well.
+    pub fn with_span(mut self, span: &Span) -> Self {
+        if self.0.span.is_none() {
+            self.0.span = Some(span.clone());
+        }
+
+        if self.0.elided_frames == 0 {
+            if let Some(frame) = self.0.trace.last_mut().filter(|frame| frame.span.is_none()) {
+                frame.span = Some(span.clone());
+            }
+        }
+        self
+    }
+
+    /// Records `frame` as the caller of the innermost frame recorded so far.
+    pub fn with_frame(mut self, frame: Frame) -> Self {
+        if self.0.trace.len() < MAX_TRACE_FRAMES {
+            self.0.trace.push(frame);
+        } else {
+            self.0.elided_frames += 1;
+        }
+        self
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.0.kind
+    }
+
+    pub fn message(&self) -> &str {
+        &self.0.message
+    }
+
+    pub fn irritants(&self) -> &[SExpr] {
+        &self.0.irritants
+    }
+
+    pub fn procedure(&self) -> Option<&str> {
+        self.0.procedure.as_deref()
+    }
+
+    pub fn span(&self) -> Option<&Span> {
+        self.0.span.as_ref()
+    }
+
+    /// The procedure calls active when the error was raised, innermost first.
+    pub fn trace(&self) -> &[Frame] {
+        &self.0.trace
+    }
+
+    /// The number of outer frames left out of the trace.
+    pub fn elided_frames(&self) -> usize {
+        self.0.elided_frames
+    }
+
+    /// The error message followed by its backtrace, one frame per line.
+    pub fn report(&self) -> String {
+        let mut output = self.to_string();
+
+        for frame in self.trace() {
+            output.push_str(&format!("\n  {}", frame));
+        }
+
+        if self.elided_frames() > 0 {
+            output.push_str(&format!("\n  ... {} more frames", self.elided_frames()));
+        }
+
+        output
+    }
+}
+
+impl fmt::Display for SchemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.procedure() {
+            Some(procedure) => write!(f, "Exception in {}: {}", procedure, self.message())?,
+            None => write!(f, "Exception: {}", self.message())?,
+        }
+
+        match self.span() {
+            Some(span) => write!(f, " (at {})", span),
+            None => Ok(()),
+        }
+    }
+}
+
+// Shows the backtrace when an error is returned from `main`
+impl fmt::Debug for SchemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.report())
+    }
+}
+
+impl std::error::Error for SchemeError {}
+
+impl From<ReadError> for SchemeError {
+    fn from(error: ReadError) -> Self {
+        let message = match error {
+            ReadError::Incomplete(_) => "Invalid syntax: unexpected end of input".to_string(),
+            ReadError::Syntax(ref message, _) => format!("Invalid syntax: {}", message),
+        };
+
+        Self::new(ErrorKind::Syntax, message).with_span(error.span())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let error = SchemeError::wrong_type("car", &SExpr::from(5), "a pair");
+        assert_eq!(error.to_string(), "Exception in car: 5 is not a pair");
+        assert_eq!(error.kind(), ErrorKind::WrongType);
+        assert_eq!(error.irritants().len(), 1);
+
+        let error = SchemeError::unbound_variable("x");
+        assert_eq!(error.to_string(), "Exception: could not find a value bound to <x>");
+        assert_eq!(error.procedure(), None);
+    }
+
+    #[test]
+    fn test_span() {
+        let span = |line| Span {
+            file: None,
+            start: Position { line, column: 1 },
+            end: Position { line, column: 2 },
+        };
+
+        let error =
+            SchemeError::arity("cdr", "1 argument", 2).with_span(&span(3)).with_span(&span(7));
+        assert_eq!(error.span().unwrap().start.line, 3);
+        assert_eq!(
+            error.to_string(),
+            "Exception in cdr: expected 1 argument, found 2 (at line 3, column 1)"
+        );
+    }
+
+    #[test]
+    fn test_trace() {
+        let mut error = SchemeError::unbound_variable("x")
+            .with_frame(Frame::new("inner"))
+            .with_frame(Frame { tail_calls: 3, ..Frame::new("outer") });
+        assert_eq!(error.trace().len(), 2);
+        assert_eq!(
+            error.report(),
+            "Exception: could not find a value bound to <x>\n  in inner\n  in outer [3 tail calls elided]"
+        );
+
+        for _ in 0..MAX_TRACE_FRAMES {
+            error = error.with_frame(Frame::new("deep"));
+        }
+        assert_eq!(error.trace().len(), MAX_TRACE_FRAMES);
+        assert_eq!(error.elided_frames(), 2);
+        assert!(error.report().ends_with("\n  in deep\n  ... 2 more frames"));
+    }
+}
@@ -1,11 +1,94 @@
+use std::cell::Cell;
+use std::ptr::fn_addr_eq;
+use std::rc::Rc;
+use std::sync::Arc;
+
 use super::{
     accessor::Accessor,
-    builtins::{Primitive, SpecialForm},
+    builtins::{apply_arguments, unwind_to, wind_depth, Primitive, SpecialForm},
+    compiler::returns_value,
     environment::{EnvAccessor, Environment, SchemeEnvironment},
+    error::{ErrorKind, Frame, SchemeError},
+    optimizer::optimize,
+    reader::Span,
     s_expression::*,
+    vm,
 };
 
-pub type EvalOutput = Result<SExpr, String>;
+pub type EvalOutput = Result<SExpr, SchemeError>;
+
+/// Pending continuations allowed by default, that is how deeply non-tail calls may nest.
+pub const DEFAULT_MAX_DEPTH: usize = 1_000_000;
+
+/// Builtins such as `load` or `eval` evaluate code by calling `eval` again, which does use the
+/// native stack, so how deeply these calls nest is bounded separately. Forms such as `time` and
+/// `dynamic-wind` rather leave their code to the evaluator.
+const MAX_NESTED_EVALS: usize = 64;
+
+/// How expressions are evaluated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Strategy {
+    /// Expressions are compiled to bytecode, which is run by a virtual machine.
+    #[default]
+    Bytecode,
+    /// Expressions are evaluated by walking them directly.
+    TreeWalking,
+}
+
+/// How an interpreter evaluates expressions. They are held by its root environment, so that
+/// evaluations started by builtins such as `eval` or `load` follow them too.
+#[derive(Clone, Debug)]
+pub struct Settings {
+    /// How many continuations an evaluation may keep pending before failing with
+    /// `ErrorKind::RecursionLimit`.
+    pub max_depth: usize,
+    pub strategy: Strategy,
+    /// Whether expressions are optimized before being evaluated.
+    pub optimize: bool,
+    /// After how many calls compound procedures are compiled to native code, if ever.
+    #[cfg(feature = "jit")]
+    pub jit_threshold: Option<usize>,
+    /// How many significant digits inexact numbers carry as big floats, if any.
+    #[cfg(feature = "bigfloat")]
+    pub float_precision: Option<usize>,
+    /// The arguments returned by `command-line`, those the process was started with if `None`.
+    pub command_line: Option<Arc<[String]>>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_MAX_DEPTH,
+            strategy: Strategy::default(),
+            optimize: false,
+            #[cfg(feature = "jit")]
+            jit_threshold: Some(super::jit::DEFAULT_JIT_THRESHOLD),
+            #[cfg(feature = "bigfloat")]
+            float_precision: None,
+            command_line: None,
+        }
+    }
+}
+
+impl Settings {
+    /// How many significant digits inexact numbers carry as big floats, never any unless the
+    /// `bigfloat` feature is enabled.
+    pub fn float_precision(&self) -> Option<usize> {
+        #[cfg(feature = "bigfloat")]
+        return self.float_precision;
+        #[cfg(not(feature = "bigfloat"))]
+        return None;
+    }
+}
+
+thread_local! {
+    static NESTED_EVALS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// The settings of the interpreter `env` belongs to.
+pub fn settings(env: &ProcedureEnv) -> Result<Settings, SchemeError> {
+    Ok(Environment::get_root(env.clone())?.access()?.settings())
+}
 
 pub struct Evaluator {
     root_environment: EnvAccessor<Environment>,
@@ -26,139 +109,938 @@ impl Evaluator {
         Self { root_environment: env }
     }
 
+    pub fn settings(&self) -> Result<Settings, SchemeError> {
+        Ok(self.root_environment.access()?.settings())
+    }
+
+    pub fn set_settings(&self, settings: Settings) -> Result<(), SchemeError> {
+        self.root_environment.access_mut()?.set_settings(settings);
+        Ok(())
+    }
+
     pub fn eval(&self, expression: &SExpr) -> EvalOutput {
-        eval(expression, self.root_environment.clone())
+        match self.settings()?.optimize {
+            true => {
+                eval(&optimize(expression, &self.root_environment), self.root_environment.clone())
+            }
+            false => eval(expression, self.root_environment.clone()),
+        }
     }
 }
 
-fn expand_args(args: &ListImplementation, env: ProcedureEnv) -> Result<ListImplementation, String> {
-    let mut expanded_args = ListImplementation::new();
+pub fn eval(expression: &SExpr, env: ProcedureEnv) -> EvalOutput {
+    let settings = settings(&env)?;
 
-    for arg in args.iter() {
-        match arg {
-            SExpr::List(_) if arg.is_quoted_list() == Ok(true) => {
-                expanded_args.push(arg.unquote()?)
-            }
-            _ => expanded_args.push(eval(arg, env.clone())?),
+    nested(env.clone(), || match settings.strategy {
+        Strategy::Bytecode => vm::eval(expression, env, settings),
+        Strategy::TreeWalking => Machine::new(env, settings).run(Control::Eval(expression.clone())),
+    })
+}
+
+/// Calls `procedure` with arguments that are already evaluated.
+pub fn apply(procedure: &SExpr, args: ListImplementation, env: ProcedureEnv) -> EvalOutput {
+    let settings = settings(&env)?;
+
+    nested(env.clone(), || match settings.strategy {
+        Strategy::Bytecode => vm::apply(procedure, args, env, settings),
+        Strategy::TreeWalking => {
+            Machine::new(env, settings).run(Control::Apply(procedure.clone(), args))
         }
+    })
+}
+
+fn nested(env: ProcedureEnv, evaluation: impl FnOnce() -> EvalOutput) -> EvalOutput {
+    let nested = NESTED_EVALS.with(|nested| nested.replace(nested.get() + 1));
+    let winders = wind_depth();
+
+    let result = if nested < MAX_NESTED_EVALS {
+        evaluation()
+    } else {
+        Err(SchemeError::new(
+            ErrorKind::RecursionLimit,
+            format!("evaluation nested more than {} times within builtins", MAX_NESTED_EVALS),
+        ))
+    };
+
+    // An error leaves the dynamic-winds it was raised within
+    let result = result.map_err(|e| unwind_to(winders, e, env));
+
+    NESTED_EVALS.with(|nested| nested.set(nested.get() - 1));
+    result
+}
+
+/// The value of the variable `name` in `env`.
+pub fn lookup(env: &ProcedureEnv, name: &Symbol) -> EvalOutput {
+    variable_value(name, env.access()?.get(name))
+}
+
+/// The value of the variable `name` given its binding, if any.
+pub fn variable_value(name: &str, binding: Option<SExpr>) -> EvalOutput {
+    match binding {
+        Some(SExpr::Keyword(_)) => Err(SchemeError::new(
+            ErrorKind::Syntax,
+            format!("keyword {} cannot be used as a value", name),
+        )
+        .with_irritants([SExpr::symbol(name)])),
+        Some(value) => Ok(value),
+        None => Err(SchemeError::unbound_variable(name)),
     }
+}
 
-    Ok(expanded_args)
+/// The error raised when more than `max_depth` calls or continuations are pending.
+pub fn recursion_limit(max_depth: usize) -> SchemeError {
+    SchemeError::new(
+        ErrorKind::RecursionLimit,
+        format!("maximum recursion depth of {} exceeded", max_depth),
+    )
 }
 
-pub fn eval(expression: &SExpr, env: ProcedureEnv) -> EvalOutput {
-    let mut current_expression = expression.clone();
-    let mut current_env = env.clone();
-
-    loop {
-        match current_expression {
-            SExpr::Symbol(ref val) => {
-                return match current_env.access().get(val) {
-                    Some(v) => Ok(v),
-                    None => Err(format!(
-                        "Exception: in eval: could not find a value bound to <{}>",
-                        val
-                    )),
+pub fn is_true(value: &SExpr) -> bool {
+    !matches!(value, SExpr::Boolean(false))
+}
+
+pub fn is_keyword(expression: Option<&SExpr>, keyword: &str) -> bool {
+    matches!(expression, Some(SExpr::Symbol(symbol)) if symbol == keyword)
+}
+
+type Expressions = <ListImplementation as IntoIterator>::IntoIter;
+type Bindings = std::vec::IntoIter<(Symbol, SExpr)>;
+
+/// A `do` loop, shared by the continuations of all its iterations.
+struct DoLoop {
+    /// The name, initializer and optional step of each loop variable.
+    variables: Vec<(Symbol, SExpr, Option<SExpr>)>,
+    test: SExpr,
+    results: ListImplementation,
+    commands: ListImplementation,
+    env: ProcedureEnv,
+}
+
+/// What remains to be done with the value of the expression being evaluated.
+enum Continuation {
+    /// The value is the operator of an application, written as `operator`, read from `span`.
+    Operator {
+        operator: SExpr,
+        args: ListImplementation,
+        span: Option<Arc<Span>>,
+    },
+    /// The value is the argument following `evaluated`.
+    Argument {
+        procedure: Procedure,
+        name: Option<String>,
+        args: Expressions,
+        evaluated: ListImplementation,
+        span: Option<Arc<Span>>,
+    },
+    /// The value is the test of an `if`, followed by its branches.
+    If(Expressions),
+    /// The value is discarded and evaluation moves on to the rest of a body.
+    Sequence(Expressions),
+    Define(Symbol),
+    Set(Symbol),
+    /// The value initializes `name` in the environment of a `let`, `letrec` or `letrec*`.
+    Let {
+        name: Symbol,
+        bindings: Bindings,
+        let_env: ProcedureEnv,
+        body: Expressions,
+    },
+    /// The value initializes `name` in a new environment nested in the current one.
+    LetStar {
+        name: Symbol,
+        bindings: Bindings,
+        body: Expressions,
+    },
+    /// The value is the test of a `cond` clause, selecting `body` if true.
+    Cond {
+        body: ListImplementation,
+        clauses: Expressions,
+        otherwise: Option<SExpr>,
+    },
+    /// The value is the key of a `case`.
+    Case(Expressions),
+    /// The value is the receiver of a `=>` clause, to be called with the given argument.
+    Receiver(SExpr),
+    /// The value is the test of a `when`, or of an `unless` if `negated`.
+    When {
+        body: Expressions,
+        negated: bool,
+    },
+    And(Expressions),
+    Or(Expressions),
+    /// The value initializes the loop variable following `values`.
+    DoInit {
+        do_loop: Rc<DoLoop>,
+        values: Vec<SExpr>,
+    },
+    /// The value is the termination test of a loop iteration.
+    DoTest(Rc<DoLoop>),
+    /// The value is discarded and the loop moves on to the rest of its commands.
+    DoCommand {
+        do_loop: Rc<DoLoop>,
+        commands: Expressions,
+    },
+    /// The value is the step of the loop variable following `values`.
+    DoStep {
+        do_loop: Rc<DoLoop>,
+        values: Vec<SExpr>,
+    },
+}
+
+/// A continuation along with the environment and the procedure frame it resumes in.
+struct Pending {
+    continuation: Continuation,
+    env: ProcedureEnv,
+    frame: Option<Frame>,
+}
+
+enum Control {
+    Eval(SExpr),
+    Apply(SExpr, ListImplementation),
+    Return(SExpr),
+}
+
+/// Evaluates expressions keeping pending work on a heap allocated stack rather than on the
+/// native one, so that non-tail recursion is only limited by `max_depth`. Expressions in tail
+/// position never push a continuation, so loops run in constant space.
+struct Machine {
+    stack: Vec<Pending>,
+    env: ProcedureEnv,
+    /// The compound procedure currently being run. Tail calls replace the frame rather than
+    /// pushing one, so only their count is kept.
+    frame: Option<Frame>,
+    max_depth: usize,
+}
+
+impl Machine {
+    fn new(env: ProcedureEnv, settings: Settings) -> Self {
+        Self { stack: vec![], env, frame: None, max_depth: settings.max_depth }
+    }
+
+    fn run(mut self, mut control: Control) -> EvalOutput {
+        loop {
+            let next = match control {
+                Control::Eval(expression) => self.eval(expression),
+                Control::Apply(procedure, args) => self.apply_value(procedure, args),
+                Control::Return(value) => match self.stack.pop() {
+                    Some(pending) => {
+                        self.env = pending.env;
+                        self.frame = pending.frame;
+                        self.resume(pending.continuation, value)
+                    }
+                    None => return Ok(value),
+                },
+            };
+
+            control = match next {
+                Ok(control) => control,
+                Err(e) => return Err(self.unwind(e)),
+            };
+        }
+    }
+
+    /// Records on `error` the frames of the procedures still waiting for a value.
+    fn unwind(self, error: SchemeError) -> SchemeError {
+        let pending = self.stack.into_iter().rev().filter_map(|pending| pending.frame);
+        self.frame.into_iter().chain(pending).fold(error, SchemeError::with_frame)
+    }
+
+    fn push(&mut self, continuation: Continuation) -> Result<(), SchemeError> {
+        if self.stack.len() >= self.max_depth {
+            return Err(recursion_limit(self.max_depth));
+        }
+
+        let frame = self.frame.take();
+        self.stack.push(Pending { continuation, env: self.env.clone(), frame });
+        Ok(())
+    }
+
+    /// The binding of `name`, which is a keyword if it names a special form.
+    fn binding(&self, name: &Symbol) -> EvalOutput {
+        self.env.access()?.get(name).ok_or_else(|| SchemeError::unbound_variable(name))
+    }
+
+    /// The value of the variable `name`.
+    fn lookup(&self, name: &Symbol) -> EvalOutput {
+        lookup(&self.env, name)
+    }
+
+    fn eval(&mut self, expression: SExpr) -> Result<Control, SchemeError> {
+        match expression {
+            SExpr::Symbol(ref name) => Ok(Control::Return(self.lookup(name)?)),
+            SExpr::Null => Err(SchemeError::syntax("eval", "empty combination ()")),
+            SExpr::Pair(ref pair) => {
+                let (operator, args) = {
+                    let pair = pair.access()?;
+                    match pair.cdr.as_list() {
+                        Ok(args) => (pair.car.clone(), args),
+                        Err(_) => {
+                            return Err(SchemeError::syntax(
+                                "eval",
+                                format!("improper combination {}", expression),
+                            ))
+                        }
+                    }
+                };
+
+                let span = expression.span();
+                match operator {
+                    SExpr::Symbol(ref name) => {
+                        let value = self.binding(name)?;
+                        self.apply_operator(value, operator, args, span)
+                    }
+                    _ => {
+                        let continuation =
+                            Continuation::Operator { operator: operator.clone(), args, span };
+                        self.push(continuation)?;
+                        Ok(Control::Eval(operator))
+                    }
+                }
+            }
+            other => Ok(Control::Return(other)),
+        }
+    }
+
+    fn resume(&mut self, continuation: Continuation, value: SExpr) -> Result<Control, SchemeError> {
+        match continuation {
+            Continuation::Operator { operator, args, span } => {
+                self.apply_operator(value, operator, args, span)
+            }
+            Continuation::Argument { procedure, name, args, mut evaluated, span } => {
+                evaluated.push(value);
+                self.next_argument(procedure, name, args, evaluated, span)
+            }
+            Continuation::If(mut branches) => {
+                let consequent = branches.next();
+                let alternative = branches.next();
+                let branch = if is_true(&value) { consequent } else { alternative };
+
+                Ok(branch.map_or(Control::Return(SExpr::Ok), Control::Eval))
+            }
+            Continuation::Sequence(body) => self.sequence(body),
+            Continuation::Define(name) => match self.env.access_mut()?.define(&name, &value) {
+                Ok(_) => Ok(Control::Return(SExpr::Ok)),
+                Err(_) => Err(SchemeError::syntax("define", format!("error defining {}", name))),
+            },
+            Continuation::Set(name) => {
+                self.env.access_mut()?.set(&name, &value)?;
+                Ok(Control::Return(SExpr::Ok))
+            }
+            Continuation::Let { name, bindings, let_env, body } => {
+                let_env.access_mut()?.define(&name, &value)?;
+                self.next_binding(bindings, let_env, body)
+            }
+            Continuation::LetStar { name, bindings, body } => {
+                self.env = Environment::new_child(self.env.clone());
+                self.env.access_mut()?.define(&name, &value)?;
+                self.next_sequential_binding(bindings, body)
+            }
+            Continuation::Cond { body, clauses, otherwise } => match is_true(&value) {
+                true => self.clause_body("cond", value, body),
+                false => self.next_clause(clauses, otherwise),
+            },
+            Continuation::Case(clauses) => self.case_clause(value, clauses),
+            Continuation::Receiver(arg) => {
+                Ok(Control::Apply(value, ListImplementation::from_iter([arg])))
+            }
+            Continuation::When { body, negated } => match is_true(&value) != negated {
+                true => self.sequence(body),
+                false => Ok(Control::Return(SExpr::Unspecified)),
+            },
+            Continuation::And(args) => match is_true(&value) {
+                true => self.next_operand(args, Continuation::And),
+                false => Ok(Control::Return(value)),
+            },
+            Continuation::Or(args) => match is_true(&value) {
+                true => Ok(Control::Return(value)),
+                false => self.next_operand(args, Continuation::Or),
+            },
+            Continuation::DoInit { do_loop, mut values } => {
+                values.push(value);
+                self.do_init(do_loop, values)
+            }
+            Continuation::DoTest(do_loop) => match is_true(&value) {
+                true if do_loop.results.is_empty() => Ok(Control::Return(SExpr::Unspecified)),
+                true => self.sequence(do_loop.results.clone().into_iter()),
+                false => {
+                    let commands = do_loop.commands.clone().into_iter();
+                    self.do_command(do_loop, commands)
+                }
+            },
+            Continuation::DoCommand { do_loop, commands } => self.do_command(do_loop, commands),
+            Continuation::DoStep { do_loop, mut values } => {
+                values.push(value);
+                self.do_step(do_loop, values)
+            }
+        }
+    }
+
+    fn apply_operator(
+        &mut self, operator_value: SExpr, operator: SExpr, args: ListImplementation,
+        span: Option<Arc<Span>>,
+    ) -> Result<Control, SchemeError> {
+        match operator_value {
+            SExpr::Keyword(form) => self.special_form(form, args),
+            SExpr::Procedure(procedure) => {
+                let name = match operator {
+                    SExpr::Symbol(name) => Some(name.to_string()),
+                    _ => None,
+                };
+
+                let evaluated = ListImplementation::new();
+                self.next_argument(procedure, name, args.into_iter(), evaluated, span)
+            }
+            non_proc => Err(SchemeError::wrong_type("eval", &non_proc, "a procedure")),
+        }
+    }
+
+    /// Evaluates the remaining `args` of an application, applying `procedure` once all of them
+    /// have a value. Arguments needing no evaluation step are handled without a continuation.
+    fn next_argument(
+        &mut self, procedure: Procedure, name: Option<String>, mut args: Expressions,
+        mut evaluated: ListImplementation, span: Option<Arc<Span>>,
+    ) -> Result<Control, SchemeError> {
+        while let Some(arg) = args.next() {
+            match arg {
+                SExpr::Pair(_) | SExpr::Null => {
+                    let continuation =
+                        Continuation::Argument { procedure, name, args, evaluated, span };
+                    self.push(continuation)?;
+                    return Ok(Control::Eval(arg));
+                }
+                SExpr::Symbol(ref name) => evaluated.push(self.lookup(name)?),
+                other => evaluated.push(other),
+            }
+        }
+
+        self.apply(procedure, name, evaluated, span)
+    }
+
+    fn apply_value(
+        &mut self, procedure: SExpr, args: ListImplementation,
+    ) -> Result<Control, SchemeError> {
+        match procedure {
+            SExpr::Procedure(procedure) => self.apply(procedure, None, args, None),
+            other => Err(SchemeError::wrong_type("apply", &other, "a procedure")),
+        }
+    }
+
+    /// Calls `procedure` with evaluated `args`. Compound procedures are run in place of the
+    /// expression that called them, so that the call is a proper tail call. The frame of the
+    /// call points at `span`, where the expression was read from.
+    fn apply(
+        &mut self, procedure: Procedure, name: Option<String>, args: ListImplementation,
+        span: Option<Arc<Span>>,
+    ) -> Result<Control, SchemeError> {
+        match procedure {
+            Procedure::Primitive(primitive) => {
+                if fn_addr_eq(primitive, Primitive::APPLY) {
+                    let (procedure, args) = apply_arguments(args)?;
+                    return Ok(Control::Apply(procedure, args));
+                }
+
+                if fn_addr_eq(primitive, Primitive::EVAL) {
+                    if let Some(SExpr::Environment(env)) = args.s_cadr() {
+                        self.env = env.clone();
+                    }
+                    return Ok(Control::Eval(primitive(args, self.env.clone())?));
+                }
+
+                if fn_addr_eq(primitive, Primitive::DYNAMIC_WIND) {
+                    return Ok(Control::Eval(primitive(args, self.env.clone())?));
+                }
+
+                Ok(Control::Return(primitive(args, self.env.clone())?))
+            }
+            Procedure::Compound(ref lambda, ref closure_env) => {
+                let lambda_env = bind_arguments(&procedure, lambda, closure_env, args)?;
+
+                let name = name.unwrap_or_else(|| SExpr::Procedure(procedure.clone()).to_string());
+                let tail_calls = match self.frame {
+                    Some(ref frame) => frame.tail_calls + 1,
+                    None => 0,
+                };
+                let span = span.map(|span| span.as_ref().clone());
+                self.frame = Some(Frame { span, tail_calls, ..Frame::new(name) });
+
+                self.env = lambda_env;
+                self.sequence(lambda.body.clone().into_iter())
+            }
+        }
+    }
+
+    /// Evaluates `body` in order, the last expression being in tail position.
+    fn sequence(&mut self, mut body: Expressions) -> Result<Control, SchemeError> {
+        match body.next() {
+            Some(expression) if body.len() == 0 => Ok(Control::Eval(expression)),
+            Some(expression) => {
+                self.push(Continuation::Sequence(body))?;
+                Ok(Control::Eval(expression))
+            }
+            None => Err(SchemeError::arity("begin", "at least 1 argument", 0)),
+        }
+    }
+
+    fn special_form(
+        &mut self, form: SpecialFormSignature, args: ListImplementation,
+    ) -> Result<Control, SchemeError> {
+        if fn_addr_eq(form, SpecialForm::IF) {
+            self.eval_if(args)
+        } else if fn_addr_eq(form, SpecialForm::BEGIN) {
+            self.sequence(args.into_iter())
+        } else if fn_addr_eq(form, SpecialForm::DEFINE) {
+            match (args.s_len(), args.s_car(), args.s_cadr()) {
+                (2.., Some(SExpr::Symbol(name)), Some(value)) => {
+                    let value = value.clone();
+                    self.push(Continuation::Define(name.clone()))?;
+                    Ok(Control::Eval(value))
                 }
+                _ => Ok(Control::Return(form(args, self.env.clone())?)),
             }
-            SExpr::List(list) => {
-                if list.access().s_len() > 0 {
-                    let first = eval(list.access().s_car().unwrap(), current_env.clone());
-                    match first {
-                        Ok(res) => match res {
-                            SExpr::Procedure(proc) => {
-                                let args = list.access().s_cdr().unwrap();
-
-                                match proc {
-                                    Procedure::SpecialForm(special_form) => {
-                                        if special_form == SpecialForm::QUOTE
-                                            || special_form == SpecialForm::QUASIQUOTE
-                                            || special_form == SpecialForm::DEFINE
-                                            || special_form == SpecialForm::SET
-                                            || special_form == SpecialForm::LET
-                                            || special_form == SpecialForm::LET_STAR
-                                            || special_form == SpecialForm::TIME
-                                        {
-                                            let result = special_form(args, current_env.clone());
-                                            return match result {
-                                                Ok(expression) => Ok(expression),
-                                                Err(e) => Err(e),
-                                            };
-                                        } else {
-                                            let result = special_form(args, current_env.clone());
-                                            match result {
-                                                Ok(expression) => current_expression = expression,
-                                                Err(e) => return Err(e),
-                                            }
-                                        }
-                                    }
-                                    Procedure::Primitive(primitive) => {
-                                        let expanded_args =
-                                            expand_args(&args, current_env.clone())?;
-
-                                        if primitive != Primitive::EVAL
-                                            && primitive != Primitive::APPLY
-                                        {
-                                            return primitive(expanded_args, current_env.clone());
-                                        }
-
-                                        current_expression =
-                                            primitive(expanded_args, current_env.clone())?;
-                                    }
-                                    Procedure::Compound(
-                                        ref arg_names,
-                                        ref body,
-                                        ref closure_env,
-                                    ) => {
-                                        if arg_names.s_len() != args.s_len() {
-                                            return Err(String::from("Exception: found different lengths for arguments and their names"));
-                                        }
-
-                                        let expanded_args =
-                                            expand_args(&args, current_env.clone())?;
-
-                                        let lambda_env =
-                                            Environment::new_child(closure_env.clone());
-
-                                        for (name, arg) in
-                                            arg_names.iter().zip(expanded_args.iter())
-                                        {
-                                            if lambda_env.access_mut().define(&name, &arg).is_err()
-                                            {
-                                                return Err(String::from("Exception: could not bind value to the procedure frame"));
-                                            }
-                                        }
-
-                                        let eval_env = Environment::new_child(lambda_env);
-
-                                        let mut new = ListImplementation::new();
-                                        new.push(SExpr::Procedure(Procedure::SpecialForm(
-                                            SpecialForm::BEGIN,
-                                        )));
-                                        body.iter().for_each(|x| new.push(x.clone()));
-
-                                        current_expression = SExpr::List(SchemeList::new(new));
-                                        current_env = eval_env.clone();
-                                        continue;
-                                    }
-                                };
-                            }
-                            non_proc => {
-                                return Err(format!(
-                                    "Exception in eval: #<{}> is not a procedure",
-                                    non_proc
-                                ))
-                            }
-                        },
-                        Err(e) => return Err(e),
-                    };
-                } else {
-                    return Err(format!("Exception: wrong syntax {}", SExpr::List(list)));
+        } else if fn_addr_eq(form, SpecialForm::SET) {
+            self.eval_set(args)
+        } else if fn_addr_eq(form, SpecialForm::LET) {
+            match args.s_car() {
+                Some(SExpr::Symbol(name)) => {
+                    let name = name.clone();
+                    self.eval_named_let(name, args)
                 }
+                _ => self.eval_let("let", args),
+            }
+        } else if fn_addr_eq(form, SpecialForm::LET_STAR) {
+            self.eval_let("let*", args)
+        } else if fn_addr_eq(form, SpecialForm::LETREC) {
+            self.eval_let("letrec", args)
+        } else if fn_addr_eq(form, SpecialForm::LETREC_STAR) {
+            self.eval_let("letrec*", args)
+        } else if fn_addr_eq(form, SpecialForm::COND) {
+            self.eval_cond(args)
+        } else if fn_addr_eq(form, SpecialForm::CASE) {
+            self.eval_case(args)
+        } else if fn_addr_eq(form, SpecialForm::WHEN) || fn_addr_eq(form, SpecialForm::UNLESS) {
+            self.eval_when(args, fn_addr_eq(form, SpecialForm::UNLESS))
+        } else if fn_addr_eq(form, SpecialForm::AND) {
+            self.next_operand(args.into_iter(), Continuation::And)
+        } else if fn_addr_eq(form, SpecialForm::OR) {
+            self.next_operand(args.into_iter(), Continuation::Or)
+        } else if fn_addr_eq(form, SpecialForm::DO) {
+            self.eval_do(args)
+        } else {
+            let result = form(args, self.env.clone())?;
+
+            match returns_value(form) {
+                true => Ok(Control::Return(result)),
+                false => Ok(Control::Eval(result)),
             }
-            other => return Ok(other),
         }
     }
+
+    fn eval_if(&mut self, args: ListImplementation) -> Result<Control, SchemeError> {
+        let length = args.s_len();
+        if length != 2 && length != 3 {
+            return Err(SchemeError::arity("if", "two or three arguments", length));
+        }
+
+        let mut args = args.into_iter();
+        match args.next() {
+            Some(test) => {
+                self.push(Continuation::If(args))?;
+                Ok(Control::Eval(test))
+            }
+            None => Ok(Control::Return(SExpr::Ok)),
+        }
+    }
+
+    fn eval_set(&mut self, args: ListImplementation) -> Result<Control, SchemeError> {
+        let length = args.s_len();
+        if length != 2 {
+            return Err(SchemeError::arity("set!", "2 arguments", length));
+        }
+
+        match (args.s_car(), args.s_cadr()) {
+            (Some(SExpr::Symbol(name)), Some(value)) => {
+                let value = value.clone();
+                self.push(Continuation::Set(name.clone()))?;
+                Ok(Control::Eval(value))
+            }
+            (Some(other), _) => Err(SchemeError::wrong_type("set!", other, "a symbol")),
+            _ => Err(SchemeError::arity("set!", "2 arguments", length)),
+        }
+    }
+
+    /// Evaluates `let`, `let*`, `letrec` and `letrec*`, whose bodies are all in tail position.
+    fn eval_let(&mut self, form: &str, args: ListImplementation) -> Result<Control, SchemeError> {
+        let length = args.s_len();
+        if length < 2 {
+            return Err(SchemeError::arity(form, "at least 2 arguments", length));
+        }
+
+        let mut args = args.into_iter();
+        let bindings = match args.next() {
+            Some(bindings) => let_bindings(form, &bindings)?,
+            None => vec![],
+        };
+
+        match form {
+            "let*" => self.next_sequential_binding(bindings.into_iter(), args),
+            "letrec" | "letrec*" => {
+                let let_env = Environment::new_child(self.env.clone());
+                for (name, _) in bindings.iter() {
+                    let_env.access_mut()?.define(name, &SExpr::Unspecified)?;
+                }
+
+                // Initializers are evaluated where the bound names are already visible
+                self.env = let_env.clone();
+                self.next_binding(bindings.into_iter(), let_env, args)
+            }
+            _ => {
+                let let_env = Environment::new_child(self.env.clone());
+                self.next_binding(bindings.into_iter(), let_env, args)
+            }
+        }
+    }
+
+    /// Evaluates `(let name bindings body...)` as a call to a procedure bound to `name` within
+    /// its own body.
+    fn eval_named_let(
+        &mut self, name: Symbol, args: ListImplementation,
+    ) -> Result<Control, SchemeError> {
+        let length = args.s_len();
+        if length < 3 {
+            return Err(SchemeError::arity("let", "at least 3 arguments", length));
+        }
+
+        let mut args = args.into_iter().skip(1);
+        let bindings = match args.next() {
+            Some(bindings) => let_bindings("let", &bindings)?,
+            None => vec![],
+        };
+        let (arg_names, inits): (Vec<Symbol>, ListImplementation) = bindings.into_iter().unzip();
+
+        let loop_env = Environment::new_child(self.env.clone());
+        let procedure =
+            Procedure::Compound(Lambda::new(arg_names, args.collect()), loop_env.clone());
+        loop_env.access_mut()?.define(&name, &SExpr::Procedure(procedure.clone()))?;
+
+        let evaluated = ListImplementation::new();
+        self.next_argument(procedure, Some(name.to_string()), inits.into_iter(), evaluated, None)
+    }
+
+    fn next_binding(
+        &mut self, mut bindings: Bindings, let_env: ProcedureEnv, body: Expressions,
+    ) -> Result<Control, SchemeError> {
+        match bindings.next() {
+            Some((name, init)) => {
+                self.push(Continuation::Let { name, bindings, let_env, body })?;
+                Ok(Control::Eval(init))
+            }
+            None => {
+                self.env = let_env;
+                self.sequence(body)
+            }
+        }
+    }
+
+    fn next_sequential_binding(
+        &mut self, mut bindings: Bindings, body: Expressions,
+    ) -> Result<Control, SchemeError> {
+        match bindings.next() {
+            Some((name, init)) => {
+                self.push(Continuation::LetStar { name, bindings, body })?;
+                Ok(Control::Eval(init))
+            }
+            None => self.sequence(body),
+        }
+    }
+
+    fn eval_cond(&mut self, args: ListImplementation) -> Result<Control, SchemeError> {
+        let length = args.s_len();
+        if length == 0 {
+            return Err(SchemeError::arity("cond", "at least 1 argument", length));
+        }
+
+        // Besides `(else expression...)` clauses, a trailing `else expression` is accepted
+        if length > 3 && is_keyword(args.s_ref(length - 2), "else") {
+            let otherwise = args.last().cloned();
+            self.next_clause(args.extract_range(0, length - 2).into_iter(), otherwise)
+        } else {
+            self.next_clause(args.into_iter(), None)
+        }
+    }
+
+    fn next_clause(
+        &mut self, mut clauses: Expressions, otherwise: Option<SExpr>,
+    ) -> Result<Control, SchemeError> {
+        let clause = match clauses.next() {
+            Some(clause @ SExpr::Pair(_)) => match clause.as_list() {
+                Ok(clause) => clause,
+                Err(_) => return Err(SchemeError::syntax("cond", "clauses must be lists")),
+            },
+            Some(_) => return Err(SchemeError::syntax("cond", "clauses must be lists")),
+            None => return Ok(otherwise.map_or(Control::Return(SExpr::Ok), Control::Eval)),
+        };
+
+        let mut clause = clause.into_iter();
+        match clause.next() {
+            Some(ref test) if is_keyword(Some(test), "else") && clause.len() > 0 => {
+                self.sequence(clause)
+            }
+            Some(test) => {
+                self.push(Continuation::Cond { body: clause.collect(), clauses, otherwise })?;
+                Ok(Control::Eval(test))
+            }
+            None => Err(SchemeError::syntax("cond", "malformed clause")),
+        }
+    }
+
+    /// Evaluates the body of the `cond` or `case` clause selected by `value`, which is passed to
+    /// the receiver of a `=>` clause and is the result of a clause without expressions.
+    fn clause_body(
+        &mut self, form: &str, value: SExpr, body: ListImplementation,
+    ) -> Result<Control, SchemeError> {
+        if !is_keyword(body.s_car(), "=>") {
+            return match body.is_empty() {
+                true => Ok(Control::Return(value)),
+                false => self.sequence(body.into_iter()),
+            };
+        }
+
+        match (body.s_len(), body.s_cadr()) {
+            (2, Some(receiver)) => {
+                let receiver = receiver.clone();
+                self.push(Continuation::Receiver(value))?;
+                Ok(Control::Eval(receiver))
+            }
+            _ => Err(SchemeError::syntax(form, "malformed => clause")),
+        }
+    }
+
+    fn eval_case(&mut self, args: ListImplementation) -> Result<Control, SchemeError> {
+        let length = args.s_len();
+        if length < 2 {
+            return Err(SchemeError::arity("case", "at least 2 arguments", length));
+        }
+
+        let mut args = args.into_iter();
+        match args.next() {
+            Some(key) => {
+                self.push(Continuation::Case(args))?;
+                Ok(Control::Eval(key))
+            }
+            None => Ok(Control::Return(SExpr::Unspecified)),
+        }
+    }
+
+    fn case_clause(&mut self, key: SExpr, clauses: Expressions) -> Result<Control, SchemeError> {
+        for clause in clauses {
+            let clause = match clause {
+                SExpr::Pair(_) => clause.as_list()?,
+                other => return Err(SchemeError::wrong_type("case", &other, "a clause")),
+            };
+
+            let selected = match clause.s_car() {
+                Some(data @ (SExpr::Pair(_) | SExpr::Null)) => {
+                    data.as_list()?.iter().any(|datum| datum.is_eqv(&key))
+                }
+                Some(SExpr::Symbol(symbol)) if symbol == "else" => true,
+                _ => return Err(SchemeError::syntax("case", "malformed clause")),
+            };
+
+            if selected {
+                let body = clause.s_cdr().unwrap_or_default();
+                return self.clause_body("case", key, body);
+            }
+        }
+
+        Ok(Control::Return(SExpr::Unspecified))
+    }
+
+    fn eval_when(
+        &mut self, args: ListImplementation, negated: bool,
+    ) -> Result<Control, SchemeError> {
+        let length = args.s_len();
+        if length < 2 {
+            let form = if negated { "unless" } else { "when" };
+            return Err(SchemeError::arity(form, "at least 2 arguments", length));
+        }
+
+        let mut args = args.into_iter();
+        match args.next() {
+            Some(test) => {
+                self.push(Continuation::When { body: args, negated })?;
+                Ok(Control::Eval(test))
+            }
+            None => Ok(Control::Return(SExpr::Unspecified)),
+        }
+    }
+
+    /// Evaluates the next operand of `and` or `or`, the last one being in tail position.
+    fn next_operand(
+        &mut self, mut args: Expressions, continuation: fn(Expressions) -> Continuation,
+    ) -> Result<Control, SchemeError> {
+        match args.next() {
+            Some(arg) if args.len() == 0 => Ok(Control::Eval(arg)),
+            Some(arg) => {
+                self.push(continuation(args))?;
+                Ok(Control::Eval(arg))
+            }
+            None => Ok(Control::Return(SExpr::Boolean(true))),
+        }
+    }
+
+    fn eval_do(&mut self, args: ListImplementation) -> Result<Control, SchemeError> {
+        let length = args.s_len();
+        let mut args = args.into_iter();
+        let (Some(variables), Some(exit)) = (args.next(), args.next()) else {
+            return Err(SchemeError::arity("do", "at least 2 arguments", length));
+        };
+
+        let variables = match variables.as_list() {
+            Ok(list) => list.iter().map(do_variable).collect::<Result<Vec<_>, _>>()?,
+            Err(_) => return Err(SchemeError::wrong_type("do", &variables, "a list")),
+        };
+
+        let mut exit = match exit {
+            SExpr::Pair(_) if exit.list_length().is_some() => exit.as_list()?.into_iter(),
+            other => {
+                return Err(SchemeError::wrong_type("do", &other, "a (test result...) clause"))
+            }
+        };
+
+        let do_loop = DoLoop {
+            variables,
+            test: exit.next().unwrap_or(SExpr::Boolean(true)),
+            results: exit.collect(),
+            commands: args.collect(),
+            env: self.env.clone(),
+        };
+
+        self.do_init(Rc::new(do_loop), vec![])
+    }
+
+    fn do_init(&mut self, do_loop: Rc<DoLoop>, values: Vec<SExpr>) -> Result<Control, SchemeError> {
+        match do_loop.variables.get(values.len()) {
+            Some((_, init, _)) => {
+                let init = init.clone();
+                self.push(Continuation::DoInit { do_loop, values })?;
+                Ok(Control::Eval(init))
+            }
+            None => self.do_iterate(do_loop, values),
+        }
+    }
+
+    /// Starts an iteration of the loop, binding its variables afresh to `values`.
+    fn do_iterate(
+        &mut self, do_loop: Rc<DoLoop>, values: Vec<SExpr>,
+    ) -> Result<Control, SchemeError> {
+        let env = Environment::new_child(do_loop.env.clone());
+        for ((name, _, _), value) in do_loop.variables.iter().zip(values.iter()) {
+            env.access_mut()?.define(name, value)?;
+        }
+
+        self.env = env;
+        let test = do_loop.test.clone();
+        self.push(Continuation::DoTest(do_loop))?;
+        Ok(Control::Eval(test))
+    }
+
+    fn do_command(
+        &mut self, do_loop: Rc<DoLoop>, mut commands: Expressions,
+    ) -> Result<Control, SchemeError> {
+        match commands.next() {
+            Some(command) => {
+                self.push(Continuation::DoCommand { do_loop, commands })?;
+                Ok(Control::Eval(command))
+            }
+            None => self.do_step(do_loop, vec![]),
+        }
+    }
+
+    /// Evaluates the steps of the loop variables, those without one keeping their value.
+    fn do_step(
+        &mut self, do_loop: Rc<DoLoop>, mut values: Vec<SExpr>,
+    ) -> Result<Control, SchemeError> {
+        while let Some((name, _, step)) = do_loop.variables.get(values.len()) {
+            match step {
+                Some(step) => {
+                    let step = step.clone();
+                    self.push(Continuation::DoStep { do_loop, values })?;
+                    return Ok(Control::Eval(step));
+                }
+                None => values.push(self.lookup(name)?),
+            }
+        }
+
+        self.do_iterate(do_loop, values)
+    }
+}
+
+/// Fails unless `lambda` takes `argc` arguments.
+pub fn check_arity(procedure: &Procedure, lambda: &Lambda, argc: usize) -> Result<(), SchemeError> {
+    match lambda.params.len() == argc {
+        true => Ok(()),
+        false => Err(SchemeError::arity(
+            &format!("{}", SExpr::Procedure(procedure.clone())),
+            &format!("{} arguments", lambda.params.len()),
+            argc,
+        )),
+    }
+}
+
+/// A new environment within that of `lambda` binding its parameters to `args`.
+fn bind_arguments(
+    procedure: &Procedure, lambda: &Lambda, closure_env: &ProcedureEnv, args: ListImplementation,
+) -> Result<ProcedureEnv, SchemeError> {
+    check_arity(procedure, lambda, args.s_len())?;
+
+    let lambda_env = Environment::new_child(closure_env.clone());
+
+    for (name, arg) in lambda.params.iter().zip(args.iter()) {
+        if lambda_env.access_mut()?.define(name, arg).is_err() {
+            return Err(SchemeError::new(
+                ErrorKind::UnboundVariable,
+                format!("could not bind {} in the procedure frame", name),
+            ));
+        }
+    }
+
+    Ok(lambda_env)
+}
+
+/// Splits a `(name init)` binding of a let form into its name and initializer.
+fn let_binding(form: &str, binding: &SExpr) -> Result<(Symbol, SExpr), SchemeError> {
+    let parts = match binding.as_list() {
+        Ok(binding) => match (binding.s_len(), binding.s_car(), binding.s_cadr()) {
+            (2, Some(SExpr::Symbol(name)), Some(init)) => Some((name.clone(), init.clone())),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    parts.ok_or_else(|| SchemeError::wrong_type(form, binding, "a (name value) binding"))
+}
+
+/// The bindings of a let form, copied so that no pair is locked while they are evaluated.
+pub fn let_bindings(form: &str, bindings: &SExpr) -> Result<Vec<(Symbol, SExpr)>, SchemeError> {
+    match bindings.as_list() {
+        Ok(list) => list.iter().map(|binding| let_binding(form, binding)).collect(),
+        Err(_) => Err(SchemeError::wrong_type(form, bindings, "a list")),
+    }
+}
+
+/// Splits a `(name init step)` variable of a `do` loop, where the step is optional.
+pub fn do_variable(variable: &SExpr) -> Result<(Symbol, SExpr, Option<SExpr>), SchemeError> {
+    let parts = match variable.as_list() {
+        Ok(variable) => match (variable.s_len(), variable.s_car(), variable.s_cadr()) {
+            (2 | 3, Some(SExpr::Symbol(name)), Some(init)) => {
+                Some((name.clone(), init.clone(), variable.s_ref(2).cloned()))
+            }
+            _ => None,
+        },
+        _ => None,
+    };
+
+    parts.ok_or_else(|| SchemeError::wrong_type("do", variable, "a (name init step) variable"))
 }
 
 #[cfg(test)]
@@ -0,0 +1,395 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    sync::{Mutex, TryLockError},
+};
+
+use super::{
+    accessor::{Accessor, Shared, WeakShared},
+    s_expression::{Cons, Lambda, Procedure, SExpr, SString},
+};
+
+/// How many objects are allocated before the first automatic collection.
+const INITIAL_THRESHOLD: usize = 10_000;
+
+/// A value held by the heap, which knows the heap objects it refers to.
+pub trait Trace {
+    const KIND: Kind;
+
+    /// Calls `visit` with the address of every heap object held.
+    fn trace(&self, visit: &mut dyn FnMut(*const ()));
+    /// Moves out every value held into `garbage`, breaking the cycles running through it.
+    fn clear(&mut self, garbage: &mut Vec<SExpr>);
+}
+
+/// An object of the heap, whatever it holds.
+pub trait Node {
+    fn kind(&self) -> Kind;
+    /// Like `Trace::trace`, but returns `false` instead of waiting when the object is in use.
+    fn trace(&self, visit: &mut dyn FnMut(*const ())) -> bool;
+    fn clear(&self, garbage: &mut Vec<SExpr>);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Pair,
+    Vector,
+    String,
+    Closure,
+    Environment,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 5] =
+        [Kind::Pair, Kind::Vector, Kind::String, Kind::Closure, Kind::Environment];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Pair => "pairs",
+            Kind::Vector => "vectors",
+            Kind::String => "strings",
+            Kind::Closure => "closures",
+            Kind::Environment => "environments",
+        }
+    }
+}
+
+/// The objects allocated on this thread. They are only referred to weakly, so the heap never
+/// keeps one alive, and collecting frees those that only cycles keep alive.
+struct Heap {
+    objects: Vec<WeakShared<dyn Node>>,
+    allocated: usize,
+    threshold: usize,
+    collections: usize,
+    freed: usize,
+}
+
+thread_local! {
+    static HEAP: RefCell<Heap> = RefCell::new(Heap {
+        objects: vec![],
+        allocated: 0,
+        threshold: INITIAL_THRESHOLD,
+        collections: 0,
+        freed: 0,
+    });
+}
+
+/// The values left to drop by `release`, which keeps the queue for the next values freed.
+#[derive(Default)]
+struct Released {
+    queue: Vec<SExpr>,
+    /// Whether a call of `release` is dropping the queue, to which nested calls add.
+    running: bool,
+}
+
+thread_local! {
+    static RELEASED: RefCell<Released> = RefCell::new(Released::default());
+}
+
+/// The counts of the objects alive on the heap and of what was collected so far.
+#[derive(Clone, Debug, Default)]
+pub struct Statistics {
+    pub live: Vec<(Kind, usize)>,
+    pub collections: usize,
+    pub freed: usize,
+}
+
+/// Adds a newly allocated object to the heap, collecting first if enough were allocated since
+/// the last collection.
+pub fn track(object: WeakShared<dyn Node>) {
+    let collect = HEAP.with_borrow_mut(|heap| {
+        heap.objects.push(object);
+        heap.allocated += 1;
+        heap.allocated >= heap.threshold
+    });
+
+    if collect {
+        self::collect();
+    }
+}
+
+/// Frees the objects that are no longer reachable, returning how many there were.
+///
+/// Objects referred to from outside the heap, such as the environment of the interpreter or the
+/// values in use by a running procedure, are roots. They are told apart by having more strong
+/// references than the heap objects referring to them account for. Whatever the roots do not
+/// reach is only kept alive by cycles, which are broken by clearing the objects.
+pub fn collect() -> usize {
+    let Ok(objects) = HEAP.try_with(|heap| std::mem::take(&mut heap.borrow_mut().objects)) else {
+        return 0;
+    };
+
+    let nodes: Vec<Shared<dyn Node>> = objects.iter().filter_map(WeakShared::upgrade).collect();
+    drop(objects);
+
+    let index: HashMap<*const (), usize> =
+        nodes.iter().enumerate().map(|(i, node)| (Shared::as_ptr(node) as *const (), i)).collect();
+
+    // Counts the references held outside the heap, less the one just taken by `nodes`
+    let mut external: Vec<usize> =
+        nodes.iter().map(|node| Shared::strong_count(node).saturating_sub(1)).collect();
+    let mut edges: Vec<Vec<usize>> = vec![vec![]; nodes.len()];
+    let mut busy = vec![false; nodes.len()];
+
+    for (i, node) in nodes.iter().enumerate() {
+        busy[i] = !node.trace(&mut |child| {
+            if let Some(&j) = index.get(&child) {
+                edges[i].push(j);
+            }
+        });
+        for &j in &edges[i] {
+            external[j] = external[j].saturating_sub(1);
+        }
+    }
+
+    // Objects in use hold references that could not be counted, so they are roots as well
+    let mut reachable = vec![false; nodes.len()];
+    let mut pending: Vec<usize> =
+        (0..nodes.len()).filter(|&i| external[i] > 0 || busy[i]).collect();
+    while let Some(i) = pending.pop() {
+        if !std::mem::replace(&mut reachable[i], true) {
+            pending.extend(edges[i].iter().filter(|&&j| !reachable[j]));
+        }
+    }
+
+    let mut garbage = vec![];
+    let mut freed = 0;
+    for (node, _) in nodes.iter().zip(&reachable).filter(|(_, &reachable)| !reachable) {
+        node.clear(&mut garbage);
+        freed += 1;
+    }
+
+    let survivors: Vec<WeakShared<dyn Node>> = nodes
+        .iter()
+        .zip(&reachable)
+        .filter(|(_, &reachable)| reachable)
+        .map(|(node, _)| Shared::downgrade(node))
+        .collect();
+
+    // Dropping the garbage may free more objects, which are no longer in the heap
+    drop(garbage);
+    drop(nodes);
+
+    HEAP.with_borrow_mut(|heap| {
+        let allocated = std::mem::replace(&mut heap.objects, survivors);
+        heap.objects.extend(allocated);
+        heap.allocated = 0;
+        heap.threshold = INITIAL_THRESHOLD.max(2 * heap.objects.len());
+        heap.collections += 1;
+        heap.freed += freed;
+    });
+
+    freed
+}
+
+/// Drops what `value` holds without recursing into the objects only it refers to. Those are
+/// queued instead and dropped one after the other by the outermost call on the thread, so that
+/// no chain of pairs, vectors, closures or environments is too long to free.
+pub fn release(value: &mut impl Trace) {
+    // A thread being torn down has no queue left, so the value is dropped with what it holds
+    let Ok(outermost) = RELEASED.try_with(|released| {
+        let mut released = released.borrow_mut();
+        value.clear(&mut released.queue);
+        !std::mem::replace(&mut released.running, true)
+    }) else {
+        return;
+    };
+
+    if outermost {
+        while let Some(next) = RELEASED.with_borrow_mut(|released| released.queue.pop()) {
+            drop(next);
+        }
+        RELEASED.with_borrow_mut(|released| released.running = false);
+    }
+}
+
+pub fn statistics() -> Statistics {
+    HEAP.with_borrow(|heap| {
+        let mut live: Vec<(Kind, usize)> = Kind::ALL.iter().map(|&kind| (kind, 0)).collect();
+        for node in heap.objects.iter().filter_map(WeakShared::upgrade) {
+            if let Some(count) = live.iter_mut().find(|(kind, _)| *kind == node.kind()) {
+                count.1 += 1;
+            }
+        }
+
+        Statistics { live, collections: heap.collections, freed: heap.freed }
+    })
+}
+
+/// Calls `visit` with the heap objects `value` refers to.
+pub fn trace_value(value: &SExpr, visit: &mut dyn FnMut(*const ())) {
+    match value {
+        SExpr::Pair(pair) => visit(pair.as_ptr()),
+        SExpr::Vector(vector) => visit(vector.as_ptr()),
+        SExpr::String(string) => visit(string.as_ptr()),
+        SExpr::Environment(env) => visit(env.as_ptr()),
+        SExpr::Procedure(Procedure::Compound(lambda, env)) => {
+            visit(Shared::as_ptr(lambda) as *const ());
+            visit(env.as_ptr());
+        }
+        _ => {}
+    }
+}
+
+impl<T: Trace> Node for Mutex<T> {
+    fn kind(&self) -> Kind {
+        T::KIND
+    }
+
+    fn trace(&self, visit: &mut dyn FnMut(*const ())) -> bool {
+        match self.try_lock() {
+            Ok(value) => value.trace(visit),
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner().trace(visit),
+            Err(TryLockError::WouldBlock) => return false,
+        }
+        true
+    }
+
+    fn clear(&self, garbage: &mut Vec<SExpr>) {
+        // Unreachable objects cannot be in use, so this never fails
+        if let Ok(mut value) = self.try_lock() {
+            value.clear(garbage);
+        }
+    }
+}
+
+impl<T: Trace> Node for RefCell<T> {
+    fn kind(&self) -> Kind {
+        T::KIND
+    }
+
+    fn trace(&self, visit: &mut dyn FnMut(*const ())) -> bool {
+        match self.try_borrow() {
+            Ok(value) => value.trace(visit),
+            Err(_) => return false,
+        }
+        true
+    }
+
+    fn clear(&self, garbage: &mut Vec<SExpr>) {
+        if let Ok(mut value) = self.try_borrow_mut() {
+            value.clear(garbage);
+        }
+    }
+}
+
+/// Closures are the environment they capture along with their lambda, which holds the quoted
+/// data of its body. Lambdas cannot be changed, but are traced for that data.
+impl Node for Lambda {
+    fn kind(&self) -> Kind {
+        Kind::Closure
+    }
+
+    fn trace(&self, visit: &mut dyn FnMut(*const ())) -> bool {
+        self.body.iter().for_each(|expression| trace_value(expression, visit));
+        true
+    }
+
+    fn clear(&self, _: &mut Vec<SExpr>) {}
+}
+
+impl Trace for Cons {
+    const KIND: Kind = Kind::Pair;
+
+    fn trace(&self, visit: &mut dyn FnMut(*const ())) {
+        trace_value(&self.car, visit);
+        trace_value(&self.cdr, visit);
+    }
+
+    fn clear(&mut self, garbage: &mut Vec<SExpr>) {
+        garbage.push(std::mem::replace(&mut self.car, SExpr::Null));
+        garbage.push(std::mem::replace(&mut self.cdr, SExpr::Null));
+    }
+}
+
+impl Trace for Vec<SExpr> {
+    const KIND: Kind = Kind::Vector;
+
+    fn trace(&self, visit: &mut dyn FnMut(*const ())) {
+        self.iter().for_each(|value| trace_value(value, visit));
+    }
+
+    fn clear(&mut self, garbage: &mut Vec<SExpr>) {
+        garbage.append(self);
+    }
+}
+
+impl Trace for SString {
+    const KIND: Kind = Kind::String;
+
+    fn trace(&self, _: &mut dyn FnMut(*const ())) {}
+
+    fn clear(&mut self, _: &mut Vec<SExpr>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        environment::{EnvAccessor, Environment, SchemeEnvironment},
+        s_expression::{ListImplementation, SchemePair, Symbol},
+    };
+
+    /// A list of two elements whose last pair points back to the first.
+    fn cycle() -> SExpr {
+        let list = SExpr::list([SExpr::from(1), SExpr::from(2)]);
+        if let Some(SExpr::Pair(last)) = list.cdr() {
+            last.access_mut().unwrap().cdr = list.clone();
+        }
+        list
+    }
+
+    fn live(kind: Kind) -> usize {
+        statistics().live.iter().find(|(k, _)| *k == kind).unwrap().1
+    }
+
+    #[test]
+    fn heap_collect_cycles() {
+        collect();
+        let pairs = live(Kind::Pair);
+
+        for _ in 0..100 {
+            cycle();
+        }
+
+        assert_eq!(live(Kind::Pair), pairs + 200);
+        assert_eq!(collect(), 200);
+        assert_eq!(live(Kind::Pair), pairs);
+    }
+
+    #[test]
+    fn heap_keep_roots() {
+        collect();
+
+        let list = cycle();
+
+        let held = SchemePair::new(Cons { car: list.clone(), cdr: SExpr::Null, span: None });
+        assert_eq!(collect(), 0);
+        assert_eq!(list.car().unwrap().to_string(), "1");
+
+        drop(list);
+        assert_eq!(collect(), 0);
+        assert_eq!(held.access().unwrap().car.car().unwrap().to_string(), "1");
+
+        drop(held);
+        assert_eq!(collect(), 2);
+    }
+
+    #[test]
+    fn heap_collect_closures() {
+        collect();
+        let environments = live(Kind::Environment);
+
+        for _ in 0..10 {
+            let env = EnvAccessor::new(Environment::new());
+            let lambda = Lambda::new(vec![], ListImplementation::new());
+            let closure = SExpr::Procedure(Procedure::Compound(lambda, env.clone()));
+            env.access_mut().unwrap().define(&Symbol::new("f"), &closure).unwrap();
+        }
+
+        assert_eq!(live(Kind::Environment), environments + 10);
+        assert_eq!(collect(), 20);
+        assert_eq!(live(Kind::Environment), environments);
+        assert_eq!(live(Kind::Closure), 0);
+    }
+}
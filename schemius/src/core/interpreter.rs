@@ -1,66 +1,107 @@
 use std::fs::read_to_string;
 use std::io::{self, Write};
+use std::path::Path;
 
-use crate::core::reader;
+use crate::core::reader::{self, Reader};
 use crate::scheme::prelude::PRELUDE;
 
+use super::builtins::with_source_file;
 use super::constants::tokens;
 use super::environment::Environment;
+use super::error::{ErrorKind, SchemeError};
 use super::evaluator::EvalOutput;
-use super::{evaluator::Evaluator, s_expression::SExpr};
+use super::{
+    evaluator::{Evaluator, Settings},
+    s_expression::SExpr,
+};
+
+pub use super::evaluator::{Strategy, DEFAULT_MAX_DEPTH};
+#[cfg(feature = "jit")]
+pub use super::jit::DEFAULT_JIT_THRESHOLD;
 
 pub struct Interpreter {
     current_expression: String,
     evaluator: Evaluator,
-    line_idx: usize,
-    lines: Vec<String>,
 }
 
 impl Interpreter {
-    pub fn new(environment: Option<Environment>) -> Self {
+    /// An interpreter evaluating in `environment`, or else in the standard environment, once the
+    /// prelude is evaluated there, which fails if `environment` lacks what the prelude uses.
+    pub fn new(environment: Option<Environment>) -> Result<Self, SchemeError> {
         let evaluator = match environment {
             Some(env) => Evaluator::new(Some(env)),
             None => Evaluator::default(),
         };
-        let source = PRELUDE.lines().fold("".to_string(), |current, next| current + next);
-        let mut prelude = source.to_string();
-        let expression = match reader::read(&mut prelude) {
-            Ok(expr) => expr,
-            Err(_) => SExpr::Unspecified,
-        };
 
-        match evaluator.eval(&expression) {
-            Ok(_) => {
-                Self { current_expression: String::new(), evaluator, line_idx: 0, lines: vec![] }
-            }
-            Err(_) => Self {
-                current_expression: String::new(),
-                evaluator: Evaluator::default(),
-                line_idx: 0,
-                lines: vec![],
-            },
-        }
+        let interpreter = Self { current_expression: String::new(), evaluator };
+        interpreter.eval_source(Reader::new(PRELUDE))?;
+
+        Ok(interpreter)
     }
 
-    fn read(&mut self, next_line: &dyn Fn(&mut Interpreter) -> Result<String, String>) {
-        loop {
-            // TODO: Handle the case of parentheses inside strings and all other similar cases
-            //      -> Only parenthes used as syntax elements should be considered here
-            let lparens =
-                self.current_expression.chars().filter(|c| c == &'(' || c == &'[').count();
-            let rparens =
-                self.current_expression.chars().filter(|c| c == &')' || c == &']').count();
-
-            if lparens == rparens {
-                break;
-            }
+    /// Sets the arguments returned by `command-line`, the first one being the program name.
+    pub fn set_command_line(&mut self, command_line: Vec<String>) {
+        self.change_settings(|settings| settings.command_line = Some(command_line.into()));
+    }
+
+    /// Sets how deeply non-tail calls may nest before evaluation fails with
+    /// `ErrorKind::RecursionLimit`, by default `DEFAULT_MAX_DEPTH`.
+    pub fn set_max_depth(&mut self, depth: usize) {
+        self.change_settings(|settings| settings.max_depth = depth);
+    }
+
+    /// Sets how expressions are evaluated, by default `Strategy::Bytecode`.
+    pub fn set_strategy(&mut self, strategy: Strategy) {
+        self.change_settings(|settings| settings.strategy = strategy);
+    }
+
+    /// Sets whether expressions are optimized before being evaluated, which is off by default.
+    /// Optimized code folds arithmetic on literal numbers, drops the branches of `if` made
+    /// unreachable by a constant test and calls primitives without looking their names up, as
+    /// long as no global variable bound to a primitive was bound to something else since.
+    pub fn set_optimize(&mut self, optimize: bool) {
+        self.change_settings(|settings| settings.optimize = optimize);
+    }
+
+    /// Sets after how many calls compound procedures run by the virtual machine are compiled
+    /// to native code, by default `DEFAULT_JIT_THRESHOLD`, or disables compilation if `None`.
+    #[cfg(feature = "jit")]
+    pub fn set_jit_threshold(&mut self, threshold: Option<usize>) {
+        self.change_settings(|settings| settings.jit_threshold = threshold);
+    }
+
+    /// Sets how many significant decimal digits inexact numbers carry, reading inexact literals
+    /// and converting exact numbers to big floats of that precision, or keeps them native
+    /// floats if `None`. Elementary functions of big floats are computed at their precision,
+    /// except those with complex results, such as `(sqrt -2.0)`, as complex numbers keep native
+    /// float parts.
+    #[cfg(feature = "bigfloat")]
+    pub fn set_float_precision(&mut self, digits: Option<usize>) {
+        self.change_settings(|settings| settings.float_precision = digits.filter(|d| *d > 0));
+    }
+
+    /// Reads `source` with the float precision of this interpreter.
+    fn reader<'a>(&self, source: &'a str) -> Reader<'a> {
+        let settings = self.evaluator.settings().expect("no evaluation is running");
+        Reader::new(source).with_float_precision(settings.float_precision())
+    }
 
-            let next = next_line(self).unwrap_or("".to_string());
+    fn change_settings(&mut self, change: impl FnOnce(&mut Settings)) {
+        // The root environment is only in use while an expression is being evaluated
+        let mut settings = self.evaluator.settings().expect("no evaluation is running");
+        change(&mut settings);
+        self.evaluator.set_settings(settings).expect("no evaluation is running");
+    }
+
+    fn read(&mut self, next_line: &dyn Fn(&mut Interpreter) -> Result<String, SchemeError>) {
+        while reader::is_incomplete(&self.current_expression) {
+            let next = next_line(self).unwrap_or(String::from(tokens::EOF));
 
-            if next.is_empty() || next.starts_with(tokens::PREFIX_COMMENT) {
-                continue;
+            if next == tokens::EOF {
+                break;
             }
 
+            self.current_expression.push('\n');
             self.current_expression.push_str(&next)
         }
     }
@@ -76,73 +117,81 @@ impl Interpreter {
         }
     }
 
-    fn print_error(&self, error: &str) {
-        println!("{}", error);
+    fn print_error(&self, error: &SchemeError) {
+        println!("{}", error.report());
     }
 
     fn main_loop(
-        &mut self, next_line: &dyn Fn(&mut Interpreter) -> Result<String, String>,
-    ) -> Result<(), String> {
-        let mut expression: Result<SExpr, String>;
-
-        Ok(loop {
+        &mut self, next_line: &dyn Fn(&mut Interpreter) -> Result<String, SchemeError>,
+    ) -> Result<(), SchemeError> {
+        loop {
             self.current_expression = next_line(self)?;
-            self.read(next_line);
-
-            if self.current_expression.is_empty()
-                || self.current_expression.starts_with(tokens::PREFIX_COMMENT)
-            {
-                continue;
-            }
 
             if self.current_expression == tokens::EOF {
                 break;
             }
 
-            expression = reader::read(&mut self.current_expression);
-            match self.eval(&expression?) {
-                Ok(expr) => self.print(&expr),
-                Err(e) => self.print_error(&e),
+            self.read(next_line);
+
+            for datum in self.reader(&self.current_expression) {
+                match datum.map_err(SchemeError::from).and_then(|datum| self.eval(&datum.expr)) {
+                    Ok(expr) => self.print(&expr),
+                    Err(e) => self.print_error(&e),
+                }
             }
-        })
+        }
+
+        Ok(())
     }
 
-    pub fn run_repl(&mut self) -> Result<(), String> {
+    pub fn run_repl(&mut self) -> Result<(), SchemeError> {
         self.main_loop(&read_line_from_repl)
     }
 
-    pub fn execute_file(&mut self, path: String) -> Result<(), String> {
-        match read_to_string(path.as_str()) {
-            Ok(file) => {
-                for line in file.lines() {
-                    self.lines.push(line.trim().to_string());
+    pub fn execute_file(&mut self, path: String) -> Result<(), SchemeError> {
+        let source = match read_to_string(path.as_str()) {
+            Ok(source) => source,
+            Err(e) => {
+                return Err(SchemeError::new(
+                    ErrorKind::Io,
+                    format!("could not read file {}: {}", path, e),
+                ))
+            }
+        };
+
+        with_source_file(Path::new(&path), || {
+            let precision = self.evaluator.settings()?.float_precision();
+            for datum in Reader::with_file(&source, &path).with_float_precision(precision) {
+                let datum = datum?;
+                match self.eval(&datum.expr) {
+                    Ok(expr) => self.print(&expr),
+                    Err(e) => self.print_error(&e.with_span(&datum.span)),
                 }
-                self.main_loop(&read_line_from_file)
             }
-            Err(_) => Err(format!("Could not read file {}", path)),
-        }
-    }
 
-    fn is_preliminarily_validated(&self, expression_string: &str) -> bool {
-        // TODO: Judge the validity of the approach and extend the function if it's worth
-        !((expression_string.trim().starts_with(tokens::OPEN_PAREN)
-            && !expression_string.trim_end().ends_with(tokens::CLOSED_PAREN))
-            || (expression_string.trim().starts_with(tokens::OPEN_BRACKET)
-                && !expression_string.trim_end().ends_with(tokens::CLOSED_BRACKET)))
+            Ok(())
+        })
     }
 
-    pub fn eval_expression(&mut self, expression_string: String) -> EvalOutput {
-        if !self.is_preliminarily_validated(&expression_string) {
-            return Err("Exception: Invalid syntax.".to_string());
+    /// Evaluates every datum read from `reader` in order, stopping at the first error.
+    fn eval_source(&self, reader: Reader) -> EvalOutput {
+        let mut result = SExpr::Unspecified;
+
+        for datum in reader {
+            result = self.eval(&datum?.expr)?;
         }
 
-        let mut line = expression_string.clone();
-        let expression: SExpr = reader::read(&mut line)?;
+        Ok(result)
+    }
 
-        self.eval(&expression)
+    /// Evaluates all the expressions in `expression_string`, returning the value of the last one.
+    pub fn eval_expression(&mut self, expression_string: String) -> EvalOutput {
+        self.eval_source(self.reader(&expression_string))
     }
 
-    pub fn eval_expression_no_print(&mut self, expression_string: String) -> Result<(), String> {
+    pub fn eval_expression_no_print(
+        &mut self, expression_string: String,
+    ) -> Result<(), SchemeError> {
         match self.eval_expression(expression_string) {
             Ok(_) => Ok(()),
             Err(e) => Err(e),
@@ -151,14 +200,16 @@ impl Interpreter {
 
     pub fn eval_expression_and_format(
         &mut self, expression_string: String,
-    ) -> Result<String, String> {
+    ) -> Result<String, SchemeError> {
         match self.eval_expression(expression_string) {
             Ok(expr) => Ok(format!("{}", expr)),
             Err(e) => Err(e),
         }
     }
 
-    pub fn eval_expression_and_print(&mut self, expression_string: String) -> Result<(), String> {
+    pub fn eval_expression_and_print(
+        &mut self, expression_string: String,
+    ) -> Result<(), SchemeError> {
         let result = self.eval_expression(expression_string)?;
         self.print(&result);
         Ok(())
@@ -167,30 +218,34 @@ impl Interpreter {
 
 impl Default for Interpreter {
     fn default() -> Self {
-        Self::new(None)
+        Self::new(None).expect("the prelude evaluates in the standard environment")
     }
 }
 
-fn read_line_from_file(interpreter: &mut Interpreter) -> Result<String, String> {
-    if interpreter.lines.len() > interpreter.line_idx {
-        let line = interpreter.lines[interpreter.line_idx].clone();
-        interpreter.line_idx += 1;
+fn read_line_from_repl(_: &mut Interpreter) -> Result<String, SchemeError> {
+    let io_error = |e: io::Error| SchemeError::new(ErrorKind::Io, e.to_string());
+    let mut line = String::new();
+
+    io::stdout().write_all(b"> ").map_err(io_error)?;
+    io::stdout().flush().map_err(io_error)?;
 
-        Ok(line)
-    } else {
-        Ok(String::from(tokens::EOF))
+    // Zero bytes read means standard input was closed
+    if io::stdin().read_line(&mut line).map_err(io_error)? == 0 {
+        return Ok(String::from(tokens::EOF));
     }
-}
 
-fn read_line_from_repl(_: &mut Interpreter) -> Result<String, String> {
-    let mut line = String::new();
+    Ok(line.trim().to_string())
+}
 
-    io::stdout().write_all(b"> ").unwrap();
-    io::stdout().flush().unwrap();
-    io::stdin().read_line(&mut line).unwrap();
+#[cfg(test)]
+mod tests {
+    use crate::core::environment::SchemeEnvironment;
 
-    line = line.strip_suffix('\n').or(line.strip_suffix('\r')).unwrap().to_string();
-    line.extend([' ']);
+    use super::*;
 
-    Ok(line.trim().to_string())
+    #[test]
+    fn interpreter_prelude_error() {
+        let error = Interpreter::new(Some(Environment::new())).err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::UnboundVariable);
+    }
 }
@@ -0,0 +1,877 @@
+use std::fmt;
+use std::mem;
+use std::ptr::fn_addr_eq;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+
+use cranelift_codegen::{
+    ir::{
+        condcodes::{FloatCC, IntCC},
+        types, AbiParam, Block, FuncRef, InstBuilder, MemFlags, Type, UserFuncName, Value,
+    },
+    settings::{self, Configurable},
+};
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext, Variable};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::{default_libcall_names, Linkage, Module};
+use num::ToPrimitive;
+
+use super::{
+    accessor::{Accessor, Shared},
+    builtins::{Primitive, SpecialForm},
+    compiler::MAX_NESTING_DEPTH,
+    environment::SchemeEnvironment,
+    s_expression::*,
+};
+
+/// Calls a compound procedure takes, by default, before its body is compiled to native code.
+pub const DEFAULT_JIT_THRESHOLD: usize = 1000;
+
+/// Non-tail calls native code may nest before handing the call back to the interpreter, whose
+/// stack is not the native one.
+const MAX_NATIVE_DEPTH: i64 = 4096;
+
+/// Times native code may give up before the procedure is left to the interpreter for good.
+const MAX_DEOPTIMIZATIONS: usize = 16;
+
+/// Native code works on machine words, so it is only generated when fixnums and flonums have
+/// 64 bits.
+const SUPPORTED: bool = NativeInt::BITS == 64 && mem::size_of::<NativeFloat>() == 8;
+
+/// What native code returns along with its value, telling whether the value is valid.
+const COMPLETED: i64 = 0;
+/// Returned when native code cannot go on, for instance when a fixnum operation overflows, so
+/// that the interpreter runs the whole call instead. Native code has no side effects, which
+/// makes running it again from the start safe.
+const DEOPTIMIZED: i64 = 1;
+
+/// How often a lambda was called and the native code compiled for it, if any.
+#[derive(Debug, Default)]
+pub struct JitState {
+    calls: AtomicUsize,
+    deoptimizations: AtomicUsize,
+    /// Set once the lambda became hot, to `None` if its body could not be compiled.
+    native: OnceLock<Option<Native>>,
+}
+
+/// Calls `lambda` with `args` through native code, compiling it if it was called `threshold`
+/// times. The interpreter is left to run the call whenever `None` is returned.
+pub fn call(
+    lambda: &Shared<Lambda>, env: &ProcedureEnv, args: &ListImplementation,
+    threshold: Option<usize>,
+) -> Option<SExpr> {
+    let threshold = threshold?;
+    let state = &lambda.jit;
+
+    let native = match state.native.get() {
+        Some(native) => native.as_ref()?,
+        None if state.calls.fetch_add(1, Ordering::Relaxed) + 1 < threshold => return None,
+        None => state.native.get_or_init(|| compile(lambda, env, args)).as_ref()?,
+    };
+
+    if state.deoptimizations.load(Ordering::Relaxed) >= MAX_DEOPTIMIZATIONS {
+        return None;
+    }
+
+    let value = native.run(lambda, env, args);
+    if value.is_none() {
+        state.deoptimizations.fetch_add(1, Ordering::Relaxed);
+    }
+    value
+}
+
+/// The machine representation of a value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    Fixnum,
+    Flonum,
+    Boolean,
+}
+
+impl Kind {
+    fn of(value: &SExpr) -> Option<Kind> {
+        match value {
+            SExpr::Number(SNumber::Int(_)) => Some(Kind::Fixnum),
+            SExpr::Number(SNumber::Float(_)) => Some(Kind::Flonum),
+            SExpr::Boolean(_) => Some(Kind::Boolean),
+            _ => None,
+        }
+    }
+
+    fn ir(self) -> Type {
+        match self {
+            Kind::Fixnum => types::I64,
+            Kind::Flonum => types::F64,
+            Kind::Boolean => types::I8,
+        }
+    }
+
+    /// The bits of `value` as passed to native code, if it is of this kind.
+    fn encode(self, value: &SExpr) -> Option<i64> {
+        match (self, value) {
+            (Kind::Fixnum, SExpr::Number(SNumber::Int(n))) => n.to_i64(),
+            (Kind::Flonum, SExpr::Number(SNumber::Float(f))) => Some(f.to_f64()?.to_bits() as i64),
+            (Kind::Boolean, SExpr::Boolean(b)) => Some(*b as i64),
+            _ => None,
+        }
+    }
+
+    fn decode(self, bits: i64) -> SExpr {
+        match self {
+            Kind::Fixnum => SExpr::Number(SNumber::Int(bits as NativeInt)),
+            Kind::Flonum => {
+                SExpr::Number(SNumber::Float(f64::from_bits(bits as u64) as NativeFloat))
+            }
+            Kind::Boolean => SExpr::Boolean(bits != 0),
+        }
+    }
+}
+
+/// What a name used as an operator must still be bound to for native code to be run.
+#[derive(Clone, Copy)]
+enum Guard {
+    Primitive(ProcedureSignature),
+    /// The procedure being compiled, which native code calls directly.
+    Itself,
+}
+
+#[derive(Clone, Copy)]
+enum Arithmetic {
+    Add,
+    Sub,
+    Mul,
+}
+
+#[derive(Clone, Copy)]
+enum Comparison {
+    Equal,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    fn int(self) -> IntCC {
+        match self {
+            Comparison::Equal => IntCC::Equal,
+            Comparison::Less => IntCC::SignedLessThan,
+            Comparison::LessOrEqual => IntCC::SignedLessThanOrEqual,
+            Comparison::Greater => IntCC::SignedGreaterThan,
+            Comparison::GreaterOrEqual => IntCC::SignedGreaterThanOrEqual,
+        }
+    }
+
+    fn float(self) -> FloatCC {
+        match self {
+            Comparison::Equal => FloatCC::Equal,
+            Comparison::Less => FloatCC::LessThan,
+            Comparison::LessOrEqual => FloatCC::LessThanOrEqual,
+            Comparison::Greater => FloatCC::GreaterThan,
+            Comparison::GreaterOrEqual => FloatCC::GreaterThanOrEqual,
+        }
+    }
+}
+
+/// An expression of the body of a procedure along with the kind of its value.
+struct Typed {
+    node: Node,
+    kind: Kind,
+}
+
+enum Node {
+    Fixnum(i64),
+    Flonum(f64),
+    Boolean(bool),
+    /// A parameter, or a free variable once the parameters are counted.
+    Input(usize),
+    ToFlonum(Box<Typed>),
+    Arithmetic(Arithmetic, Box<Typed>, Box<Typed>),
+    Comparison(Comparison, Vec<Typed>),
+    Not(Box<Typed>),
+    And(Vec<Typed>),
+    Or(Vec<Typed>),
+    If(Box<Typed>, Box<Typed>, Box<Typed>),
+    /// A call to the procedure itself.
+    Call(Vec<Typed>),
+}
+
+impl Typed {
+    fn new(node: Node, kind: Kind) -> Self {
+        Self { node, kind }
+    }
+
+    fn flonum(self) -> Typed {
+        match self.kind {
+            Kind::Fixnum => Typed::new(Node::ToFlonum(Box::new(self)), Kind::Flonum),
+            _ => self,
+        }
+    }
+}
+
+/// Gives the body of a procedure its kinds, given those of its parameters and of its value.
+/// Anything beyond fixnum and flonum arithmetic, comparisons, `if`, `and`, `or` and calls to
+/// the procedure itself is left to the interpreter.
+struct Typer<'a> {
+    lambda: &'a Shared<Lambda>,
+    env: &'a ProcedureEnv,
+    /// The kinds of the parameters followed by those of the free variables.
+    inputs: Vec<Kind>,
+    free: Vec<Symbol>,
+    guards: Vec<(Symbol, Guard)>,
+    result: Kind,
+    /// How many expressions enclose the one being typed. Bodies nested deeper than the compiler
+    /// recurses are left to the interpreter.
+    depth: usize,
+}
+
+impl Typer<'_> {
+    fn guard(&mut self, name: &Symbol, guard: Guard) {
+        if !self.guards.iter().any(|(other, _)| other == name) {
+            self.guards.push((name.clone(), guard));
+        }
+    }
+
+    fn variable(&mut self, name: &Symbol) -> Option<Typed> {
+        let params = &self.lambda.params;
+        if let Some(index) = params.iter().position(|param| param == name) {
+            return Some(Typed::new(Node::Input(index), self.inputs[index]));
+        }
+
+        let index = match self.free.iter().position(|free| free == name) {
+            Some(index) => index,
+            None => {
+                let kind = Kind::of(&self.env.access().ok()?.get(name)?)?;
+                self.free.push(name.clone());
+                self.inputs.push(kind);
+                self.free.len() - 1
+            }
+        };
+        Some(Typed::new(Node::Input(params.len() + index), self.inputs[params.len() + index]))
+    }
+
+    fn expression(&mut self, expression: &SExpr) -> Option<Typed> {
+        if self.depth == MAX_NESTING_DEPTH {
+            return None;
+        }
+
+        self.depth += 1;
+        let typed = self.nested_expression(expression);
+        self.depth -= 1;
+
+        typed
+    }
+
+    fn nested_expression(&mut self, expression: &SExpr) -> Option<Typed> {
+        match expression {
+            SExpr::Number(SNumber::Int(n)) => {
+                Some(Typed::new(Node::Fixnum(n.to_i64()?), Kind::Fixnum))
+            }
+            SExpr::Number(SNumber::Float(f)) => {
+                Some(Typed::new(Node::Flonum(f.to_f64()?), Kind::Flonum))
+            }
+            SExpr::Boolean(b) => Some(Typed::new(Node::Boolean(*b), Kind::Boolean)),
+            SExpr::Symbol(name) => self.variable(name),
+            SExpr::Pair(_) => {
+                let Some(SExpr::Symbol(operator)) = expression.car() else {
+                    return None;
+                };
+                let args: Vec<SExpr> = expression.cdr()?.as_list().ok()?.into_iter().collect();
+                if self.lambda.params.contains(&operator) {
+                    return None;
+                }
+
+                let binding = self.env.access().ok()?.get(&operator)?;
+                match binding {
+                    SExpr::Keyword(form) => self.special_form(form, &args),
+                    SExpr::Procedure(Procedure::Primitive(primitive)) => {
+                        self.guard(&operator, Guard::Primitive(primitive));
+                        self.primitive(primitive, &args)
+                    }
+                    SExpr::Procedure(Procedure::Compound(ref lambda, _))
+                        if Shared::ptr_eq(lambda, self.lambda) =>
+                    {
+                        self.guard(&operator, Guard::Itself);
+                        self.call(&args)
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn expressions(&mut self, expressions: &[SExpr]) -> Option<Vec<Typed>> {
+        expressions.iter().map(|expression| self.expression(expression)).collect()
+    }
+
+    fn special_form(&mut self, form: SpecialFormSignature, args: &[SExpr]) -> Option<Typed> {
+        if fn_addr_eq(form, SpecialForm::IF) {
+            let [test, consequent, alternative] = args else {
+                return None;
+            };
+            let (test, consequent, alternative) = (
+                self.expression(test)?,
+                self.expression(consequent)?,
+                self.expression(alternative)?,
+            );
+
+            // Only #f is false, so numbers always select the consequent
+            match (test.kind, consequent.kind == alternative.kind) {
+                (_, false) => None,
+                (Kind::Boolean, true) => {
+                    let kind = consequent.kind;
+                    let node =
+                        Node::If(Box::new(test), Box::new(consequent), Box::new(alternative));
+                    Some(Typed::new(node, kind))
+                }
+                _ => Some(consequent),
+            }
+        } else if fn_addr_eq(form, SpecialForm::AND) || fn_addr_eq(form, SpecialForm::OR) {
+            let operands = self.expressions(args)?;
+            if operands.iter().any(|operand| operand.kind != Kind::Boolean) {
+                return None;
+            }
+
+            let node = match fn_addr_eq(form, SpecialForm::AND) {
+                true => Node::And(operands),
+                false => Node::Or(operands),
+            };
+            Some(Typed::new(node, Kind::Boolean))
+        } else {
+            None
+        }
+    }
+
+    fn primitive(&mut self, primitive: ProcedureSignature, args: &[SExpr]) -> Option<Typed> {
+        let operands = self.expressions(args)?;
+        if fn_addr_eq(primitive, Primitive::NOT) {
+            let [operand] = <[Typed; 1]>::try_from(operands).ok()?;
+            return match operand.kind {
+                Kind::Boolean => Some(Typed::new(Node::Not(Box::new(operand)), Kind::Boolean)),
+                _ => Some(Typed::new(Node::Boolean(false), Kind::Boolean)),
+            };
+        }
+
+        if operands.iter().any(|operand| operand.kind == Kind::Boolean) {
+            return None;
+        }
+
+        let comparisons = [
+            (Primitive::EQUAL, Comparison::Equal),
+            (Primitive::LT, Comparison::Less),
+            (Primitive::LE, Comparison::LessOrEqual),
+            (Primitive::GT, Comparison::Greater),
+            (Primitive::GE, Comparison::GreaterOrEqual),
+        ];
+        if let Some((_, comparison)) =
+            comparisons.into_iter().find(|(other, _)| fn_addr_eq(primitive, *other))
+        {
+            return match operands.len() {
+                0 | 1 => None,
+                _ => Some(Typed::new(Node::Comparison(comparison, operands), Kind::Boolean)),
+            };
+        }
+
+        // Operands are combined from the left as the interpreter does, starting from the
+        // neutral element for sums and products
+        let mut operands = operands.into_iter();
+        let (operation, first) = if fn_addr_eq(primitive, Primitive::SUM) {
+            (Arithmetic::Add, Typed::new(Node::Fixnum(0), Kind::Fixnum))
+        } else if fn_addr_eq(primitive, Primitive::PROD) {
+            (Arithmetic::Mul, Typed::new(Node::Fixnum(1), Kind::Fixnum))
+        } else if fn_addr_eq(primitive, Primitive::DIFF) {
+            match operands.len() {
+                0 => return None,
+                1 => (Arithmetic::Sub, Typed::new(Node::Fixnum(0), Kind::Fixnum)),
+                _ => (Arithmetic::Sub, operands.next()?),
+            }
+        } else {
+            return None;
+        };
+
+        Some(operands.fold(first, |left, right| match (&left.node, left.kind, right.kind) {
+            // Adding a fixnum to zero or multiplying it by one leaves it as it is
+            (Node::Fixnum(0), _, Kind::Fixnum) if matches!(operation, Arithmetic::Add) => right,
+            (Node::Fixnum(1), _, Kind::Fixnum) if matches!(operation, Arithmetic::Mul) => right,
+            (_, Kind::Fixnum, Kind::Fixnum) => Typed::new(
+                Node::Arithmetic(operation, Box::new(left), Box::new(right)),
+                Kind::Fixnum,
+            ),
+            _ => {
+                let (left, right) = (left.flonum(), right.flonum());
+                Typed::new(
+                    Node::Arithmetic(operation, Box::new(left), Box::new(right)),
+                    Kind::Flonum,
+                )
+            }
+        }))
+    }
+
+    fn call(&mut self, args: &[SExpr]) -> Option<Typed> {
+        let args = self.expressions(args)?;
+        let params = &self.inputs[..self.lambda.params.len()];
+        if args.len() != params.len()
+            || args.iter().zip(params).any(|(arg, kind)| arg.kind != *kind)
+        {
+            return None;
+        }
+
+        Some(Typed::new(Node::Call(args), self.result))
+    }
+}
+
+/// A procedure compiled to native code for arguments of given kinds.
+pub struct Native {
+    /// Keeps the code alive, being freed along with the lambda.
+    module: Option<JITModule>,
+    entry: unsafe extern "C" fn(*const i64, *mut i64) -> i64,
+    inputs: Vec<Kind>,
+    free: Vec<Symbol>,
+    guards: Vec<(Symbol, Guard)>,
+    result: Kind,
+}
+
+// The module is only kept to free the code once no closure can run it any more
+unsafe impl Send for Native {}
+unsafe impl Sync for Native {}
+
+impl fmt::Debug for Native {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Native")
+            .field("inputs", &self.inputs)
+            .field("free", &self.free)
+            .field("result", &self.result)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for Native {
+    fn drop(&mut self) {
+        if let Some(module) = self.module.take() {
+            unsafe { module.free_memory() };
+        }
+    }
+}
+
+impl Native {
+    /// Runs the native code if `args` and the variables it depends on in `env` are still of
+    /// the kinds it was compiled for.
+    fn run(
+        &self, lambda: &Shared<Lambda>, env: &ProcedureEnv, args: &ListImplementation,
+    ) -> Option<SExpr> {
+        let mut inputs = Vec::from_iter(args.iter().cloned());
+        {
+            let env = env.access().ok()?;
+            for (name, guard) in self.guards.iter() {
+                let valid = match (guard, env.get(name)) {
+                    (
+                        Guard::Primitive(primitive),
+                        Some(SExpr::Procedure(Procedure::Primitive(p))),
+                    ) => fn_addr_eq(*primitive, p),
+                    (Guard::Itself, Some(SExpr::Procedure(Procedure::Compound(other, _)))) => {
+                        Shared::ptr_eq(lambda, &other)
+                    }
+                    _ => false,
+                };
+                if !valid {
+                    return None;
+                }
+            }
+            for name in self.free.iter() {
+                inputs.push(env.get(name)?);
+            }
+        }
+
+        if inputs.len() != self.inputs.len() {
+            return None;
+        }
+        let bits = inputs.iter().zip(self.inputs.iter()).map(|(input, kind)| kind.encode(input));
+        let bits = bits.collect::<Option<Vec<i64>>>()?;
+
+        let mut result = 0;
+        match unsafe { (self.entry)(bits.as_ptr(), &mut result) } {
+            COMPLETED => Some(self.result.decode(result)),
+            _ => None,
+        }
+    }
+}
+
+/// Compiles `lambda` for arguments of the kinds of `args`, trying each kind for its value.
+fn compile(
+    lambda: &Shared<Lambda>, env: &ProcedureEnv, args: &ListImplementation,
+) -> Option<Native> {
+    if !SUPPORTED || lambda.body.s_len() != 1 || args.s_len() != lambda.params.len() {
+        return None;
+    }
+
+    let params = args.iter().map(Kind::of).collect::<Option<Vec<Kind>>>()?;
+    let body = lambda.body.s_car()?;
+
+    [Kind::Fixnum, Kind::Flonum, Kind::Boolean].into_iter().find_map(|result| {
+        let inputs = params.clone();
+        let mut typer =
+            Typer { lambda, env, inputs, free: vec![], guards: vec![], result, depth: 0 };
+        let typed = typer.expression(body).filter(|typed| typed.kind == result)?;
+        let (module, entry) = generate(&typed, &typer.inputs, lambda.params.len(), result)?;
+
+        Some(Native {
+            module: Some(module),
+            entry,
+            inputs: typer.inputs,
+            free: typer.free,
+            guards: typer.guards,
+            result,
+        })
+    })
+}
+
+type Entry = unsafe extern "C" fn(*const i64, *mut i64) -> i64;
+
+/// Generates the native code of `body`, whose inputs are of kinds `inputs`, the first `params`
+/// of them being parameters. Returns the module holding the code with the function reading
+/// inputs from an array, writing the value and returning whether it completed.
+fn generate(
+    body: &Typed, inputs: &[Kind], params: usize, result: Kind,
+) -> Option<(JITModule, Entry)> {
+    let mut flags = settings::builder();
+    flags.set("opt_level", "speed").ok()?;
+    flags.set("use_colocated_libcalls", "false").ok()?;
+    flags.set("is_pic", "false").ok()?;
+    flags.set("enable_multi_ret_implicit_sret", "true").ok()?;
+    let isa = cranelift_native::builder().ok()?.finish(settings::Flags::new(flags)).ok()?;
+    let mut module = JITModule::new(JITBuilder::with_isa(isa, default_libcall_names()));
+    let pointer = module.target_config().pointer_type();
+
+    // The body takes the depth of the call and the inputs, returning a status and the value
+    let mut signature = module.make_signature();
+    signature.params.push(AbiParam::new(types::I64));
+    signature.params.extend(inputs.iter().map(|kind| AbiParam::new(kind.ir())));
+    signature.returns.extend([AbiParam::new(types::I64), AbiParam::new(types::I64)]);
+    let body_id = module.declare_function("body", Linkage::Local, &signature).ok()?;
+
+    let mut context = module.make_context();
+    let mut function_context = FunctionBuilderContext::new();
+    context.func.signature = signature.clone();
+    context.func.name = UserFuncName::user(0, body_id.as_u32());
+    {
+        let mut builder = FunctionBuilder::new(&mut context.func, &mut function_context);
+        let function = module.declare_func_in_func(body_id, builder.func);
+        let entry = builder.create_block();
+        let header = builder.create_block();
+        let deoptimize = builder.create_block();
+
+        builder.append_block_params_for_function_params(entry);
+        builder.switch_to_block(entry);
+        let values = builder.block_params(entry).to_vec();
+        let variables: Vec<Variable> = inputs
+            .iter()
+            .enumerate()
+            .map(|(index, kind)| {
+                let variable = Variable::from_u32(index as u32);
+                builder.declare_var(variable, kind.ir());
+                builder.def_var(variable, values[index + 1]);
+                variable
+            })
+            .collect();
+
+        let too_deep =
+            builder.ins().icmp_imm(IntCC::SignedGreaterThan, values[0], MAX_NATIVE_DEPTH);
+        builder.ins().brif(too_deep, deoptimize, &[], header, &[]);
+
+        builder.switch_to_block(deoptimize);
+        let (status, zero) =
+            (builder.ins().iconst(types::I64, DEOPTIMIZED), builder.ins().iconst(types::I64, 0));
+        builder.ins().return_(&[status, zero]);
+
+        builder.switch_to_block(header);
+        let mut emitter = Emitter {
+            builder,
+            function,
+            variables,
+            params,
+            depth: values[0],
+            header,
+            deoptimize,
+            result,
+        };
+        emitter.tail(body);
+
+        emitter.builder.seal_all_blocks();
+        emitter.builder.finalize();
+    }
+    module.define_function(body_id, &mut context).ok()?;
+    module.clear_context(&mut context);
+
+    let mut signature = module.make_signature();
+    signature.params.extend([AbiParam::new(pointer), AbiParam::new(pointer)]);
+    signature.returns.push(AbiParam::new(types::I64));
+    let entry_id = module.declare_function("entry", Linkage::Local, &signature).ok()?;
+    context.func.signature = signature;
+    context.func.name = UserFuncName::user(0, entry_id.as_u32());
+    {
+        let mut builder = FunctionBuilder::new(&mut context.func, &mut function_context);
+        let function = module.declare_func_in_func(body_id, builder.func);
+        let block = builder.create_block();
+        builder.append_block_params_for_function_params(block);
+        builder.switch_to_block(block);
+        let (input, output) = (builder.block_params(block)[0], builder.block_params(block)[1]);
+
+        let mut args = vec![builder.ins().iconst(types::I64, 0)];
+        for (index, kind) in inputs.iter().enumerate() {
+            let bits = builder.ins().load(types::I64, MemFlags::trusted(), input, index as i32 * 8);
+            args.push(match kind {
+                Kind::Fixnum => bits,
+                Kind::Flonum => builder.ins().bitcast(types::F64, MemFlags::new(), bits),
+                Kind::Boolean => builder.ins().ireduce(types::I8, bits),
+            });
+        }
+        let call = builder.ins().call(function, &args);
+        let (status, value) = (builder.inst_results(call)[0], builder.inst_results(call)[1]);
+        builder.ins().store(MemFlags::trusted(), value, output, 0);
+        builder.ins().return_(&[status]);
+
+        builder.seal_all_blocks();
+        builder.finalize();
+    }
+    module.define_function(entry_id, &mut context).ok()?;
+    module.clear_context(&mut context);
+
+    module.finalize_definitions().ok()?;
+    let code = module.get_finalized_function(entry_id);
+    let entry = unsafe { mem::transmute::<*const u8, Entry>(code) };
+    Some((module, entry))
+}
+
+/// Emits the instructions of the body of a procedure.
+struct Emitter<'a> {
+    builder: FunctionBuilder<'a>,
+    /// The body itself, for calls that are not in tail position.
+    function: FuncRef,
+    variables: Vec<Variable>,
+    params: usize,
+    depth: Value,
+    /// Where the body starts, which tail calls jump to once they rebound the parameters.
+    header: Block,
+    deoptimize: Block,
+    result: Kind,
+}
+
+impl Emitter<'_> {
+    /// Continues in a new block if `condition` is false and gives up otherwise.
+    fn deoptimize_if(&mut self, condition: Value) {
+        let next = self.builder.create_block();
+        self.builder.ins().brif(condition, self.deoptimize, &[], next, &[]);
+        self.builder.switch_to_block(next);
+    }
+
+    fn encode(&mut self, value: Value, kind: Kind) -> Value {
+        match kind {
+            Kind::Fixnum => value,
+            Kind::Flonum => self.builder.ins().bitcast(types::I64, MemFlags::new(), value),
+            Kind::Boolean => self.builder.ins().uextend(types::I64, value),
+        }
+    }
+
+    fn decode(&mut self, bits: Value, kind: Kind) -> Value {
+        match kind {
+            Kind::Fixnum => bits,
+            Kind::Flonum => self.builder.ins().bitcast(types::F64, MemFlags::new(), bits),
+            Kind::Boolean => self.builder.ins().ireduce(types::I8, bits),
+        }
+    }
+
+    /// Emits `typed` in tail position, returning its value or jumping back to the header.
+    fn tail(&mut self, typed: &Typed) {
+        match &typed.node {
+            Node::If(test, consequent, alternative) => {
+                let test = self.value(test);
+                let (then, otherwise) = (self.builder.create_block(), self.builder.create_block());
+                self.builder.ins().brif(test, then, &[], otherwise, &[]);
+
+                self.builder.switch_to_block(then);
+                self.tail(consequent);
+                self.builder.switch_to_block(otherwise);
+                self.tail(alternative);
+            }
+            Node::Call(args) => {
+                // All arguments are computed before any parameter is rebound
+                let values: Vec<Value> = args.iter().map(|arg| self.value(arg)).collect();
+                for (variable, value) in self.variables[..self.params].iter().copied().zip(values) {
+                    self.builder.def_var(variable, value);
+                }
+                self.builder.ins().jump(self.header, &[]);
+            }
+            _ => {
+                let value = self.value(typed);
+                let bits = self.encode(value, typed.kind);
+                let status = self.builder.ins().iconst(types::I64, COMPLETED);
+                self.builder.ins().return_(&[status, bits]);
+            }
+        }
+    }
+
+    fn value(&mut self, typed: &Typed) -> Value {
+        match &typed.node {
+            Node::Fixnum(n) => self.builder.ins().iconst(types::I64, *n),
+            Node::Flonum(f) => self.builder.ins().f64const(*f),
+            Node::Boolean(b) => self.builder.ins().iconst(types::I8, *b as i64),
+            Node::Input(index) => self.builder.use_var(self.variables[*index]),
+            Node::ToFlonum(operand) => {
+                let operand = self.value(operand);
+                self.builder.ins().fcvt_from_sint(types::F64, operand)
+            }
+            Node::Arithmetic(operation, left, right) => {
+                let (left, right) = (self.value(left), self.value(right));
+                match (typed.kind, operation) {
+                    (Kind::Fixnum, operation) => {
+                        let (value, overflow) = match operation {
+                            Arithmetic::Add => self.builder.ins().sadd_overflow(left, right),
+                            Arithmetic::Sub => self.builder.ins().ssub_overflow(left, right),
+                            Arithmetic::Mul => self.builder.ins().smul_overflow(left, right),
+                        };
+                        self.deoptimize_if(overflow);
+                        value
+                    }
+                    (_, Arithmetic::Add) => self.builder.ins().fadd(left, right),
+                    (_, Arithmetic::Sub) => self.builder.ins().fsub(left, right),
+                    (_, Arithmetic::Mul) => self.builder.ins().fmul(left, right),
+                }
+            }
+            Node::Comparison(comparison, operands) => {
+                let values: Vec<(Value, Kind)> =
+                    operands.iter().map(|operand| (self.value(operand), operand.kind)).collect();
+
+                let mut result = self.builder.ins().iconst(types::I8, 1);
+                for pair in values.windows(2) {
+                    let ((left, left_kind), (right, right_kind)) = (pair[0], pair[1]);
+                    let holds = match (left_kind, right_kind) {
+                        (Kind::Fixnum, Kind::Fixnum) => {
+                            self.builder.ins().icmp(comparison.int(), left, right)
+                        }
+                        _ => {
+                            let left = self.flonum(left, left_kind);
+                            let right = self.flonum(right, right_kind);
+                            self.builder.ins().fcmp(comparison.float(), left, right)
+                        }
+                    };
+                    result = self.builder.ins().band(result, holds);
+                }
+                result
+            }
+            Node::Not(operand) => {
+                let operand = self.value(operand);
+                self.builder.ins().icmp_imm(IntCC::Equal, operand, 0)
+            }
+            Node::And(operands) | Node::Or(operands) => {
+                let and = matches!(typed.node, Node::And(_));
+                let mut result = self.builder.ins().iconst(types::I8, and as i64);
+                for operand in operands {
+                    let operand = self.value(operand);
+                    result = match and {
+                        true => self.builder.ins().band(result, operand),
+                        false => self.builder.ins().bor(result, operand),
+                    };
+                }
+                result
+            }
+            Node::If(test, consequent, alternative) => {
+                let test = self.value(test);
+                let (then, otherwise) = (self.builder.create_block(), self.builder.create_block());
+                let merge = self.builder.create_block();
+                let value = self.builder.append_block_param(merge, typed.kind.ir());
+                self.builder.ins().brif(test, then, &[], otherwise, &[]);
+
+                self.builder.switch_to_block(then);
+                let consequent = self.value(consequent);
+                self.builder.ins().jump(merge, &[consequent]);
+                self.builder.switch_to_block(otherwise);
+                let alternative = self.value(alternative);
+                self.builder.ins().jump(merge, &[alternative]);
+
+                self.builder.switch_to_block(merge);
+                value
+            }
+            Node::Call(args) => {
+                let depth = self.builder.ins().iadd_imm(self.depth, 1);
+                let mut values = vec![depth];
+                values.extend(args.iter().map(|arg| self.value(arg)));
+                for variable in self.variables[self.params..].iter().copied() {
+                    values.push(self.builder.use_var(variable));
+                }
+
+                let call = self.builder.ins().call(self.function, &values);
+                let (status, bits) =
+                    (self.builder.inst_results(call)[0], self.builder.inst_results(call)[1]);
+                self.deoptimize_if(status);
+                self.decode(bits, self.result)
+            }
+        }
+    }
+
+    fn flonum(&mut self, value: Value, kind: Kind) -> Value {
+        match kind {
+            Kind::Fixnum => self.builder.ins().fcvt_from_sint(types::F64, value),
+            _ => value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::{
+        environment::{EnvAccessor, Environment},
+        evaluator::eval,
+        reader::read_all,
+    };
+
+    use super::*;
+
+    /// Defines the procedures of `source` and compiles `name` for the arguments of `call`,
+    /// running it if it could be compiled.
+    fn native(source: &str, name: &str, call: &str) -> Option<SExpr> {
+        let env = EnvAccessor::new(Environment::default());
+        for expression in read_all(source).unwrap() {
+            eval(&expression, env.clone()).unwrap();
+        }
+
+        let Some(SExpr::Procedure(Procedure::Compound(lambda, closure))) =
+            env.access().unwrap().get(&Symbol::new(name))
+        else {
+            panic!("{} is not a compound procedure", name);
+        };
+        let args = read_all(call).unwrap().into_iter().collect();
+
+        compile(&lambda, &closure, &args)?.run(&lambda, &closure, &args)
+    }
+
+    #[test]
+    fn jit_compiled() {
+        let fib = "(define (fib n) (if (< n 2) n (+ (fib (- n 1)) (fib (- n 2)))))";
+        assert_eq!(native(fib, "fib", "20").unwrap().to_string(), "6765");
+
+        let loop_ = "(define (f x n) (if (= n 0) x (f (* x 1.5) (- n 1))))";
+        assert_eq!(native(loop_, "f", "2.0 2").unwrap().to_string(), "4.5");
+
+        let free = "(define k 3) (define (g x) (and (> x k) (not (= x 10))))";
+        assert_eq!(native(free, "g", "4").unwrap().to_string(), "#t");
+    }
+
+    #[test]
+    fn jit_not_compiled() {
+        assert!(native("(define (f x) (car x))", "f", "(1)").is_none());
+        assert!(native("(define (f x) (/ x 2))", "f", "1").is_none());
+        assert!(native("(define (f x) (display x) x)", "f", "1").is_none());
+        assert!(native("(define (f x) (if (= x 0) 1 #f))", "f", "1").is_none());
+    }
+
+    #[test]
+    fn jit_deoptimized() {
+        let fact = "(define (fact n) (if (= n 0) 1 (* n (fact (- n 1)))))";
+        assert_eq!(native(fact, "fact", "20").unwrap().to_string(), "2432902008176640000");
+        assert!(native(fact, "fact", "21").is_none());
+
+        let count = "(define (count n) (if (= n 0) 0 (+ 1 (count (- n 1)))))";
+        assert!(native(count, "count", "100000").is_none());
+    }
+}
@@ -1,8 +1,15 @@
 mod accessor;
 mod builtins;
+mod compiler;
 mod constants;
 mod environment;
+pub mod error;
 mod evaluator;
+mod heap;
 pub mod interpreter;
+#[cfg(feature = "jit")]
+mod jit;
+mod optimizer;
 mod reader;
 mod s_expression;
+mod vm;
@@ -0,0 +1,455 @@
+use std::collections::HashSet;
+use std::ptr::fn_addr_eq;
+use std::sync::Arc;
+
+use super::{
+    accessor::Accessor,
+    builtins::{Primitive, SpecialForm},
+    compiler::{definitions, MAX_NESTING_DEPTH},
+    environment::SchemeEnvironment,
+    evaluator::{do_variable, is_true, let_bindings},
+    reader::Span,
+    s_expression::*,
+};
+
+/// Primitives whose calls with numbers only are computed ahead of evaluation.
+const FOLDABLE: [ProcedureSignature; 9] = [
+    Primitive::SUM,
+    Primitive::DIFF,
+    Primitive::PROD,
+    Primitive::QUOT,
+    Primitive::EQUAL,
+    Primitive::GT,
+    Primitive::GE,
+    Primitive::LT,
+    Primitive::LE,
+];
+
+/// How many expressions deep the optimizer recurses. Each level takes several times the native
+/// stack the compiler does, so those nested deeper are left as written much sooner.
+const MAX_OPTIMIZED_DEPTH: usize = MAX_NESTING_DEPTH / 4;
+
+/// Rewrites `expression` into an equivalent one that is cheaper to evaluate in `env`. Calls to
+/// arithmetic primitives with literal numbers are replaced by their value, other primitives
+/// called by their global name are referred to directly, and the branches of `if` that a
+/// constant test makes unreachable are dropped.
+///
+/// A primitive is only referred to directly if its name is bound to it in `env` and is neither
+/// bound nor assigned within `expression`. Each expression of a body relying on primitives checks
+/// before being evaluated that no variable of `env` bound to a primitive was bound to something
+/// else since, and is otherwise evaluated as written.
+pub fn optimize(expression: &SExpr, env: &ProcedureEnv) -> SExpr {
+    let Some(rebound) = env.access().ok().and_then(|env| NativeInt::try_from(env.rebound()).ok())
+    else {
+        return expression.clone();
+    };
+    let guard = SExpr::list([SExpr::Keyword(SpecialForm::UNCHANGED), SExpr::from(rebound)]);
+
+    let mut assigned = HashSet::new();
+    assigned_names(expression, &mut assigned);
+
+    Optimizer { env, assigned, bound: vec![], guard, direct: false, depth: 0 }.statement(expression)
+}
+
+/// Collects in `names` the variables `expression` may define or assign.
+fn assigned_names(expression: &SExpr, names: &mut HashSet<Symbol>) {
+    // The expressions left to look at are kept on a stack, however deeply they are nested
+    let mut pending = vec![expression.clone()];
+
+    while let Some(expression) = pending.pop() {
+        let SExpr::Pair(_) = expression else {
+            continue;
+        };
+
+        let target = expression.cdr().and_then(|operands| operands.car());
+        if let (Some(SExpr::Symbol(head)), Some(target)) = (expression.car(), target) {
+            if head == "define" || head == "set!" {
+                match target {
+                    SExpr::Symbol(name) => _ = names.insert(name.clone()),
+                    signature @ SExpr::Pair(_) => {
+                        if let Some(SExpr::Symbol(name)) = signature.car() {
+                            names.insert(name);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        pending.extend(expression.pairs());
+    }
+}
+
+/// The variables bound by the parameters of a lambda, which may be a single symbol or an
+/// improper list.
+fn parameter_names(params: &SExpr) -> Vec<Symbol> {
+    let mut names: Vec<Symbol> = params
+        .pairs()
+        .filter_map(|param| match param {
+            SExpr::Symbol(name) => Some(name),
+            _ => None,
+        })
+        .collect();
+
+    let mut tail = params.clone();
+    while let Some(cdr) = tail.cdr() {
+        tail = cdr;
+    }
+    if let SExpr::Symbol(name) = tail {
+        names.push(name);
+    }
+
+    names
+}
+
+struct Optimizer<'a> {
+    /// Where names not bound by the expression being optimized are looked up.
+    env: &'a ProcedureEnv,
+    /// The variables defined or assigned anywhere in the expression.
+    assigned: HashSet<Symbol>,
+    /// The variables bound by the enclosing lambdas and binding forms, which shadow any global
+    /// of the same name.
+    bound: Vec<Symbol>,
+    /// The call checking that no primitive was bound to something else since.
+    guard: SExpr,
+    /// Whether the expression being optimized refers to primitives directly.
+    direct: bool,
+    /// How many expressions enclose the one being optimized.
+    depth: usize,
+}
+
+impl Optimizer<'_> {
+    fn is_bound(&self, name: &Symbol) -> bool {
+        self.bound.contains(name)
+    }
+
+    /// The special form `name` stands for, unless a variable of the same name shadows it.
+    fn keyword(&self, name: &Symbol) -> Option<SpecialFormSignature> {
+        if self.is_bound(name) {
+            return None;
+        }
+
+        match self.env.access().ok()?.get(name) {
+            Some(SExpr::Keyword(form)) => Some(form),
+            _ => None,
+        }
+    }
+
+    /// The primitive the global `name` is bound to, unless the expression may change it.
+    fn primitive(&self, name: &Symbol) -> Option<ProcedureSignature> {
+        if self.is_bound(name) || self.assigned.contains(name) {
+            return None;
+        }
+
+        match self.env.access().ok()?.get(name) {
+            Some(SExpr::Procedure(Procedure::Primitive(primitive))) => Some(primitive),
+            _ => None,
+        }
+    }
+
+    /// The value of `expression` if it evaluates to itself or is quoted.
+    fn constant(&self, expression: &SExpr) -> Option<SExpr> {
+        match expression {
+            SExpr::Symbol(_) | SExpr::Null => None,
+            SExpr::Pair(_) => {
+                let form = match expression.car()? {
+                    SExpr::Symbol(ref name) => self.keyword(name)?,
+                    SExpr::Keyword(form) => form,
+                    _ => return None,
+                };
+                let datum = expression.cdr()?.as_list().ok()?;
+                match (fn_addr_eq(form, SpecialForm::QUOTE), datum.s_len()) {
+                    (true, 1) => datum.s_car().cloned(),
+                    _ => None,
+                }
+            }
+            other => Some(other.clone()),
+        }
+    }
+
+    fn expression(&mut self, expression: &SExpr) -> SExpr {
+        if self.depth == MAX_OPTIMIZED_DEPTH {
+            return expression.clone();
+        }
+
+        self.depth += 1;
+        let optimized = self.nested_expression(expression);
+        self.depth -= 1;
+
+        optimized
+    }
+
+    fn nested_expression(&mut self, expression: &SExpr) -> SExpr {
+        let SExpr::Pair(_) = expression else {
+            return expression.clone();
+        };
+        let (Some(operator), Some(Ok(args))) =
+            (expression.car(), expression.cdr().map(|operands| operands.as_list()))
+        else {
+            return expression.clone();
+        };
+        let args: Vec<SExpr> = args.into_iter().collect();
+
+        let form = match operator {
+            SExpr::Symbol(ref name) => self.keyword(name),
+            SExpr::Keyword(form) => Some(form),
+            _ => None,
+        };
+
+        match form {
+            Some(form) => self.special_form(expression, form, operator, args),
+            None => self.application(operator, args, expression.span()),
+        }
+    }
+
+    fn expressions(&mut self, expressions: &[SExpr]) -> Vec<SExpr> {
+        expressions.iter().map(|expression| self.expression(expression)).collect()
+    }
+
+    /// Optimizes an expression of a body, which is evaluated as written instead if a primitive
+    /// it refers to directly was bound to something else by then.
+    fn statement(&mut self, expression: &SExpr) -> SExpr {
+        let outer = std::mem::replace(&mut self.direct, false);
+        let optimized = self.expression(expression);
+
+        match std::mem::replace(&mut self.direct, outer) {
+            true => SExpr::list([
+                SExpr::Keyword(SpecialForm::IF),
+                self.guard.clone(),
+                optimized,
+                expression.clone(),
+            ]),
+            false => optimized,
+        }
+    }
+
+    fn statements(&mut self, expressions: &[SExpr]) -> Vec<SExpr> {
+        expressions.iter().map(|expression| self.statement(expression)).collect()
+    }
+
+    /// Optimizes a call read from `span`, which the call keeps unless it is folded.
+    fn application(&mut self, operator: SExpr, args: Vec<SExpr>, span: Option<Arc<Span>>) -> SExpr {
+        let args = self.expressions(&args);
+        let primitive = match operator {
+            SExpr::Symbol(ref name) => self.primitive(name),
+            _ => None,
+        };
+
+        let Some(primitive) = primitive else {
+            let operator = self.expression(&operator);
+            return SExpr::list([operator].into_iter().chain(args)).with_span(span);
+        };
+
+        let foldable = FOLDABLE.into_iter().any(|other| fn_addr_eq(primitive, other));
+        if foldable && args.iter().all(|arg| matches!(arg, SExpr::Number(_))) {
+            // Calls failing, for instance dividing by zero, are left to fail when evaluated
+            let operands = ListImplementation::from_iter(args.iter().cloned());
+            if let Ok(value) = primitive(operands, self.env.clone()) {
+                self.direct = true;
+                return value;
+            }
+        }
+
+        self.direct = true;
+        let operator = SExpr::Procedure(Procedure::Primitive(primitive));
+        SExpr::list([operator].into_iter().chain(args)).with_span(span)
+    }
+
+    /// Optimizes the operands of a special form, leaving those of forms it does not know as
+    /// they are.
+    fn special_form(
+        &mut self, expression: &SExpr, form: SpecialFormSignature, operator: SExpr,
+        args: Vec<SExpr>,
+    ) -> SExpr {
+        let rebuilt = |args: Vec<SExpr>| SExpr::list([operator.clone()].into_iter().chain(args));
+
+        if fn_addr_eq(form, SpecialForm::IF) {
+            let args = self.expressions(&args);
+            match (args.len(), args.first().and_then(|test| self.constant(test))) {
+                (2 | 3, Some(test)) if is_true(&test) => args[1].clone(),
+                (3, Some(_)) => args[2].clone(),
+                _ => rebuilt(args),
+            }
+        } else if fn_addr_eq(form, SpecialForm::BEGIN) {
+            rebuilt(self.statements(&args))
+        } else if [SpecialForm::AND, SpecialForm::OR, SpecialForm::WHEN, SpecialForm::UNLESS]
+            .into_iter()
+            .any(|other| fn_addr_eq(form, other))
+        {
+            rebuilt(self.expressions(&args))
+        } else if fn_addr_eq(form, SpecialForm::DEFINE) || fn_addr_eq(form, SpecialForm::SET) {
+            match args.as_slice() {
+                [name @ SExpr::Symbol(_), value] => {
+                    rebuilt(vec![name.clone(), self.statement(value)])
+                }
+                [signature @ SExpr::Pair(_), body @ ..]
+                    if fn_addr_eq(form, SpecialForm::DEFINE) && !body.is_empty() =>
+                {
+                    let params = signature.cdr().unwrap_or(SExpr::Null);
+                    let body = self.body(parameter_names(&params), body);
+                    rebuilt([signature.clone()].into_iter().chain(body).collect())
+                }
+                _ => expression.clone(),
+            }
+        } else if fn_addr_eq(form, SpecialForm::LAMBDA) {
+            match args.split_first() {
+                Some((params, body)) if !body.is_empty() => {
+                    let body = self.body(parameter_names(params), body);
+                    rebuilt([params.clone()].into_iter().chain(body).collect())
+                }
+                _ => expression.clone(),
+            }
+        } else if [SpecialForm::LET, SpecialForm::LET_STAR, SpecialForm::LETREC]
+            .into_iter()
+            .chain([SpecialForm::LETREC_STAR])
+            .any(|other| fn_addr_eq(form, other))
+        {
+            self.let_form(args).map(rebuilt).unwrap_or_else(|| expression.clone())
+        } else if fn_addr_eq(form, SpecialForm::COND) {
+            let clauses = args.iter().map(|clause| match clause.as_list() {
+                Ok(clause) => SExpr::list(self.expressions(&Vec::from_iter(clause))),
+                Err(_) => self.expression(clause),
+            });
+            rebuilt(clauses.collect())
+        } else if fn_addr_eq(form, SpecialForm::CASE) {
+            let Some((key, clauses)) = args.split_first() else {
+                return expression.clone();
+            };
+            let key = self.expression(key);
+            let clauses = clauses.iter().map(|clause| match clause.as_list() {
+                Ok(clause) => {
+                    let clause = Vec::from_iter(clause);
+                    let (data, body) = clause.split_first().unwrap_or((&SExpr::Null, &[]));
+                    SExpr::list([data.clone()].into_iter().chain(self.expressions(body)))
+                }
+                Err(_) => clause.clone(),
+            });
+            rebuilt([key].into_iter().chain(clauses).collect())
+        } else if fn_addr_eq(form, SpecialForm::DO) {
+            self.do_form(args).map(rebuilt).unwrap_or_else(|| expression.clone())
+        } else {
+            expression.clone()
+        }
+    }
+
+    /// Optimizes a body within which `names` and the variables it defines are bound.
+    fn body(&mut self, names: Vec<Symbol>, body: &[SExpr]) -> Vec<SExpr> {
+        let outer = self.bound.len();
+        self.bound.extend(names);
+        self.bound.extend(definitions(body));
+
+        let body = self.statements(body);
+        self.bound.truncate(outer);
+        body
+    }
+
+    /// Optimizes the operands of `let`, `let*`, `letrec`, `letrec*` and named `let`, leaving
+    /// malformed ones for the forms to report. Bound names are taken to shadow globals in every
+    /// initializer, which is only conservative.
+    fn let_form(&mut self, args: Vec<SExpr>) -> Option<Vec<SExpr>> {
+        let (name, args) = match args.first() {
+            Some(SExpr::Symbol(name)) => (Some(name.clone()), &args[1..]),
+            _ => (None, &args[..]),
+        };
+        let (bindings, body) = args.split_first().filter(|(_, body)| !body.is_empty())?;
+        let (names, inits): (Vec<Symbol>, Vec<SExpr>) =
+            let_bindings("let", bindings).ok()?.into_iter().unzip();
+        let names: Vec<Symbol> = name.iter().cloned().chain(names).collect();
+
+        let outer = self.bound.len();
+        self.bound.extend(names.clone());
+        let inits = self.expressions(&inits);
+        self.bound.truncate(outer);
+
+        let bindings = names.iter().skip(usize::from(name.is_some())).zip(inits);
+        let bindings =
+            bindings.map(|(name, init)| SExpr::list([SExpr::Symbol(name.clone()), init]));
+        let bindings = SExpr::list(bindings.collect::<Vec<SExpr>>());
+
+        let body = self.body(names, body);
+        Some(name.map(SExpr::Symbol).into_iter().chain([bindings]).chain(body).collect())
+    }
+
+    /// Optimizes the operands of `do`, whose variables are bound in all of them but their
+    /// initializers.
+    fn do_form(&mut self, args: Vec<SExpr>) -> Option<Vec<SExpr>> {
+        let [variables, exit, commands @ ..] = args.as_slice() else {
+            return None;
+        };
+        let variables = variables.as_list().ok()?;
+        let variables = variables.iter().map(do_variable).collect::<Result<Vec<_>, _>>().ok()?;
+        let exit = Vec::from_iter(exit.as_list().ok()?);
+
+        let inits: Vec<SExpr> = variables.iter().map(|(_, init, _)| init.clone()).collect();
+        let inits = self.expressions(&inits);
+
+        let outer = self.bound.len();
+        self.bound.extend(variables.iter().map(|(name, _, _)| name.clone()));
+        let variables = variables.iter().zip(inits).map(|((name, _, step), init)| {
+            let step = step.as_ref().map(|step| self.expression(step));
+            SExpr::list([SExpr::Symbol(name.clone()), init].into_iter().chain(step))
+        });
+        let variables = SExpr::list(variables.collect::<Vec<SExpr>>());
+        let exit = SExpr::list(self.expressions(&exit));
+        let commands = self.expressions(commands);
+        self.bound.truncate(outer);
+
+        Some([variables, exit].into_iter().chain(commands).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::{
+        environment::{EnvAccessor, Environment},
+        reader::read_all,
+    };
+
+    use super::*;
+
+    fn optimized(source: &str) -> String {
+        let expression = read_all(source).unwrap().remove(0);
+        optimize(&expression, &EnvAccessor::new(Environment::default())).to_string()
+    }
+
+    /// How an expression of a body relying on primitives is printed once optimized.
+    fn guarded(optimized: &str, original: &str) -> String {
+        format!("(#<special form> (#<special form> 0) {} {})", optimized, original)
+    }
+
+    #[test]
+    fn optimizer_constant_folding() {
+        assert_eq!(
+            optimized("(lambda (n) (* n (+ 1 2)))"),
+            format!("(lambda (n) {})", guarded("(#<primitive> n 3)", "(* n (+ 1 2))"))
+        );
+        assert_eq!(
+            optimized("(list (< 1 2) (/ 1 0))"),
+            guarded("(#<primitive> #t (#<primitive> 1 0))", "(list (< 1 2) (/ 1 0))")
+        );
+        assert_eq!(
+            optimized("(define x (+ 1 2))"),
+            format!("(define x {})", guarded("3", "(+ 1 2)"))
+        );
+    }
+
+    #[test]
+    fn optimizer_shadowed_primitive() {
+        assert_eq!(optimized("(let ((+ -)) (+ 1 2))"), "(let ((+ -)) (+ 1 2))");
+        assert_eq!(
+            optimized("(begin (set! car cdr) (car 1 2))"),
+            "(begin (set! car cdr) (car 1 2))"
+        );
+    }
+
+    #[test]
+    fn optimizer_dead_branches() {
+        assert_eq!(
+            optimized("(if (= 1 1) 'yes 'no)"),
+            guarded("(quote yes)", "(if (= 1 1) (quote yes) (quote no))")
+        );
+        assert_eq!(optimized("(if '() 1 2)"), "1");
+        assert_eq!(optimized("(if #f 1)"), "(if #f 1)");
+        assert_eq!(optimized("(lambda (if) (if #f 1 2))"), "(lambda (if) (if #f 1 2))");
+    }
+}
@@ -0,0 +1,292 @@
+use std::{path::PathBuf, sync::Arc};
+
+use super::{
+    super::constants::{chars, tokens},
+    Position, ReadError, Span,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TokenKind {
+    OpenParen,
+    CloseParen,
+    OpenBracket,
+    CloseBracket,
+    VectorOpen,
+    Quote,
+    Quasiquote,
+    Unquote,
+    UnquoteSplicing,
+    DatumComment,
+    String(String),
+    /// A `|pipe quoted|` symbol, which is never case folded nor parsed as a number.
+    Identifier(String),
+    Char(char),
+    Atom(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+pub struct Lexer<'a> {
+    source: &'a str,
+    offset: usize,
+    position: Position,
+    file: Option<Arc<PathBuf>>,
+    fold_case: bool,
+}
+
+fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '(' | ')' | '[' | ']' | '"' | ';' | '|')
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(source: &'a str, file: Option<Arc<PathBuf>>) -> Self {
+        Self {
+            source,
+            offset: 0,
+            position: Position { line: 1, column: 1 },
+            file,
+            fold_case: false,
+        }
+    }
+
+    /// Makes the lexer fold the case of identifiers and characters from the start, as if the
+    /// source began with `#!fold-case`.
+    pub fn with_fold_case(self) -> Self {
+        Self { fold_case: true, ..self }
+    }
+
+    pub fn span_from(&self, start: Position) -> Span {
+        Span { file: self.file.clone(), start, end: self.position }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.source[self.offset..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.offset += c.len_utf8();
+
+        if c == '\n' {
+            self.position.line += 1;
+            self.position.column = 1;
+        } else {
+            self.position.column += 1;
+        }
+
+        Some(c)
+    }
+
+    fn bump_n(&mut self, n: usize) {
+        for _ in 0..n {
+            self.bump();
+        }
+    }
+
+    fn incomplete(&self, start: Position) -> ReadError {
+        ReadError::Incomplete(self.span_from(start))
+    }
+
+    fn syntax(&self, message: String, start: Position) -> ReadError {
+        ReadError::Syntax(message, self.span_from(start))
+    }
+
+    /// Consumes characters up to the next delimiter.
+    fn take_until_delimiter(&mut self) -> String {
+        let mut output = String::new();
+        while let Some(c) = self.peek().filter(|c| !is_delimiter(*c)) {
+            output.push(c);
+            self.bump();
+        }
+
+        output
+    }
+
+    fn skip_block_comment(&mut self) -> Result<(), ReadError> {
+        let start = self.position;
+        let mut depth = 0;
+
+        loop {
+            if self.rest().starts_with(tokens::PREFIX_BLOCK_COMMENT) {
+                depth += 1;
+                self.bump_n(2);
+            } else if self.rest().starts_with(tokens::SUFFIX_BLOCK_COMMENT) {
+                depth -= 1;
+                self.bump_n(2);
+                if depth == 0 {
+                    return Ok(());
+                }
+            } else if self.bump().is_none() {
+                return Err(self.incomplete(start));
+            }
+        }
+    }
+
+    /// Skips whitespace, comments and directives preceding the next token.
+    fn skip_atmosphere(&mut self) -> Result<(), ReadError> {
+        loop {
+            let rest = self.rest();
+
+            if self.peek().is_some_and(char::is_whitespace) {
+                self.bump();
+            } else if rest.starts_with(tokens::PREFIX_COMMENT)
+                || (self.offset == 0 && rest.starts_with("#!/"))
+            {
+                while self.peek().is_some_and(|c| c != '\n') {
+                    self.bump();
+                }
+            } else if rest.starts_with(tokens::PREFIX_BLOCK_COMMENT) {
+                self.skip_block_comment()?;
+            } else if rest.starts_with(tokens::PREFIX_DIRECTIVE) {
+                let start = self.position;
+                self.bump_n(2);
+
+                match self.take_until_delimiter().to_lowercase().as_str() {
+                    tokens::FOLD_CASE => self.fold_case = true,
+                    tokens::NO_FOLD_CASE => self.fold_case = false,
+                    other => {
+                        return Err(self.syntax(format!("unknown directive #!{}", other), start))
+                    }
+                }
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Reads the body of a string or pipe-quoted symbol, replacing its escape sequences.
+    fn quoted(&mut self, quote: char, start: Position) -> Result<String, ReadError> {
+        let mut output = String::new();
+        self.bump();
+
+        loop {
+            let c = self.bump().ok_or_else(|| self.incomplete(start))?;
+
+            if c == quote {
+                return Ok(output);
+            } else if c != '\\' {
+                output.push(c);
+                continue;
+            }
+
+            let escape_start = self.position;
+            match self.bump().ok_or_else(|| self.incomplete(start))? {
+                'a' => output.push('\u{7}'),
+                'b' => output.push('\u{8}'),
+                't' => output.push('\t'),
+                'n' => output.push('\n'),
+                'r' => output.push('\r'),
+                'x' | 'X' => {
+                    let mut hex = String::new();
+                    loop {
+                        match self.bump() {
+                            Some(';') => break,
+                            Some(digit) if digit.is_ascii_hexdigit() => hex.push(digit),
+                            Some(_) => {
+                                return Err(
+                                    self.syntax(format!("invalid escape \\x{}", hex), escape_start)
+                                )
+                            }
+                            None => return Err(self.incomplete(start)),
+                        }
+                    }
+
+                    match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                        Some(c) => output.push(c),
+                        None => {
+                            return Err(
+                                self.syntax(format!("invalid escape \\x{};", hex), escape_start)
+                            )
+                        }
+                    }
+                }
+                // A backslash at the end of a line, surrounded by intraline whitespace, joins the lines
+                c if c.is_whitespace() => {
+                    let mut has_newline = c == '\n';
+                    while let Some(next) = self.peek().filter(|c| c.is_whitespace()) {
+                        if has_newline && next == '\n' {
+                            break;
+                        }
+                        has_newline |= next == '\n';
+                        self.bump();
+                    }
+
+                    if !has_newline {
+                        return Err(self.syntax("invalid escape \\ ".to_string(), escape_start));
+                    }
+                }
+                c => output.push(c),
+            }
+        }
+    }
+
+    fn char(&mut self, start: Position) -> Result<char, ReadError> {
+        self.bump_n(tokens::PREFIX_CHAR.len());
+
+        let first = self.bump().ok_or_else(|| self.incomplete(start))?;
+        let rest = self.take_until_delimiter();
+        if rest.is_empty() {
+            return Ok(first);
+        }
+
+        let name = format!("{}{}", first, rest);
+        let name = if self.fold_case { name.to_lowercase() } else { name };
+        if let Some((_, c)) = chars::NAMES.iter().find(|(char_name, _)| *char_name == name) {
+            return Ok(*c);
+        }
+
+        match name.strip_prefix('x').and_then(|hex| u32::from_str_radix(hex, 16).ok()) {
+            Some(code) => match char::from_u32(code) {
+                Some(c) => Ok(c),
+                None => Err(self.syntax(format!("invalid character #\\{}", name), start)),
+            },
+            None => Err(self.syntax(format!("unknown character name #\\{}", name), start)),
+        }
+    }
+
+    pub fn next_token(&mut self) -> Result<Option<Token>, ReadError> {
+        self.skip_atmosphere()?;
+
+        let start = self.position;
+        let rest = self.rest();
+        let (kind, length) = match self.peek() {
+            None => return Ok(None),
+            Some('(') => (TokenKind::OpenParen, 1),
+            Some(')') => (TokenKind::CloseParen, 1),
+            Some('[') => (TokenKind::OpenBracket, 1),
+            Some(']') => (TokenKind::CloseBracket, 1),
+            Some('\'') => (TokenKind::Quote, 1),
+            Some('`') => (TokenKind::Quasiquote, 1),
+            Some(',') if rest.starts_with(tokens::UNQUOTE_SPLICING) => {
+                (TokenKind::UnquoteSplicing, 2)
+            }
+            Some(',') => (TokenKind::Unquote, 1),
+            Some(_) if rest.starts_with(tokens::VECTOR_OPEN) => (TokenKind::VectorOpen, 2),
+            Some(_) if rest.starts_with(tokens::PREFIX_DATUM_COMMENT) => {
+                (TokenKind::DatumComment, 2)
+            }
+            Some('"') => (TokenKind::String(self.quoted('"', start)?), 0),
+            Some('|') => (TokenKind::Identifier(self.quoted('|', start)?), 0),
+            Some(_) if rest.starts_with(tokens::PREFIX_CHAR) => {
+                (TokenKind::Char(self.char(start)?), 0)
+            }
+            Some(_) => {
+                let atom = self.take_until_delimiter();
+                let atom = if self.fold_case { atom.to_lowercase() } else { atom };
+                (TokenKind::Atom(atom), 0)
+            }
+        };
+
+        self.bump_n(length);
+
+        Ok(Some(Token { kind, span: self.span_from(start) }))
+    }
+}
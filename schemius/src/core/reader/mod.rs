@@ -0,0 +1,202 @@
+mod lexer;
+mod parser;
+
+use std::{fmt, path::PathBuf, sync::Arc};
+
+use super::s_expression::SExpr;
+
+pub use parser::Reader;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// The region of a source a datum or error comes from; lines and columns start at 1.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Span {
+    pub file: Option<Arc<PathBuf>>,
+    pub start: Position,
+    pub end: Position,
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.file {
+            Some(ref file) => {
+                write!(f, "{}:{}:{}", file.display(), self.start.line, self.start.column)
+            }
+            None => write!(f, "line {}, column {}", self.start.line, self.start.column),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Datum {
+    pub expr: SExpr,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReadError {
+    /// The source ended in the middle of a datum, more input could complete it.
+    Incomplete(Span),
+    Syntax(String, Span),
+}
+
+impl ReadError {
+    pub fn is_incomplete(&self) -> bool {
+        matches!(self, ReadError::Incomplete(_))
+    }
+
+    pub fn span(&self) -> &Span {
+        match self {
+            ReadError::Incomplete(span) | ReadError::Syntax(_, span) => span,
+        }
+    }
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadError::Incomplete(span) => {
+                write!(f, "Exception: Invalid syntax: unexpected end of input at {}.", span)
+            }
+            ReadError::Syntax(message, span) => {
+                write!(f, "Exception: Invalid syntax: {} at {}.", message, span)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub fn read_all(source: &str) -> Result<Vec<SExpr>, ReadError> {
+    Reader::new(source).map(|datum| datum.map(|datum| datum.expr)).collect()
+}
+
+/// Tells whether `source` is a prefix of valid input that still needs more characters,
+/// such as an unclosed list, string or block comment.
+pub fn is_incomplete(source: &str) -> bool {
+    Reader::new(source).any(|datum| datum.is_err_and(|e| e.is_incomplete()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_strings(source: &str) -> Vec<String> {
+        read_all(source).unwrap().iter().map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn test_read() {
+        let res = read_all("(+ 1 2) 3");
+        assert_eq!(res.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_read_all() {
+        let source = "(define x 1) ; comment (\n(+ x\n 2)\n'x";
+        let res = read_all(source);
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_read_all_unterminated() {
+        let res = read_all("(+ 1 2");
+        assert!(res.is_err_and(|e| e.is_incomplete()));
+    }
+
+    #[test]
+    fn test_read_unbalanced_parentheses() {
+        let res = read_all("(+ 1 2))");
+        assert!(matches!(res, Err(ReadError::Syntax(..))));
+        assert!(matches!(read_all("(+ 1 2]"), Err(ReadError::Syntax(..))));
+    }
+
+    #[test]
+    fn test_read_comments() {
+        let source = "#| outer #| nested |# still outer |# (+ 1 #;(ignored) 2) #;3";
+        assert_eq!(read_strings(source), vec!["(+ 1 2)"]);
+        assert!(is_incomplete("#| open #| nested |#"));
+        assert!(is_incomplete("(1 #;"));
+    }
+
+    #[test]
+    fn test_read_pipe_symbols() {
+        assert_eq!(read_strings(r"|hello world| |a\|b| |\x41;|"), vec!["hello world", "a|b", "A"]);
+    }
+
+    #[test]
+    fn test_read_fold_case() {
+        let source = "ABC #!fold-case ABC |ABC| #\\NEWLINE #!no-fold-case ABC";
+        assert_eq!(read_strings(source), vec!["ABC", "abc", "ABC", "#\\\n", "ABC"]);
+    }
+
+    #[test]
+    fn test_read_delimiters() {
+        let res = read_all("(a\"b\"c) #\\( #\\) #\\space #true #FALSE").unwrap();
+        assert_eq!(res.len(), 6);
+        assert_eq!(res[0].to_string(), "(a \"b\" c)");
+        assert!(matches!(res[1], SExpr::Char('(')));
+        assert!(matches!(res[2], SExpr::Char(')')));
+        assert!(matches!(res[3], SExpr::Char(' ')));
+        assert!(matches!(res[4], SExpr::Boolean(true)));
+        assert!(matches!(res[5], SExpr::Boolean(false)));
+    }
+
+    #[test]
+    fn test_read_string_escapes() {
+        let res = read_strings("\"a\\tb\\\"c\\\\d\\x3bb;\" \"one \\\n    two\"");
+        assert_eq!(res, vec!["\"a\tb\"c\\d\u{3bb}\"", "\"one two\""]);
+        assert!(is_incomplete("\"unterminated"));
+    }
+
+    #[test]
+    fn test_read_dotted() {
+        assert_eq!(read_strings("(1 . 2) (1 . (2 3)) (1 . ())"), vec!["(1 . 2)", "(1 2 3)", "(1)"]);
+        assert!(matches!(read_all("(1 2 . 3)").unwrap()[0], SExpr::Pair(_)));
+        assert!(read_all("(. 1)").is_err());
+        assert!(read_all("(1 . 2 3)").is_err());
+    }
+
+    #[test]
+    fn test_read_spans() {
+        let mut reader = Reader::with_file("(a\n  b)\n  'c ; done\n", "test.scm");
+
+        let first = reader.read_datum().unwrap().unwrap();
+        assert_eq!(first.span.start, Position { line: 1, column: 1 });
+        assert_eq!(first.span.end, Position { line: 2, column: 5 });
+
+        let second = reader.read_datum().unwrap().unwrap();
+        assert_eq!(second.span.to_string(), "test.scm:3:3");
+        assert!(reader.read_datum().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_read_error_positions() {
+        let error = read_all("(1 2)\n(3 #\\bogus)").unwrap_err();
+        assert!(!error.is_incomplete());
+        assert_eq!(error.span().start, Position { line: 2, column: 4 });
+
+        let error = read_all("(1 2)\n(3 4").unwrap_err();
+        assert!(error.is_incomplete());
+        assert_eq!(error.span().start, Position { line: 2, column: 1 });
+    }
+
+    #[test]
+    fn test_read_nesting_depth() {
+        let nested = |depth| format!("{}{}", "(".repeat(depth), ")".repeat(depth));
+        let data = read_all(&nested(100_000)).unwrap();
+        assert_eq!(data.len(), 1);
+        drop(data);
+
+        let error = read_all(&nested(100_000)[1..]).unwrap_err();
+        assert!(!error.is_incomplete());
+        assert!(read_all(&"'".repeat(100_000)).unwrap_err().is_incomplete());
+        let comments = format!("{}{}", "#;".repeat(100_000), "1 ".repeat(100_000));
+        assert!(read_all(&comments).unwrap().is_empty());
+    }
+}
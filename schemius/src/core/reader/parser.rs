@@ -0,0 +1,413 @@
+use std::{path::PathBuf, sync::Arc};
+
+use num::Num;
+use regex::Regex;
+use std::sync::LazyLock;
+
+use super::{
+    super::{accessor::Accessor, constants::tokens, s_expression::*},
+    lexer::{Lexer, Token, TokenKind},
+    Datum, Position, ReadError, Span,
+};
+
+static COMPLEX_POLAR_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\d*(\.\d+)?(/?\d*(\.\d+)?)?)@(\d*(\.\d+)?(/?\d*(\.\d+)?)?)$").unwrap()
+});
+
+/// Reads data one at a time from a source string, keeping track of their positions.
+pub struct Reader<'a> {
+    lexer: Lexer<'a>,
+    failed: bool,
+    /// How many significant digits inexact literals carry as big floats, if any.
+    float_precision: Option<usize>,
+}
+
+/// A datum whose parts are still being read. Data are read with a stack of these rather than
+/// by recursion, so that they can be nested however deeply.
+enum Pending {
+    List(PendingList),
+    /// The abbreviation of a quote, waiting for the datum quoted.
+    Quote(&'static str, Span),
+    /// A datum comment, waiting for the datum it discards.
+    Comment(Span),
+}
+
+impl Pending {
+    /// Where the datum starts, from which the source is incomplete if it ends before it does.
+    fn start(&self) -> Position {
+        match self {
+            Pending::List(list) => list.open.start,
+            Pending::Quote(_, span) | Pending::Comment(span) => span.start,
+        }
+    }
+}
+
+struct PendingList {
+    open: Span,
+    closing: TokenKind,
+    is_vector: bool,
+    items: VectorImplementation,
+    /// Whether a dot was read, the datum after it being the tail.
+    dotted: bool,
+    tail: Option<SExpr>,
+}
+
+impl PendingList {
+    fn push(&mut self, datum: SExpr) {
+        match self.dotted {
+            true => self.tail = Some(datum),
+            false => self.items.push(datum),
+        }
+    }
+
+    fn finish(self, span: Span) -> SExpr {
+        let span = Some(Arc::new(span));
+        match (self.is_vector, self.tail) {
+            (true, _) => SExpr::Vector(SchemeVector::new(self.items)),
+            (false, None) => SExpr::list(self.items).with_span(span),
+            (false, Some(tail)) => SExpr::improper_list(self.items, tail).with_span(span),
+        }
+    }
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(source: &'a str) -> Self {
+        Self { lexer: Lexer::new(source, None), failed: false, float_precision: None }
+    }
+
+    /// Creates a reader whose spans refer to `path`.
+    pub fn with_file(source: &'a str, path: impl Into<PathBuf>) -> Self {
+        Self { lexer: Lexer::new(source, Some(Arc::new(path.into()))), ..Self::new(source) }
+    }
+
+    /// Makes the reader read inexact literals as big floats of `digits` significant digits, or
+    /// as native floats if `None`.
+    pub fn with_float_precision(self, digits: Option<usize>) -> Self {
+        Self { float_precision: digits, ..self }
+    }
+
+    /// Makes the reader fold the case of identifiers and characters, as `include-ci` does.
+    pub fn with_fold_case(self) -> Self {
+        Self { lexer: self.lexer.with_fold_case(), ..self }
+    }
+
+    /// Reads the next datum, returning `None` once the source is exhausted.
+    pub fn read_datum(&mut self) -> Result<Option<Datum>, ReadError> {
+        let mut pending: Vec<Pending> = vec![];
+        // Where the datum starts, past the datum comments before it
+        let mut start = None;
+
+        loop {
+            let token = match (self.lexer.next_token()?, pending.last()) {
+                (Some(token), _) => token,
+                (None, None) => return Ok(None),
+                (None, Some(innermost)) => {
+                    return Err(ReadError::Incomplete(self.lexer.span_from(innermost.start())))
+                }
+            };
+
+            if pending.iter().all(|pending| matches!(pending, Pending::Comment(_))) {
+                start = Some(token.span.start);
+            }
+
+            let Some(mut datum) = self.take(token, &mut pending)? else {
+                continue;
+            };
+
+            // The datum completes the ones pending until one is waiting for another datum
+            loop {
+                match pending.pop() {
+                    Some(Pending::List(mut list)) => {
+                        list.push(datum);
+                        pending.push(Pending::List(list));
+                        break;
+                    }
+                    Some(Pending::Quote(quote, _)) => {
+                        datum = SExpr::list([SExpr::symbol(quote), datum]);
+                    }
+                    Some(Pending::Comment(_)) => break,
+                    None => {
+                        let span = self.lexer.span_from(start.unwrap());
+                        return Ok(Some(Datum { expr: datum, span }));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reads `token` within the innermost of the `pending` data, returning the datum it completes
+    /// if any. Tokens starting a datum of several tokens push it on `pending` instead.
+    fn take(
+        &mut self, token: Token, pending: &mut Vec<Pending>,
+    ) -> Result<Option<SExpr>, ReadError> {
+        if let Some(Pending::List(list)) = pending.last_mut() {
+            match token.kind {
+                ref kind if *kind == list.closing && list.dotted && list.tail.is_none() => {
+                    return Err(ReadError::Syntax("expected a datum after dot".into(), token.span))
+                }
+                ref kind if *kind == list.closing => {
+                    let Some(Pending::List(list)) = pending.pop() else { unreachable!() };
+                    let span = self.lexer.span_from(list.open.start);
+                    return Ok(Some(list.finish(span)));
+                }
+                TokenKind::CloseParen | TokenKind::CloseBracket => {
+                    return Err(ReadError::Syntax("mismatched parentheses".into(), token.span))
+                }
+                _ if list.tail.is_some() => {
+                    return Err(ReadError::Syntax(
+                        "expected a single datum after dot".into(),
+                        token.span,
+                    ))
+                }
+                TokenKind::Atom(ref atom)
+                    if atom == tokens::DOT
+                        && !list.is_vector
+                        && !list.items.is_empty()
+                        && !list.dotted =>
+                {
+                    list.dotted = true;
+                    return Ok(None);
+                }
+                _ => {}
+            }
+        }
+
+        let quote = match token.kind {
+            TokenKind::OpenParen | TokenKind::OpenBracket | TokenKind::VectorOpen => {
+                let closing = match token.kind {
+                    TokenKind::OpenBracket => TokenKind::CloseBracket,
+                    _ => TokenKind::CloseParen,
+                };
+                pending.push(Pending::List(PendingList {
+                    is_vector: token.kind == TokenKind::VectorOpen,
+                    open: token.span,
+                    closing,
+                    items: VectorImplementation::new(),
+                    dotted: false,
+                    tail: None,
+                }));
+                return Ok(None);
+            }
+            TokenKind::CloseParen | TokenKind::CloseBracket => {
+                return Err(ReadError::Syntax("unexpected closing parenthesis".into(), token.span))
+            }
+            TokenKind::String(string) => {
+                return Ok(Some(SExpr::String(SchemeString::new(string.into()))))
+            }
+            TokenKind::Identifier(symbol) => return Ok(Some(SExpr::symbol(&symbol))),
+            TokenKind::Char(c) => return Ok(Some(SExpr::Char(c))),
+            TokenKind::Atom(atom) if atom == tokens::DOT => {
+                return Err(ReadError::Syntax("unexpected dot".into(), token.span))
+            }
+            TokenKind::Atom(atom) => {
+                return parse_atom(&atom, self.float_precision)
+                    .map(Some)
+                    .map_err(|e| ReadError::Syntax(e, token.span))
+            }
+            TokenKind::DatumComment => {
+                pending.push(Pending::Comment(token.span));
+                return Ok(None);
+            }
+            TokenKind::Quote => tokens::QUOTE_EXPLICIT,
+            TokenKind::Quasiquote => tokens::QUASIQUOTE_EXPLICIT,
+            TokenKind::Unquote => tokens::UNQUOTE_EXPLICIT,
+            TokenKind::UnquoteSplicing => tokens::UNQUOTE_SPLICING_EXPLICIT,
+        };
+
+        pending.push(Pending::Quote(quote, token.span));
+        Ok(None)
+    }
+}
+
+impl Iterator for Reader<'_> {
+    type Item = Result<Datum, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        let result = self.read_datum();
+        self.failed = result.is_err();
+
+        result.transpose()
+    }
+}
+
+fn parse_atom(token: &str, precision: Option<usize>) -> Result<SExpr, String> {
+    let folded =
+        if token.starts_with(tokens::PREFIX) { token.to_lowercase() } else { token.into() };
+    let token = folded.as_str();
+
+    match token {
+        tokens::TRUE | tokens::TRUE_EXPLICIT => Ok(SExpr::Boolean(true)),
+        tokens::FALSE | tokens::FALSE_EXPLICIT => Ok(SExpr::Boolean(false)),
+        tokens::NEGATIVE_NAN | tokens::POSITIVE_NAN => Ok(SExpr::from(NativeFloat::NAN)),
+        tokens::NEGATIVE_INFINITY => Ok(SExpr::from(NativeFloat::NEG_INFINITY)),
+        tokens::POSITIVE_INFINITY => Ok(SExpr::from(NativeFloat::INFINITY)),
+        _ => parse_number(token, precision),
+    }
+}
+
+fn parse_number(token: &str, precision: Option<usize>) -> Result<SExpr, String> {
+    let n_prefixes = if token.len() > 2
+        && token.chars().next() == tokens::PREFIX.chars().next()
+        && token.chars().nth(1).is_some_and(|c| c.is_ascii_alphabetic())
+    {
+        if token.len() > 4
+            && token.chars().nth(2) == Some('#')
+            && token.chars().nth(3).is_some_and(|c| c.is_ascii_alphabetic())
+        {
+            2
+        } else {
+            1
+        }
+    } else {
+        0
+    };
+
+    let (has_prefix, radix, is_exact) = if n_prefixes == 2 {
+        match (&token[0..2], &token[2..4]) {
+            (tokens::PREFIX_BINARY, tokens::PREFIX_EXACT)
+            | (tokens::PREFIX_EXACT, tokens::PREFIX_BINARY) => (true, 2, Some(true)),
+            (tokens::PREFIX_BINARY, tokens::PREFIX_INEXACT)
+            | (tokens::PREFIX_INEXACT, tokens::PREFIX_BINARY) => (true, 2, Some(false)),
+            (tokens::PREFIX_OCTAL, tokens::PREFIX_EXACT)
+            | (tokens::PREFIX_EXACT, tokens::PREFIX_OCTAL) => (true, 8, Some(true)),
+            (tokens::PREFIX_OCTAL, tokens::PREFIX_INEXACT)
+            | (tokens::PREFIX_INEXACT, tokens::PREFIX_OCTAL) => (true, 8, Some(false)),
+            (tokens::PREFIX_DECIMAL, tokens::PREFIX_EXACT)
+            | (tokens::PREFIX_EXACT, tokens::PREFIX_DECIMAL) => (true, 10, Some(true)),
+            (tokens::PREFIX_DECIMAL, tokens::PREFIX_INEXACT)
+            | (tokens::PREFIX_INEXACT, tokens::PREFIX_DECIMAL) => (true, 10, Some(false)),
+            (tokens::PREFIX_HEX, tokens::PREFIX_EXACT)
+            | (tokens::PREFIX_EXACT, tokens::PREFIX_HEX) => (true, 16, Some(true)),
+            (tokens::PREFIX_HEX, tokens::PREFIX_INEXACT)
+            | (tokens::PREFIX_INEXACT, tokens::PREFIX_HEX) => (true, 16, Some(false)),
+            _ => (false, 10, None),
+        }
+    } else if n_prefixes == 1 {
+        match &token[0..2] {
+            tokens::PREFIX_BINARY => (true, 2, None),
+            tokens::PREFIX_OCTAL => (true, 8, None),
+            tokens::PREFIX_DECIMAL => (true, 10, None),
+            tokens::PREFIX_HEX => (true, 16, None),
+            tokens::PREFIX_EXACT => (true, 10, Some(true)),
+            tokens::PREFIX_INEXACT => (true, 10, Some(false)),
+            _ => (false, 10, None),
+        }
+    } else {
+        (false, 10, None)
+    };
+
+    if has_prefix {
+        let number = if n_prefixes == 1 { &token[2..] } else { &token[4..] };
+        match NativeInt::from_str_radix(number, radix) {
+            Ok(n) => match is_exact {
+                Some(true) | None => Ok(SExpr::from(n)),
+                Some(false) => Ok(SExpr::Number(SNumber::Int(n).inexact(precision))),
+            },
+            _ => match NativeBigInt::from_str_radix(number, radix) {
+                Ok(n) => match is_exact {
+                    Some(true) | None => Ok(SExpr::from(n)),
+                    Some(false) => Ok(SExpr::Number(SNumber::from(n).inexact(precision))),
+                },
+                _ => match NativeRational::from_str_radix(number, radix) {
+                    Ok(q) => match is_exact {
+                        Some(true) | None => Ok(SExpr::from(q)),
+                        Some(false) => Ok(SExpr::Number(SNumber::from(q).inexact(precision))),
+                    },
+                    _ => match NativeFloat::from_str_radix(number, radix) {
+                        Ok(f) => match is_exact {
+                            Some(true) => match NativeRational::from_float(f) {
+                                Some(q) => Ok(SExpr::from(q)),
+                                None => Err(format!("{} has no exact representation", token)),
+                            },
+                            Some(false) | None => Ok(read_inexact(number, radix, f, precision)),
+                        },
+                        _ => match NativeExactComplex::from_str_radix(number, radix) {
+                            Ok(c) => match is_exact {
+                                Some(true) | None => Ok(SExpr::from(c)),
+                                Some(false) => {
+                                    Ok(SExpr::Number(SNumber::from(c).inexact(precision)))
+                                }
+                            },
+                            _ => match NativeComplex::from_str_radix(number, radix) {
+                                Ok(c) => match is_exact {
+                                    Some(true) => {
+                                        SNumber::Complex(c).exact().map(SExpr::Number).ok_or_else(
+                                            || format!("{} has no exact representation", token),
+                                        )
+                                    }
+                                    Some(false) | None => Ok(SExpr::from(c)),
+                                },
+                                _ => Err(format!("invalid number {}", token)),
+                            },
+                        },
+                    },
+                },
+            },
+        }
+    } else {
+        match token.parse::<NativeInt>() {
+            Ok(n) => Ok(SExpr::from(n)),
+            _ => match token.parse::<NativeBigInt>() {
+                Ok(n) => Ok(SExpr::from(n)),
+                _ => match token.parse::<NativeRational>() {
+                    Ok(q) => Ok(SExpr::from(q)),
+                    _ => match token.parse::<NativeFloat>() {
+                        Ok(f) => Ok(read_inexact(token, 10, f, precision)),
+                        _ => match token.parse::<NativeExactComplex>() {
+                            Ok(c) => Ok(SExpr::from(c)),
+                            _ => match parse_complex(token) {
+                                Some(c) => Ok(SExpr::from(c)),
+                                _ => match COMPLEX_POLAR_REGEX.captures(token) {
+                                    Some(_) => parse_polar_complex(token)
+                                        .ok_or_else(|| format!("invalid number {}", token)),
+                                    None => Ok(SExpr::symbol(token)),
+                                },
+                            },
+                        },
+                    },
+                },
+            },
+        }
+    }
+}
+
+/// Reads an inexact literal as a big float when a float precision is given.
+#[cfg_attr(not(feature = "bigfloat"), allow(unused_variables))]
+fn read_inexact(literal: &str, radix: u32, value: NativeFloat, precision: Option<usize>) -> SExpr {
+    #[cfg(feature = "bigfloat")]
+    if let Some(x) = precision.and_then(|precision| big_float::parse(literal, radix, precision)) {
+        return SExpr::Number(SNumber::BigFloat(x));
+    }
+
+    SExpr::from(value)
+}
+
+/// Reads a complex number in rectangular notation, whose parts may be infinities or NaN.
+fn parse_complex(token: &str) -> Option<NativeComplex> {
+    match token.parse::<NativeComplex>() {
+        Ok(c) => Some(c),
+        _ if token.ends_with('i') && (token.contains("inf.0") || token.contains("nan.0")) => {
+            token.replace("inf.0", "inf").replace("nan.0", "nan").parse().ok()
+        }
+        _ => None,
+    }
+}
+
+fn parse_polar_complex(token: &str) -> Option<SExpr> {
+    let parts = token
+        .split('@')
+        .map(|x| match x.parse::<NativeRational>() {
+            Ok(n) => Some(n.to_float()),
+            _ => x.parse::<NativeFloat>().ok(),
+        })
+        .collect::<Option<Vec<NativeFloat>>>()?;
+
+    let magnitude = parts[0];
+    let angle = parts[1];
+
+    Some(SExpr::from(NativeComplex::from_polar(magnitude, angle)))
+}
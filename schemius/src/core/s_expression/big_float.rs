@@ -0,0 +1,346 @@
+use std::fmt;
+use std::str::FromStr;
+
+use dashu_float::{ops::SquareRoot, DBig};
+use dashu_int::{ops::UnsignedAbs, IBig, Sign, UBig};
+use num::{bigint, Integer, ToPrimitive};
+
+use super::s_number::{NativeBigFloat, NativeBigInt, NativeCasts, NativeRational, SNumber};
+
+/// Extra digits intermediate results of elementary functions carry to absorb rounding errors.
+const GUARD_DIGITS: usize = 10;
+
+fn to_ibig(n: &NativeBigInt) -> IBig {
+    let (sign, bytes) = n.to_bytes_le();
+    let magnitude = UBig::from_le_bytes(&bytes);
+
+    match sign {
+        bigint::Sign::Minus => IBig::from_parts(Sign::Negative, magnitude),
+        _ => IBig::from(magnitude),
+    }
+}
+
+fn from_ibig(n: &IBig) -> NativeBigInt {
+    let (sign, magnitude) = n.clone().into_parts();
+    let magnitude = NativeBigInt::from_bytes_le(bigint::Sign::Plus, &magnitude.to_le_bytes());
+
+    match sign {
+        Sign::Negative => -magnitude,
+        Sign::Positive => magnitude,
+    }
+}
+
+fn round(x: NativeBigFloat, precision: usize) -> NativeBigFloat {
+    x.with_precision(precision).value()
+}
+
+pub fn from_rational(q: &NativeRational, precision: usize) -> NativeBigFloat {
+    let numer = round(DBig::from(to_ibig(q.numer())), precision);
+    let denom = round(DBig::from(to_ibig(q.denom())), precision);
+
+    numer / denom
+}
+
+/// The exact value of a big float, which is always finite.
+pub fn to_rational(x: &NativeBigFloat) -> NativeRational {
+    let significand = from_ibig(x.repr().significand());
+    let scale = num::pow(NativeBigInt::from(10), x.repr().exponent().unsigned_abs());
+
+    if x.repr().exponent() < 0 {
+        NativeRational::new(significand, scale)
+    } else {
+        NativeRational::from(significand * scale)
+    }
+}
+
+/// Reads a decimal literal as a big float of `precision` significant digits.
+pub fn parse(literal: &str, radix: u32, precision: usize) -> Option<NativeBigFloat> {
+    match radix {
+        10 => DBig::from_str(literal).ok().map(|x| round(x, precision)),
+        _ => None,
+    }
+}
+
+/// Divides, overflowing to a native infinity or NaN when the divisor is zero.
+pub fn divide(l: &NativeBigFloat, r: &NativeBigFloat) -> SNumber {
+    if r.repr().is_zero() {
+        SNumber::Float(l.to_float() / 0.0)
+    } else {
+        SNumber::BigFloat(l / r)
+    }
+}
+
+/// Prints like native floats do, switching to scientific notation for large and small
+/// magnitudes and always marking the number as inexact.
+pub fn write(x: &NativeBigFloat, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let significand = x.repr().significand();
+    let mut digits = significand.clone().unsigned_abs().to_string();
+    let mut exponent = x.repr().exponent();
+
+    while digits.len() > 1 && digits.ends_with('0') {
+        digits.pop();
+        exponent += 1;
+    }
+
+    if significand.sign() == Sign::Negative {
+        write!(f, "-")?;
+    }
+
+    // Position of the decimal point from the first digit
+    let point = digits.len() as isize + exponent;
+    match point - 1 {
+        magnitude if !(-4..16).contains(&magnitude) => {
+            let (first, rest) = digits.split_at(1);
+            match rest.is_empty() {
+                true => write!(f, "{}e{}", first, magnitude),
+                false => write!(f, "{}.{}e{}", first, rest, magnitude),
+            }
+        }
+        _ if point <= 0 => write!(f, "0.{}{}", "0".repeat(point.unsigned_abs()), digits),
+        _ if point as usize >= digits.len() => {
+            write!(f, "{}{}.0", digits, "0".repeat(point as usize - digits.len()))
+        }
+        _ => write!(f, "{}.{}", &digits[..point as usize], &digits[point as usize..]),
+    }
+}
+
+/// Evaluates a series whose terms come from the previous term and their index, until adding a
+/// term no longer changes the sum at the working precision.
+fn series(
+    first: NativeBigFloat, mut next: impl FnMut(&NativeBigFloat, usize) -> NativeBigFloat,
+) -> NativeBigFloat {
+    let mut sum = first.clone();
+    let mut term = first;
+
+    for index in 1.. {
+        term = next(&term, index);
+        let updated = &sum + &term;
+        if updated == sum {
+            break;
+        }
+        sum = updated;
+    }
+
+    sum
+}
+
+/// Arctangent by its Taylor series, converging quickly for small arguments only.
+fn atan_series(x: &NativeBigFloat) -> NativeBigFloat {
+    let square = x.sqr();
+
+    let mut power = x.clone();
+    series(x.clone(), |_, index| {
+        power = -(&power * &square);
+        &power / DBig::from(2 * index as u64 + 1)
+    })
+}
+
+fn pi(precision: usize) -> NativeBigFloat {
+    let one = round(DBig::ONE, precision);
+    let fifth = &one / DBig::from(5u8);
+    let inverse_239 = &one / DBig::from(239u8);
+
+    // Machin's formula
+    atan_series(&fifth) * DBig::from(16u8) - atan_series(&inverse_239) * DBig::from(4u8)
+}
+
+pub fn exp(x: &NativeBigFloat) -> NativeBigFloat {
+    x.exp()
+}
+
+pub fn ln(x: &NativeBigFloat) -> NativeBigFloat {
+    x.ln()
+}
+
+pub fn sqrt(x: &NativeBigFloat) -> NativeBigFloat {
+    x.sqrt()
+}
+
+/// Sine and cosine of an argument reduced by multiples of a quarter turn.
+fn sin_cos(x: &NativeBigFloat) -> (NativeBigFloat, NativeBigFloat) {
+    let magnitude = x.repr().digits() as isize + x.repr().exponent();
+    let working = x.precision() + GUARD_DIGITS + magnitude.max(0) as usize;
+
+    let x = round(x.clone(), working);
+    let half_pi = pi(working) / DBig::from(2u8);
+    let quarters = (&x / &half_pi).round();
+    let reduced = &x - &quarters * &half_pi;
+    let square = reduced.sqr();
+
+    let sin = series(reduced.clone(), |term, index| {
+        -(term * &square) / DBig::from((2 * index * (2 * index + 1)) as u64)
+    });
+    let cos = series(round(DBig::ONE, working), |term, index| {
+        -(term * &square) / DBig::from((2 * index * (2 * index - 1)) as u64)
+    });
+
+    let quadrant = from_ibig(&quarters.to_int().value()).mod_floor(&NativeBigInt::from(4));
+    match quadrant.to_u8() {
+        Some(0) => (sin, cos),
+        Some(1) => (cos, -sin),
+        Some(2) => (-sin, -cos),
+        _ => (-cos, sin),
+    }
+}
+
+pub fn sin(x: &NativeBigFloat) -> NativeBigFloat {
+    round(sin_cos(x).0, x.precision())
+}
+
+pub fn cos(x: &NativeBigFloat) -> NativeBigFloat {
+    round(sin_cos(x).1, x.precision())
+}
+
+pub fn tan(x: &NativeBigFloat) -> NativeBigFloat {
+    let (sin, cos) = sin_cos(x);
+    round(sin / cos, x.precision())
+}
+
+pub fn atan(x: &NativeBigFloat) -> NativeBigFloat {
+    let working = x.precision() + GUARD_DIGITS;
+    let one = round(DBig::ONE, working);
+    let mut x = round(x.clone(), working);
+
+    // Beyond one, the complementary angle of the reciprocal converges faster
+    let complement = match x.sign() {
+        Sign::Positive => x > one,
+        Sign::Negative => -x.clone() > one,
+    };
+    if complement {
+        x = &one / x;
+    }
+
+    // Each halving of the angle makes the series converge faster
+    let mut halvings = 0;
+    while !x.repr().is_zero() && x.repr().exponent() + x.repr().digits() as isize > -2 {
+        x = &x / (&one + (&one + x.sqr()).sqrt());
+        halvings += 1;
+    }
+
+    let mut angle = atan_series(&x) * DBig::from(1u64 << halvings);
+    if complement {
+        let half_pi = pi(working) / DBig::from(2u8);
+        angle = match angle.sign() {
+            Sign::Positive => half_pi - angle,
+            Sign::Negative => -half_pi - angle,
+        };
+    }
+
+    round(angle, working - GUARD_DIGITS)
+}
+
+/// The angle of the point (`x`, `y`), between -π and π.
+pub fn atan2(y: &NativeBigFloat, x: &NativeBigFloat) -> NativeBigFloat {
+    let precision = y.precision().max(x.precision());
+
+    if x.repr().is_zero() {
+        let half_pi = pi(precision + GUARD_DIGITS) / DBig::from(2u8);
+        return round(
+            match (y.repr().is_zero(), y.sign()) {
+                (true, _) => DBig::ZERO,
+                (false, Sign::Positive) => half_pi,
+                (false, Sign::Negative) => -half_pi,
+            },
+            precision,
+        );
+    }
+
+    let working = precision + GUARD_DIGITS;
+    let angle = atan(&(round(y.clone(), working) / x));
+    let angle = match (x.sign(), y.sign()) {
+        (Sign::Positive, _) => angle,
+        (Sign::Negative, Sign::Negative) => angle - pi(working),
+        (Sign::Negative, Sign::Positive) => angle + pi(working),
+    };
+
+    round(angle, precision)
+}
+
+/// The cosine of the angle whose sine is `x`, for `x` between -1 and 1.
+fn cosine_of_arcsine(x: &NativeBigFloat) -> NativeBigFloat {
+    (round(DBig::ONE, x.precision()) - x.sqr()).sqrt()
+}
+
+pub fn asin(x: &NativeBigFloat) -> NativeBigFloat {
+    let working = round(x.clone(), x.precision() + GUARD_DIGITS);
+    round(atan2(&working, &cosine_of_arcsine(&working)), x.precision())
+}
+
+pub fn acos(x: &NativeBigFloat) -> NativeBigFloat {
+    let working = round(x.clone(), x.precision() + GUARD_DIGITS);
+    round(atan2(&cosine_of_arcsine(&working), &working), x.precision())
+}
+
+/// The precision elementary functions of `arguments` are computed at: the largest one among
+/// big floats or, when there are none, `default`. No precision applies to complex arguments or
+/// native infinities and NaN.
+pub fn precision_of(arguments: &[&SNumber], default: Option<usize>) -> Option<usize> {
+    let mut precision = None;
+
+    for argument in arguments {
+        match argument {
+            SNumber::BigFloat(x) => precision = precision.max(Some(x.precision())),
+            number if number.is_real() && !number.is_infinite() && !number.is_nan() => (),
+            _ => return None,
+        }
+    }
+
+    precision.or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use dashu_float::DBig;
+
+    use super::*;
+
+    fn big(literal: &str, precision: usize) -> NativeBigFloat {
+        round(DBig::from_str(literal).unwrap(), precision)
+    }
+
+    #[test]
+    fn big_float_write() {
+        let cases = [
+            ("1", "1.0"),
+            ("-2.5", "-2.5"),
+            ("1234.5", "1234.5"),
+            ("0.00012", "0.00012"),
+            ("0.000012", "1.2e-5"),
+            ("1e16", "1e16"),
+            ("-1.25e20", "-1.25e20"),
+            ("0", "0.0"),
+        ];
+
+        for (literal, expected) in cases {
+            assert_eq!(SNumber::BigFloat(big(literal, 20)).to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn big_float_pi() {
+        let digits = "3.14159265358979323846264338327950288419716939937510582097494459230781640629";
+        assert_eq!(round(pi(80), 75), big(digits, 75));
+    }
+
+    #[test]
+    fn big_float_reduction() {
+        // Arguments are reduced by multiples of a quarter turn without losing digits
+        let x = big("1e30", 40);
+        let (sin, cos) = (sin(&x), cos(&x));
+
+        assert_eq!(sin, big("-0.0901169019121380580303864289529873302744", 40));
+        assert_eq!(round(&sin * &sin + &cos * &cos, 30), big("1", 30));
+    }
+
+    #[test]
+    fn big_float_inverse_functions() {
+        let x = big("0.3", 40);
+
+        assert_eq!(round(tan(&atan(&x)), 35), big("0.3", 35));
+        assert_eq!(round(sin(&asin(&x)), 35), big("0.3", 35));
+        assert_eq!(round(cos(&acos(&x)), 35), big("0.3", 35));
+        assert_eq!(round(exp(&ln(&x)), 35), big("0.3", 35));
+    }
+}
@@ -1,34 +1,42 @@
+#[cfg(feature = "bigfloat")]
+pub mod big_float;
 pub mod s_list;
 pub mod s_number;
+pub mod s_pair;
 pub mod s_procedure;
+pub mod s_string;
+pub mod s_symbol;
 
 use cfg_if::cfg_if;
 
-use super::{accessor::*, constants::tokens};
-use std::{collections::LinkedList, fmt, result};
+use super::{
+    accessor::*,
+    constants::tokens,
+    error::{ErrorKind, SchemeError},
+};
+use std::{collections::HashSet, fmt, ptr::fn_addr_eq};
 
-pub use self::{s_list::*, s_number::*, s_procedure::*};
-type SAccessor<T> = ThreadSafeAccessor<T>;
+pub use self::{s_list::*, s_number::*, s_pair::*, s_procedure::*, s_string::*, s_symbol::*};
+type SAccessor<T> = DefaultAccessor<T>;
 
 cfg_if! {
     if #[cfg(feature = "true_list")] {
-        pub type ListImplementation = LinkedList<SExpr>;
+        pub type ListImplementation = std::collections::LinkedList<SExpr>;
     } else {
         pub type ListImplementation = Vec<SExpr>;
     }
 }
 
-pub type PairImplementation = (Box<SExpr>, Box<SExpr>);
+pub type PairImplementation = Cons;
 pub type VectorImplementation = Vec<SExpr>;
 
 pub type SchemeBoolean = bool;
 pub type SchemeChar = char;
-pub type SchemeList = SAccessor<ListImplementation>;
 pub type SchemeNumber = SNumber;
 pub type SchemePair = SAccessor<PairImplementation>;
 pub type SchemeProcedure = Procedure;
-pub type SchemeSymbol = String;
-pub type SchemeString = SAccessor<String>;
+pub type SchemeSymbol = Symbol;
+pub type SchemeString = SAccessor<SString>;
 pub type SchemeVector = SAccessor<VectorImplementation>;
 
 #[derive(Clone, Debug)]
@@ -39,9 +47,14 @@ pub enum SExpr {
     String(SchemeString),
     Number(SchemeNumber),
     Pair(SchemePair),
-    List(SchemeList),
+    /// The empty list.
+    Null,
     Vector(SchemeVector),
     Procedure(SchemeProcedure),
+    /// The syntactic keyword of a special form. Keywords are bound like variables, so that local
+    /// variables can shadow them, but are never the value of an expression.
+    Keyword(SpecialFormSignature),
+    Environment(ProcedureEnv),
     Unspecified,
     Ok,
 }
@@ -58,11 +71,11 @@ macro_rules! impl_from_primitive {
 }
 
 macro_rules! impl_from_number {
-    ($($source:ident, $target:ident)*) => {
+    ($($source:ident)*) => {
     $(
         impl From<$source> for SExpr {
             fn from(val: $source) -> Self {
-                SExpr::Number(SNumber::$target(val))
+                SExpr::Number(SNumber::from(val))
             }
         }
     )*}
@@ -74,15 +87,105 @@ impl_from_primitive! {
 }
 
 impl_from_number! {
-    NativeInt, Int
-    NativeBigInt, BigInt
-    NativeRational, Rational
-    NativeComplex, Complex
-    NativeFloat, Float
+    NativeInt
+    NativeBigInt
+    NativeRational
+    NativeComplex
+    NativeExactComplex
+    NativeFloat
+}
+
+/// What is left to print of a value, whose lists and vectors are printed an element at a time.
+enum Printing {
+    Value(SExpr),
+    /// The rest of a list whose first element is printed already.
+    Tail(SExpr),
+    Text(&'static str),
+    /// The end of the elements of a container, which is no longer being printed.
+    Done(*const ()),
 }
 
 impl fmt::Display for SExpr {
+    // Containers are printed with a stack of what is left rather than by recursion, so that
+    // deeply nested data cannot overflow the native stack. A container met again while its
+    // elements are printed contains itself, and is printed as an ellipsis, as is one that is
+    // being changed.
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut pending = vec![Printing::Value(self.clone())];
+        let mut printing: HashSet<*const ()> = HashSet::new();
+
+        let contents = |pair: &SchemePair, printing: &HashSet<*const ()>| {
+            if printing.contains(&pair.as_ptr()) {
+                return None;
+            }
+            let cons = pair.try_access()?;
+            Some((cons.car.clone(), cons.cdr.clone()))
+        };
+
+        while let Some(next) = pending.pop() {
+            match next {
+                Printing::Value(SExpr::Pair(pair)) => match contents(&pair, &printing) {
+                    Some((car, cdr)) => {
+                        write!(f, "(")?;
+                        printing.insert(pair.as_ptr());
+                        pending.extend([
+                            Printing::Done(pair.as_ptr()),
+                            Printing::Tail(cdr),
+                            Printing::Value(car),
+                        ]);
+                    }
+                    None => write!(f, "{}", tokens::ELLIPSIS)?,
+                },
+                Printing::Value(SExpr::Vector(vector)) => {
+                    let items = match printing.contains(&vector.as_ptr()) {
+                        true => None,
+                        false => vector.try_access().map(|items| items.clone()),
+                    };
+                    let Some(items) = items else {
+                        write!(f, "{}", tokens::ELLIPSIS)?;
+                        continue;
+                    };
+
+                    write!(f, "#(")?;
+                    printing.insert(vector.as_ptr());
+                    pending.extend([Printing::Done(vector.as_ptr()), Printing::Text(")")]);
+                    for (index, item) in items.into_iter().enumerate().rev() {
+                        pending.push(Printing::Value(item));
+                        if index > 0 {
+                            pending.push(Printing::Text(" "));
+                        }
+                    }
+                }
+                Printing::Value(value) => value.fmt_atom(f)?,
+                Printing::Tail(SExpr::Null) => write!(f, ")")?,
+                Printing::Tail(SExpr::Pair(pair)) => match contents(&pair, &printing) {
+                    Some((car, cdr)) => {
+                        write!(f, " ")?;
+                        printing.insert(pair.as_ptr());
+                        pending.extend([
+                            Printing::Done(pair.as_ptr()),
+                            Printing::Tail(cdr),
+                            Printing::Value(car),
+                        ]);
+                    }
+                    None => write!(f, " {})", tokens::ELLIPSIS)?,
+                },
+                Printing::Tail(tail) => {
+                    write!(f, " {} ", tokens::DOT)?;
+                    pending.extend([Printing::Text(")"), Printing::Value(tail)]);
+                }
+                Printing::Text(text) => write!(f, "{}", text)?,
+                Printing::Done(container) => _ = printing.remove(&container),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl SExpr {
+    /// Prints a value that is neither a pair nor a vector.
+    fn fmt_atom(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             SExpr::Symbol(ref val) => write!(f, "{}", val),
             SExpr::Char(val) => write!(f, "{}{}", tokens::PREFIX_CHAR, val),
@@ -90,99 +193,90 @@ impl fmt::Display for SExpr {
             SExpr::Boolean(val) => {
                 write!(f, "{}", if *val { tokens::TRUE } else { tokens::FALSE })
             }
-            SExpr::String(ref val) => {
-                write!(f, "{}{}{}", tokens::PREFIX_STRING, *val.access(), tokens::SUFFIX_STRING)
-            }
+            SExpr::String(ref val) => match val.access() {
+                Ok(string) => {
+                    write!(f, "{}{}{}", tokens::PREFIX_STRING, *string, tokens::SUFFIX_STRING)
+                }
+                Err(_) => write!(f, "{}", tokens::ELLIPSIS),
+            },
             SExpr::Procedure(app) => match app {
-                Procedure::SpecialForm(_) => write!(f, "#<special form>"),
                 Procedure::Primitive(_) => write!(f, "#<primitive>"),
-                Procedure::Compound(args, _, _) => write!(f, "#<procedure ({})>", args.join(", ")),
+                Procedure::Compound(lambda, _) => {
+                    write!(
+                        f,
+                        "#<procedure ({})>",
+                        lambda.params.iter().map(Symbol::as_str).collect::<Vec<&str>>().join(", ")
+                    )
+                }
             },
-            SExpr::Pair(val) => {
-                let borrowed_val = val.access();
-                write!(f, "({} {} {})", borrowed_val.0, tokens::DOT, borrowed_val.1)
-            }
-            SExpr::List(ref val) => write!(
-                f,
-                "({})",
-                val.access().iter().map(|x| x.to_string()).collect::<Vec<String>>().join(" ")
-            ),
-            SExpr::Vector(ref val) => write!(
-                f,
-                "#({})",
-                val.access().iter().map(|x| x.to_string()).collect::<Vec<String>>().join(" ")
-            ),
+            SExpr::Keyword(_) => write!(f, "#<special form>"),
+            SExpr::Environment(_) => write!(f, "#<environment>"),
+            SExpr::Null => write!(f, "()"),
             SExpr::Unspecified => writeln!(f),
             SExpr::Ok => write!(f, "ok"),
+            SExpr::Pair(_) | SExpr::Vector(_) => unreachable!("containers are printed by fmt"),
         }
     }
 }
 
 impl SExpr {
-    pub fn as_int(&self) -> Result<NativeInt, String> {
+    /// The interned symbol named `name`.
+    pub fn symbol(name: &str) -> SExpr {
+        SExpr::Symbol(Symbol::new(name))
+    }
+
+    fn type_error(&self, expected: &str) -> SchemeError {
+        SchemeError::new(ErrorKind::WrongType, format!("{} is not {}", self, expected))
+            .with_irritants([self.clone()])
+    }
+
+    pub fn as_int(&self) -> Result<NativeInt, SchemeError> {
         match self {
             SExpr::Number(n) => Ok(n.to_int()?),
-            _ => Err(format!("Exception: {} is not a number", self)),
+            _ => Err(self.type_error("a number")),
         }
     }
 
-    pub fn as_char(&self) -> Result<SchemeChar, String> {
+    pub fn as_char(&self) -> Result<SchemeChar, SchemeError> {
         match self {
             SExpr::Char(val) => Ok(*val as SchemeChar),
-            _ => panic!("Exception: {} is not a character", self),
+            _ => Err(self.type_error("a character")),
         }
     }
 
-    pub fn as_symbol(&self) -> Result<SchemeSymbol, String> {
+    pub fn as_symbol(&self) -> Result<SchemeSymbol, SchemeError> {
         match self {
             SExpr::Symbol(val) => Ok(val.clone()),
-            _ => Err(format!("Exception: {} is not a symbol", self)),
+            _ => Err(self.type_error("a symbol")),
         }
     }
 
-    pub fn as_list(&self) -> Result<ListImplementation, String> {
-        Ok(match self {
-            SExpr::List(list) => list.access().clone(),
-            _ => panic!("Exception: {} is not a list", self),
-        })
+    pub fn quote(&self) -> Result<SExpr, SchemeError> {
+        Ok(SExpr::list([SExpr::symbol(tokens::QUOTE_EXPLICIT), self.clone()]))
     }
 
-    pub fn quote(&self) -> Result<SExpr, String> {
-        Ok(SExpr::List(SchemeList::new(ListImplementation::from_iter([
-            SExpr::Symbol(tokens::QUOTE_EXPLICIT.to_string()),
-            self.clone(),
-        ]))))
-    }
-
-    pub fn unquote(&self) -> Result<SExpr, String> {
+    pub fn unquote(&self) -> Result<SExpr, SchemeError> {
         match self {
-            SExpr::List(list) => {
-                let borrowed_list = list.access();
-                if borrowed_list.s_car().unwrap().is_quote().unwrap() {
-                    Ok(borrowed_list.s_ref(1).unwrap().clone())
-                } else {
-                    Err(format!("Exception: {} is not a quoted expression", self))
+            SExpr::Pair(_) => {
+                let mut items = self.pairs();
+                match (items.next(), items.next()) {
+                    (Some(quote), Some(quoted)) if quote.is_quote()? => Ok(quoted),
+                    _ => Err(self.type_error("a quoted expression")),
                 }
             }
-            _ => Err(format!("Exception: {} is not a list", self)),
+            _ => Err(self.type_error("a list")),
         }
     }
 
-    pub fn symbol_is(&self, repr: &str) -> Result<bool, String> {
+    pub fn symbol_is(&self, repr: &str) -> Result<bool, SchemeError> {
         match self {
-            SExpr::Symbol(val) => {
-                if val.as_str() == repr {
-                    Ok(true)
-                } else {
-                    Ok(false)
-                }
-            }
+            SExpr::Symbol(val) => Ok(*val == repr),
             _ => Ok(false),
         }
     }
 
     #[allow(dead_code)]
-    fn is_left_bracket(&self) -> Result<bool, String> {
+    fn is_left_bracket(&self) -> Result<bool, SchemeError> {
         if self.symbol_is(tokens::OPEN_PAREN).unwrap()
             || self.symbol_is(tokens::OPEN_BRACKET).unwrap()
         {
@@ -193,7 +287,7 @@ impl SExpr {
     }
 
     #[allow(dead_code)]
-    fn is_right_bracket(&self) -> Result<bool, String> {
+    fn is_right_bracket(&self) -> Result<bool, SchemeError> {
         if self.symbol_is(tokens::CLOSED_PAREN).unwrap()
             || self.symbol_is(tokens::CLOSED_BRACKET).unwrap()
         {
@@ -203,92 +297,104 @@ impl SExpr {
         }
     }
 
-    pub fn is_char(&self) -> Result<bool, String> {
+    pub fn is_char(&self) -> Result<bool, SchemeError> {
         match self {
             SExpr::Char(_) => Ok(true),
             _ => Ok(false),
         }
     }
 
-    pub fn is_symbol(&self) -> Result<bool, String> {
+    pub fn is_symbol(&self) -> Result<bool, SchemeError> {
         match self {
             SExpr::Symbol(_) => Ok(true),
             _ => Ok(false),
         }
     }
 
-    pub fn is_applyable(&self) -> Result<bool, String> {
+    pub fn is_applyable(&self) -> Result<bool, SchemeError> {
         match self {
-            SExpr::List(list) if list.access().s_car().unwrap().is_procedure()? => Ok(true),
+            SExpr::Pair(pair) => pair.access()?.car.is_procedure(),
             _ => Ok(false),
         }
     }
 
-    pub fn is_quote_pure(&self) -> Result<bool, String> {
+    pub fn is_quote_pure(&self) -> Result<bool, SchemeError> {
         Ok(self.symbol_is(tokens::QUOTE)? || self.symbol_is(tokens::QUOTE_EXPLICIT)?)
     }
 
-    pub fn is_quasiquote(&self) -> Result<bool, String> {
+    pub fn is_quasiquote(&self) -> Result<bool, SchemeError> {
         Ok(self.symbol_is(tokens::QUASIQUOTE)? || self.symbol_is(tokens::QUASIQUOTE_EXPLICIT)?)
     }
 
-    pub fn is_quote(&self) -> Result<bool, String> {
+    pub fn is_quote(&self) -> Result<bool, SchemeError> {
         Ok(self.is_quote_pure()? || self.is_quasiquote()?)
     }
 
-    pub fn is_unquote_pure(&self) -> Result<bool, String> {
+    pub fn is_unquote_pure(&self) -> Result<bool, SchemeError> {
         Ok(self.symbol_is(tokens::UNQUOTE)? || self.symbol_is(tokens::UNQUOTE_EXPLICIT)?)
     }
 
-    pub fn is_unquote_splicing(&self) -> Result<bool, String> {
+    pub fn is_unquote_splicing(&self) -> Result<bool, SchemeError> {
         Ok(self.symbol_is(tokens::UNQUOTE_SPLICING)?
             || self.symbol_is(tokens::UNQUOTE_SPLICING_EXPLICIT)?)
     }
 
-    pub fn is_unquote(&self) -> Result<bool, String> {
+    pub fn is_unquote(&self) -> Result<bool, SchemeError> {
         Ok(self.is_unquote_pure()? || self.is_unquote_splicing()?)
     }
 
-    pub fn is_quoted_list(&self) -> Result<bool, String> {
+    pub fn is_quoted_list(&self) -> Result<bool, SchemeError> {
         match self {
-            SExpr::List(list) => {
-                let borrowed = list.access();
-                if borrowed.s_len() > 0 {
-                    let car = borrowed.s_car().unwrap();
-                    match car {
-                        SExpr::Symbol(_) => Ok(car.is_quote().unwrap()),
-                        _ => Ok(false),
-                    }
-                } else {
-                    Ok(false)
-                }
-            }
+            SExpr::Pair(pair) => pair.access()?.car.is_quote(),
             _ => Ok(false),
         }
     }
 
-    pub fn is_string(&self) -> Result<bool, String> {
+    /// Whether both values are `eqv?`, as used to select the clauses of `case`. Symbols are
+    /// compared by identity, and so are strings, vectors and procedures.
+    pub fn is_eqv(&self, other: &SExpr) -> bool {
+        match (self, other) {
+            (SExpr::Boolean(a), SExpr::Boolean(b)) => a == b,
+            (SExpr::Char(a), SExpr::Char(b)) => a == b,
+            (SExpr::Symbol(a), SExpr::Symbol(b)) => a == b,
+            (SExpr::Number(a), SExpr::Number(b)) => a.is_exact() == b.is_exact() && a == b,
+            (SExpr::Null, SExpr::Null) => true,
+            (SExpr::Pair(_), SExpr::Pair(_)) => self.is_same_pair(other),
+            (SExpr::String(a), SExpr::String(b)) => a.ptr_eq(b),
+            (SExpr::Vector(a), SExpr::Vector(b)) => a.ptr_eq(b),
+            (SExpr::Procedure(a), SExpr::Procedure(b)) => match (a, b) {
+                (Procedure::Primitive(a), Procedure::Primitive(b)) => fn_addr_eq(*a, *b),
+                (Procedure::Compound(a, a_env), Procedure::Compound(b, b_env)) => {
+                    Shared::ptr_eq(a, b) && a_env.ptr_eq(b_env)
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    pub fn is_string(&self) -> Result<bool, SchemeError> {
         match self {
             SExpr::String(_) => Ok(true),
             _ => Ok(false),
         }
     }
 
-    pub fn is_boolean(&self) -> Result<bool, String> {
+    pub fn is_boolean(&self) -> Result<bool, SchemeError> {
         match self {
             SExpr::Boolean(_) => Ok(true),
             _ => Ok(false),
         }
     }
 
-    pub fn is_number(&self) -> Result<bool, String> {
+    pub fn is_number(&self) -> Result<bool, SchemeError> {
         match self {
             SExpr::Number(_) => Ok(true),
             _ => Ok(false),
         }
     }
 
-    pub fn is_exact(&self) -> Result<bool, String> {
+    pub fn is_exact(&self) -> Result<bool, SchemeError> {
         match self {
             SExpr::Number(n) => {
                 if n.is_exact() {
@@ -297,11 +403,11 @@ impl SExpr {
                     Ok(false)
                 }
             }
-            _ => Err(format!("Exception: {} is not a number", self)),
+            _ => Err(self.type_error("a number")),
         }
     }
 
-    pub fn is_integer(&self) -> Result<bool, String> {
+    pub fn is_integer(&self) -> Result<bool, SchemeError> {
         match self {
             SExpr::Number(n) => {
                 if n.is_integer() {
@@ -310,11 +416,11 @@ impl SExpr {
                     Ok(false)
                 }
             }
-            _ => Err(format!("Exception: {} is not a number", self)),
+            _ => Err(self.type_error("a number")),
         }
     }
 
-    pub fn is_real(&self) -> Result<bool, String> {
+    pub fn is_real(&self) -> Result<bool, SchemeError> {
         match self {
             SExpr::Number(n) => {
                 if n.is_real() {
@@ -323,11 +429,11 @@ impl SExpr {
                     Ok(false)
                 }
             }
-            _ => Err(format!("Exception: {} is not a number", self)),
+            _ => Err(self.type_error("a number")),
         }
     }
 
-    pub fn is_rational(&self) -> Result<bool, String> {
+    pub fn is_rational(&self) -> Result<bool, SchemeError> {
         match self {
             SExpr::Number(n) => {
                 if n.is_rational() {
@@ -336,11 +442,11 @@ impl SExpr {
                     Ok(false)
                 }
             }
-            _ => Err(format!("Exception: {} is not a number", self)),
+            _ => Err(self.type_error("a number")),
         }
     }
 
-    pub fn is_complex(&self) -> Result<bool, String> {
+    pub fn is_complex(&self) -> Result<bool, SchemeError> {
         match self {
             SExpr::Number(n) => {
                 if n.is_complex() {
@@ -349,11 +455,11 @@ impl SExpr {
                     Ok(false)
                 }
             }
-            _ => Err(format!("Exception: {} is not a number", self)),
+            _ => Err(self.type_error("a number")),
         }
     }
 
-    pub fn is_zero(&self) -> Result<bool, String> {
+    pub fn is_zero(&self) -> Result<bool, SchemeError> {
         match self {
             SExpr::Number(n) => {
                 if n.is_zero() {
@@ -362,11 +468,11 @@ impl SExpr {
                     Ok(false)
                 }
             }
-            _ => Err(format!("Exception: {} is not a number", self)),
+            _ => Err(self.type_error("a number")),
         }
     }
 
-    pub fn is_nan(&self) -> Result<bool, String> {
+    pub fn is_nan(&self) -> Result<bool, SchemeError> {
         match self {
             SExpr::Number(n) => {
                 if n.is_nan() {
@@ -375,11 +481,11 @@ impl SExpr {
                     Ok(false)
                 }
             }
-            _ => Err(format!("Exception: {} is not a number", self)),
+            _ => Err(self.type_error("a number")),
         }
     }
 
-    pub fn is_infinite(&self) -> Result<bool, String> {
+    pub fn is_infinite(&self) -> Result<bool, SchemeError> {
         match self {
             SExpr::Number(n) => {
                 if n.is_infinite() {
@@ -388,52 +494,42 @@ impl SExpr {
                     Ok(false)
                 }
             }
-            _ => Err(format!("Exception: {} is not a number", self)),
+            _ => Err(self.type_error("a number")),
         }
     }
 
-    pub fn is_pair(&self) -> Result<bool, String> {
+    pub fn is_pair(&self) -> Result<bool, SchemeError> {
         match self {
             SExpr::Pair(_) => Ok(true),
-            SExpr::List(_) => Ok(true),
             _ => Ok(false),
         }
     }
 
-    pub fn is_atom(&self) -> Result<bool, String> {
+    pub fn is_atom(&self) -> Result<bool, SchemeError> {
         Ok(!self.is_pair()?)
     }
 
-    pub fn is_list(&self) -> Result<bool, String> {
-        match self {
-            SExpr::List(_) => Ok(true),
-            _ => Ok(false),
-        }
+    pub fn is_list(&self) -> Result<bool, SchemeError> {
+        Ok(self.list_length().is_some())
     }
 
-    pub fn is_vector(&self) -> Result<bool, String> {
+    pub fn is_vector(&self) -> Result<bool, SchemeError> {
         match self {
             SExpr::Vector(_) => Ok(true),
             _ => Ok(false),
         }
     }
 
-    pub fn is_procedure(&self) -> Result<bool, String> {
+    pub fn is_procedure(&self) -> Result<bool, SchemeError> {
         match self {
             SExpr::Procedure(_) => Ok(true),
             _ => Ok(false),
         }
     }
 
-    pub fn is_null(&self) -> result::Result<bool, String> {
+    pub fn is_null(&self) -> Result<bool, SchemeError> {
         match self {
-            SExpr::List(list) => {
-                if list.access().is_empty() {
-                    Ok(true)
-                } else {
-                    Ok(false)
-                }
-            }
+            SExpr::Null => Ok(true),
             _ => Ok(false),
         }
     }
@@ -453,7 +549,7 @@ mod s_expression_tests {
 
     #[test]
     fn test_sexpr_as_symbol() {
-        let sexpr = SExpr::Symbol("symbol".to_string());
+        let sexpr = SExpr::symbol("symbol");
         assert_eq!(sexpr.as_symbol().unwrap(), "symbol");
     }
 
@@ -486,21 +582,21 @@ mod s_expression_tests {
 
     #[test]
     fn test_sexpr_is_applyable() {
-        let sexpr = SExpr::List(SchemeList::new(ListImplementation::from_iter([
+        let sexpr = SExpr::list([
             SExpr::Procedure(Procedure::Primitive(Primitive::SUM)),
             SExpr::from(1),
             SExpr::from(2),
-        ])));
+        ]);
         assert!(sexpr.is_applyable().unwrap());
     }
 
     #[test]
     fn test_sexpr_is_quote() {
-        let quote = SExpr::Symbol(tokens::QUOTE.to_string());
-        let quote_explicit = SExpr::Symbol(tokens::QUOTE_EXPLICIT.to_string());
-        let quasiquote = SExpr::Symbol(tokens::QUASIQUOTE.to_string());
-        let quasiquote_explicit = SExpr::Symbol(tokens::QUASIQUOTE_EXPLICIT.to_string());
-        let symbol = SExpr::Symbol("symbol".to_string());
+        let quote = SExpr::symbol(tokens::QUOTE);
+        let quote_explicit = SExpr::symbol(tokens::QUOTE_EXPLICIT);
+        let quasiquote = SExpr::symbol(tokens::QUASIQUOTE);
+        let quasiquote_explicit = SExpr::symbol(tokens::QUASIQUOTE_EXPLICIT);
+        let symbol = SExpr::symbol("symbol");
         let number = SExpr::from(42);
 
         assert!(quote.is_quote().unwrap());
@@ -530,12 +626,11 @@ mod s_expression_tests {
 
     #[test]
     fn test_sexpr_is_unquote() {
-        let unquote = SExpr::Symbol(tokens::UNQUOTE.to_string());
-        let unquote_explicit = SExpr::Symbol(tokens::UNQUOTE_EXPLICIT.to_string());
-        let unquote_splicing = SExpr::Symbol(tokens::UNQUOTE_SPLICING.to_string());
-        let unquote_splicing_explicit =
-            SExpr::Symbol(tokens::UNQUOTE_SPLICING_EXPLICIT.to_string());
-        let symbol = SExpr::Symbol("symbol".to_string());
+        let unquote = SExpr::symbol(tokens::UNQUOTE);
+        let unquote_explicit = SExpr::symbol(tokens::UNQUOTE_EXPLICIT);
+        let unquote_splicing = SExpr::symbol(tokens::UNQUOTE_SPLICING);
+        let unquote_splicing_explicit = SExpr::symbol(tokens::UNQUOTE_SPLICING_EXPLICIT);
+        let symbol = SExpr::symbol("symbol");
         let number = SExpr::from(42);
 
         assert!(unquote.is_unquote().unwrap());
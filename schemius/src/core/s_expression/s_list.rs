@@ -8,7 +8,6 @@ where
     fn new() -> Self {
         Self::from_iter(std::iter::empty())
     }
-    fn s_append(lists: &[Self]) -> Self;
     fn s_car(&self) -> Option<&T> {
         self.s_ref(0)
     }
@@ -17,13 +16,10 @@ where
     fn s_len(&self) -> usize;
     fn s_ref(&self, index: usize) -> Option<&T>;
     fn s_splice(&self, insert: Self, start: usize, end: usize) -> Self;
-    fn s_tail(&self, k: usize) -> Self;
     fn s_reverse(&self) -> Self;
-    fn set_car(&mut self, value: T);
-    fn pop(&mut self) -> Option<T>;
     fn push(&mut self, value: T);
     fn last(&self) -> Option<&T> {
-        self.s_ref(self.s_len() - 1)
+        self.s_ref(self.s_len().checked_sub(1)?)
     }
     fn extract_range(self, start: usize, end: usize) -> Self {
         let mut result = Self::new();
@@ -39,10 +35,6 @@ impl<T> SList<T> for Vec<T>
 where
     T: Clone,
 {
-    fn s_append(lists: &[Self]) -> Self {
-        lists.iter().flat_map(|list| list.iter().cloned()).collect()
-    }
-
     fn s_car(&self) -> Option<&T> {
         self.first()
     }
@@ -72,24 +64,10 @@ where
         result
     }
 
-    fn s_tail(&self, k: usize) -> Self {
-        self.iter().skip(k).cloned().collect()
-    }
-
     fn s_reverse(&self) -> Self {
         self.iter().rev().cloned().collect()
     }
 
-    fn set_car(&mut self, value: T) {
-        if let Some(first) = self.first_mut() {
-            *first = value;
-        }
-    }
-
-    fn pop(&mut self) -> Option<T> {
-        self.pop()
-    }
-
     fn push(&mut self, value: T) {
         self.push(value);
     }
@@ -99,10 +77,6 @@ impl<T> SList<T> for LinkedList<T>
 where
     T: Clone,
 {
-    fn s_append(lists: &[Self]) -> Self {
-        lists.iter().flat_map(|list| list.iter().cloned()).collect()
-    }
-
     fn s_car(&self) -> Option<&T> {
         self.front()
     }
@@ -139,24 +113,10 @@ where
         head
     }
 
-    fn s_tail(&self, k: usize) -> Self {
-        self.iter().skip(k).cloned().collect()
-    }
-
     fn s_reverse(&self) -> Self {
         self.iter().rev().cloned().collect()
     }
 
-    fn set_car(&mut self, value: T) {
-        if let Some(first) = self.front_mut() {
-            *first = value;
-        }
-    }
-
-    fn pop(&mut self) -> Option<T> {
-        self.pop_front()
-    }
-
     fn push(&mut self, value: T) {
         self.push_back(value);
     }
@@ -166,16 +126,6 @@ where
 pub mod tests_slist_vector {
     use super::*;
 
-    #[test]
-    fn test_slist_vector_append() {
-        let list1 = vec![1, 2, 3];
-        let list2 = vec![4, 5, 6];
-        let list3 = vec![7, 8, 9];
-        let lists = &[list1, list2, list3];
-        let appended = SList::s_append(lists);
-        assert_eq!(appended, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
-    }
-
     #[test]
     fn test_slist_vector_car() {
         let list = vec![1, 2, 3, 4, 5];
@@ -220,13 +170,6 @@ pub mod tests_slist_vector {
         assert_eq!(spliced2, vec![1, 20, 21, 22, 5]);
     }
 
-    #[test]
-    fn test_slist_vector_tail() {
-        let list = vec![1, 2, 3, 4, 5];
-        let tail = list.s_tail(2);
-        assert_eq!(tail, vec![3, 4, 5]);
-    }
-
     #[test]
     fn test_slist_vector_reverse() {
         let list = vec![1, 2, 3, 4, 5];
@@ -234,21 +177,6 @@ pub mod tests_slist_vector {
         assert_eq!(reversed, vec![5, 4, 3, 2, 1]);
     }
 
-    #[test]
-    fn test_slist_vector_set_car() {
-        let mut list = vec![1, 2, 3, 4, 5];
-        list.set_car(10);
-        assert_eq!(list, vec![10, 2, 3, 4, 5]);
-    }
-
-    #[test]
-    fn test_slist_vector_pop() {
-        let mut list = vec![1, 2, 3, 4, 5];
-        let popped = list.pop();
-        assert_eq!(list.len(), 4);
-        assert_eq!(popped, Some(5));
-    }
-
     #[test]
     fn test_slist_vector_push() {
         let mut list = vec![1, 2, 3, 4, 5];
@@ -274,29 +202,6 @@ pub mod tests_slist_vector {
 pub mod test_slist_linked_list {
     use super::*;
 
-    #[test]
-    fn test_slist_linked_list_append() {
-        let mut list1 = LinkedList::new();
-        list1.push_back(1);
-        list1.push_back(2);
-        list1.push_back(3);
-
-        let mut list2 = LinkedList::new();
-        list2.push_back(4);
-        list2.push_back(5);
-        list2.push_back(6);
-
-        let mut list3 = LinkedList::new();
-        list3.push_back(7);
-        list3.push_back(8);
-        list3.push_back(9);
-
-        let lists = &[list1, list2, list3];
-        let appended = SList::s_append(lists);
-        let expected = vec![1, 2, 3, 4, 5, 6, 7, 8, 9];
-        assert_eq!(appended.iter().cloned().collect::<Vec<i32>>(), expected);
-    }
-
     #[test]
     fn test_slist_linked_list_car() {
         let mut list = LinkedList::new();
@@ -357,21 +262,6 @@ pub mod test_slist_linked_list {
         assert_eq!(spliced2, LinkedList::from_iter([1, 20, 21, 22, 5]));
     }
 
-    #[test]
-    fn test_slist_linked_list_tail() {
-        let list = LinkedList::from_iter([1, 2, 3, 4, 5]);
-        let tail = list.s_tail(2);
-        assert_eq!(tail, LinkedList::from_iter([3, 4, 5]));
-    }
-
-    #[test]
-    fn test_slist_linked_list_pop() {
-        let mut list = LinkedList::from_iter([1, 2, 3, 4, 5]);
-        let popped = list.pop();
-        assert_eq!(list.len(), 4);
-        assert_eq!(popped, Some(1));
-    }
-
     #[test]
     fn test_slist_linked_list_push() {
         let mut list = LinkedList::new();
@@ -1,14 +1,16 @@
 use core::fmt;
-use std::{
-    any::TypeId,
-    cmp,
-    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign},
-};
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign};
 
 use cfg_if::cfg_if;
-use num::{integer::Roots, BigInt, BigRational, Complex, One, ToPrimitive, Zero};
+use num::{BigInt, BigRational, Complex, One, ToPrimitive, Zero};
+
+use crate::core::{
+    constants::{numbers, tokens},
+    error::{ErrorKind, SchemeError},
+};
 
-use crate::core::constants::numbers;
+#[cfg(feature = "bigfloat")]
+use super::big_float;
 
 cfg_if! {
     if #[cfg(feature = "i32")] {
@@ -35,6 +37,9 @@ cfg_if! {
 pub type NativeBigInt = BigInt;
 pub type NativeRational = BigRational;
 pub type NativeComplex = Complex<NativeFloat>;
+pub type NativeExactComplex = Complex<NativeRational>;
+#[cfg(feature = "bigfloat")]
+pub type NativeBigFloat = dashu_float::DBig;
 
 #[derive(Debug, Clone)]
 pub enum SNumber {
@@ -43,6 +48,9 @@ pub enum SNumber {
     Rational(NativeRational),
     Float(NativeFloat),
     Complex(NativeComplex),
+    ExactComplex(NativeExactComplex),
+    #[cfg(feature = "bigfloat")]
+    BigFloat(NativeBigFloat),
 }
 
 pub struct NumericalConstant;
@@ -59,33 +67,105 @@ impl NumericalConstant {
 }
 
 pub trait NativeCasts {
-    fn to_float(&self) -> Option<NativeFloat>;
+    /// Converts to the nearest float; magnitudes beyond its range become infinities.
+    fn to_float(&self) -> NativeFloat;
 }
 
 impl NativeCasts for NativeBigInt {
-    fn to_float(&self) -> Option<NativeFloat> {
-        if TypeId::of::<NativeFloat>() == TypeId::of::<f32>() {
-            self.to_f32().map(|f| f as NativeFloat)
-        } else if TypeId::of::<NativeFloat>() == TypeId::of::<f64>() {
-            self.to_f64().map(|f| f as NativeFloat)
-        } else {
-            panic!("Lacking proper implementation for NativeFloat")
+    fn to_float(&self) -> NativeFloat {
+        cfg_if! {
+            if #[cfg(feature = "f32")] {
+                self.to_f32().unwrap_or(NativeFloat::NAN)
+            } else {
+                self.to_f64().unwrap_or(NativeFloat::NAN)
+            }
         }
     }
 }
 
 impl NativeCasts for NativeRational {
-    fn to_float(&self) -> Option<NativeFloat> {
-        if TypeId::of::<NativeFloat>() == TypeId::of::<f32>() {
-            Some(self.to_f32().unwrap() as NativeFloat)
-        } else if TypeId::of::<NativeFloat>() == TypeId::of::<f64>() {
-            Some(self.to_f64().unwrap() as NativeFloat)
+    fn to_float(&self) -> NativeFloat {
+        cfg_if! {
+            if #[cfg(feature = "f32")] {
+                self.to_f32().unwrap_or(NativeFloat::NAN)
+            } else {
+                self.to_f64().unwrap_or(NativeFloat::NAN)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "bigfloat")]
+impl NativeCasts for NativeBigFloat {
+    fn to_float(&self) -> NativeFloat {
+        big_float::to_rational(self).to_float()
+    }
+}
+
+/// The nearest inexact counterpart of an exact complex number.
+fn to_inexact(c: &NativeExactComplex) -> NativeComplex {
+    NativeComplex::new(c.re.to_float(), c.im.to_float())
+}
+
+macro_rules! impl_from_native {
+    ($($source:ident, $target:ident)*) => {
+    $(
+        impl From<$source> for SNumber {
+            fn from(val: $source) -> Self {
+                SNumber::$target(val)
+            }
+        }
+    )*}
+}
+
+impl_from_native! {
+    NativeInt, Int
+    NativeFloat, Float
+    NativeComplex, Complex
+}
+
+/// Big integers that fit a fixnum are demoted to `Int`.
+impl From<NativeBigInt> for SNumber {
+    fn from(val: NativeBigInt) -> Self {
+        match NativeInt::try_from(&val) {
+            Ok(n) => SNumber::Int(n),
+            Err(_) => SNumber::BigInt(val),
+        }
+    }
+}
+
+/// Rationals with a denominator of one are demoted to integers.
+impl From<NativeRational> for SNumber {
+    fn from(val: NativeRational) -> Self {
+        if val.denom().is_one() {
+            SNumber::from(val.to_integer())
+        } else {
+            SNumber::Rational(val)
+        }
+    }
+}
+
+/// Exact complex numbers with a zero imaginary part are demoted to reals.
+impl From<NativeExactComplex> for SNumber {
+    fn from(val: NativeExactComplex) -> Self {
+        if val.im.is_zero() {
+            SNumber::from(val.re)
         } else {
-            panic!("Lacking proper implementation for NativeFloat")
+            SNumber::ExactComplex(val)
         }
     }
 }
 
+/// A part of an inexact complex number, written so that the reader gives it back.
+fn complex_part(part: NativeFloat) -> String {
+    match part {
+        part if part.is_nan() => tokens::POSITIVE_NAN.to_string(),
+        part if part == NativeFloat::INFINITY => tokens::POSITIVE_INFINITY.to_string(),
+        part if part == NativeFloat::NEG_INFINITY => tokens::NEGATIVE_INFINITY.to_string(),
+        part => format!("{:?}", part),
+    }
+}
+
 impl fmt::Display for SNumber {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -94,18 +174,25 @@ impl fmt::Display for SNumber {
             SNumber::Rational(ref val) => write!(f, "{}", val),
             SNumber::Float(ref val) => write!(f, "{:?}", val),
             SNumber::Complex(ref val) => {
+                let imaginary = complex_part(val.im);
+                let sign_text = if imaginary.starts_with(['+', '-']) { "" } else { "+" };
+                write!(f, "{}{}{}i", complex_part(val.re), sign_text, imaginary)
+            }
+            SNumber::ExactComplex(ref val) => {
                 let mut sign_text = "";
-                if val.im >= 0.0 {
+                if val.im >= NativeRational::zero() {
                     sign_text = "+";
                 }
                 write!(f, "{}{}{}i", val.re, sign_text, val.im)
             }
+            #[cfg(feature = "bigfloat")]
+            SNumber::BigFloat(ref val) => big_float::write(val, f),
         }
     }
 }
 
 macro_rules! impl_ref_op {
-    ($($capital:ident, $small:ident, $op:tt, $limit:expr)*) => {
+    ($($capital:ident, $small:ident, $op:tt, $checked:ident)*) => {
     $(
         impl $capital for &SNumber {
             type Output = SNumber;
@@ -113,40 +200,90 @@ macro_rules! impl_ref_op {
             fn $small(self, other: Self) -> Self::Output {
                 match self {
                     SNumber::Int(l) => match other {
-                        SNumber::Int(r) => if cmp::max((if *l < 0 { l + 1 } else { *l }).abs(), (if *r < 0 { r + 1 } else { *r }).abs()) < $limit { SNumber::Int(l $op r) } else { SNumber::BigInt(NativeBigInt::from(*l) $op NativeBigInt::from(*r)) },
-                        SNumber::BigInt(r) => SNumber::BigInt(l $op r),
-                        SNumber::Rational(r) => SNumber::Rational(NativeRational::from(NativeBigInt::from(*l)) $op r),
+                        // An overflowing result never fits a fixnum
+                        SNumber::Int(r) => match l.$checked(*r) {
+                            Some(n) => SNumber::Int(n),
+                            None => SNumber::BigInt(NativeBigInt::from(*l) $op NativeBigInt::from(*r)),
+                        },
+                        SNumber::BigInt(r) => SNumber::from(l $op r),
+                        SNumber::Rational(r) => SNumber::from(NativeRational::from(NativeBigInt::from(*l)) $op r),
                         SNumber::Float(r) => SNumber::Float(*l as NativeFloat $op r),
                         SNumber::Complex(c) => SNumber::Complex(NativeComplex::from(*l as NativeFloat) $op c),
+                        SNumber::ExactComplex(c) => SNumber::from(NativeExactComplex::from(NativeRational::from(NativeBigInt::from(*l))) $op c),
+                        #[cfg(feature = "bigfloat")]
+                        SNumber::BigFloat(r) => match self.to_big_float(r.precision()) {
+                            Some(l) => SNumber::BigFloat(l $op r),
+                            None => SNumber::Float(self.to_float() $op r.to_float()),
+                        },
                     },
                     SNumber::BigInt(l) => match other {
-                        SNumber::Int(r) => SNumber::BigInt(l.clone() as NativeBigInt $op r),
-                        SNumber::BigInt(r) => SNumber::BigInt(l $op r),
-                        SNumber::Rational(r) => SNumber::Rational(NativeRational::from(l.clone()) $op r),
-                        SNumber::Float(r) => SNumber::Float(l.to_float().unwrap() $op r),
-                        SNumber::Complex(c) => SNumber::Complex(NativeComplex::from(l.to_float().unwrap()) $op c),
+                        SNumber::Int(r) => SNumber::from(l $op NativeBigInt::from(*r)),
+                        SNumber::BigInt(r) => SNumber::from(l $op r),
+                        SNumber::Rational(r) => SNumber::from(NativeRational::from(l.clone()) $op r),
+                        SNumber::Float(r) => SNumber::Float(l.to_float() $op r),
+                        SNumber::Complex(c) => SNumber::Complex(NativeComplex::from(l.to_float()) $op c),
+                        SNumber::ExactComplex(c) => SNumber::from(NativeExactComplex::from(NativeRational::from(l.clone())) $op c),
+                        #[cfg(feature = "bigfloat")]
+                        SNumber::BigFloat(r) => match self.to_big_float(r.precision()) {
+                            Some(l) => SNumber::BigFloat(l $op r),
+                            None => SNumber::Float(self.to_float() $op r.to_float()),
+                        },
                     },
                     SNumber::Rational(l) => match other {
-                        SNumber::Int(r) => SNumber::Rational(l $op NativeBigInt::from(*r)),
-                        SNumber::BigInt(r) => SNumber::Rational(l $op r),
-                        SNumber::Rational(r) => SNumber::Rational(l $op r),
-                        SNumber::Float(r) => SNumber::Float(l.to_float().unwrap() $op r),
-                        SNumber::Complex(c) => SNumber::Complex(NativeComplex::from(l.to_float().unwrap() as NativeFloat) $op c),
+                        SNumber::Int(r) => SNumber::from(l $op NativeBigInt::from(*r)),
+                        SNumber::BigInt(r) => SNumber::from(l $op r),
+                        SNumber::Rational(r) => SNumber::from(l $op r),
+                        SNumber::Float(r) => SNumber::Float(l.to_float() $op r),
+                        SNumber::Complex(c) => SNumber::Complex(NativeComplex::from(l.to_float() as NativeFloat) $op c),
+                        SNumber::ExactComplex(c) => SNumber::from(NativeExactComplex::from(l.clone()) $op c),
+                        #[cfg(feature = "bigfloat")]
+                        SNumber::BigFloat(r) => match self.to_big_float(r.precision()) {
+                            Some(l) => SNumber::BigFloat(l $op r),
+                            None => SNumber::Float(self.to_float() $op r.to_float()),
+                        },
                     },
                     SNumber::Float(l) => match other {
                         SNumber::Int(r) => SNumber::Float(l $op *r as NativeFloat),
-                        SNumber::BigInt(r) => SNumber::Float(l $op r.to_float().unwrap()),
-                        SNumber::Rational(r) => SNumber::Float(l $op r.to_float().unwrap()),
+                        SNumber::BigInt(r) => SNumber::Float(l $op r.to_float()),
+                        SNumber::Rational(r) => SNumber::Float(l $op r.to_float()),
                         SNumber::Float(r) => SNumber::Float(l $op r),
                         SNumber::Complex(c) => SNumber::Complex(NativeComplex::from(*l) $op c),
+                        SNumber::ExactComplex(c) => SNumber::Complex(NativeComplex::from(*l) $op to_inexact(c)),
+                        #[cfg(feature = "bigfloat")]
+                        SNumber::BigFloat(r) => match self.to_big_float(r.precision()) {
+                            Some(l) => SNumber::BigFloat(l $op r),
+                            None => SNumber::Float(self.to_float() $op r.to_float()),
+                        },
                     },
                     SNumber::Complex(l) => match other {
                         SNumber::Int(r) => SNumber::Complex(l $op NativeComplex::new(*r as NativeFloat, 0.0)),
-                        SNumber::BigInt(r) => SNumber::Complex(l $op NativeComplex::new(r.to_float().unwrap(), 0.0)),
-                        SNumber::Rational(r) => SNumber::Complex(l $op NativeComplex::new(r.to_float().unwrap(), 0.0)),
+                        SNumber::BigInt(r) => SNumber::Complex(l $op NativeComplex::new(r.to_float(), 0.0)),
+                        SNumber::Rational(r) => SNumber::Complex(l $op NativeComplex::new(r.to_float(), 0.0)),
                         SNumber::Float(r) => SNumber::Complex(l $op NativeComplex::new(*r, 0.0)),
                         SNumber::Complex(r) => SNumber::Complex(l $op r),
-                    }
+                        SNumber::ExactComplex(r) => SNumber::Complex(l $op to_inexact(r)),
+                        #[cfg(feature = "bigfloat")]
+                        SNumber::BigFloat(r) => SNumber::Complex(l $op NativeComplex::new(r.to_float(), 0.0)),
+                    },
+                    SNumber::ExactComplex(l) => match other {
+                        SNumber::Int(r) => SNumber::from(l $op NativeExactComplex::from(NativeRational::from(NativeBigInt::from(*r)))),
+                        SNumber::BigInt(r) => SNumber::from(l $op NativeExactComplex::from(NativeRational::from(r.clone()))),
+                        SNumber::Rational(r) => SNumber::from(l $op NativeExactComplex::from(r.clone())),
+                        SNumber::Float(r) => SNumber::Complex(to_inexact(l) $op NativeComplex::new(*r, 0.0)),
+                        SNumber::Complex(r) => SNumber::Complex(to_inexact(l) $op r),
+                        SNumber::ExactComplex(r) => SNumber::from(l $op r),
+                        #[cfg(feature = "bigfloat")]
+                        SNumber::BigFloat(r) => SNumber::Complex(to_inexact(l) $op NativeComplex::new(r.to_float(), 0.0)),
+                    },
+                    #[cfg(feature = "bigfloat")]
+                    SNumber::BigFloat(l) => match other {
+                        SNumber::Complex(c) => SNumber::Complex(NativeComplex::from(l.to_float()) $op c),
+                        SNumber::ExactComplex(c) => SNumber::Complex(NativeComplex::from(l.to_float()) $op to_inexact(c)),
+                        real => match real.to_big_float(l.precision()) {
+                            Some(r) => SNumber::BigFloat(l $op r),
+                            None => SNumber::Float(l.to_float() $op real.to_float()),
+                        },
+                    },
                 }
             }
         }
@@ -154,9 +291,9 @@ macro_rules! impl_ref_op {
 }
 
 impl_ref_op! {
-    Add, add, +, NativeInt::MAX / 2
-    Sub, sub, -, NativeInt::MAX / 2
-    Mul, mul, *, (NativeInt::MAX / 2).sqrt()
+    Add, add, +, checked_add
+    Sub, sub, -, checked_sub
+    Mul, mul, *, checked_mul
 }
 
 impl Div for &SNumber {
@@ -165,56 +302,113 @@ impl Div for &SNumber {
     fn div(self, other: Self) -> Self::Output {
         match self {
             SNumber::Int(l) => match other {
-                SNumber::Int(r) => SNumber::Rational(NativeRational::new(
-                    NativeBigInt::from(*l),
-                    NativeBigInt::from(*r),
-                )),
+                SNumber::Int(r) => match (l.checked_rem(*r), l.checked_div(*r)) {
+                    (Some(0), Some(quotient)) => SNumber::Int(quotient),
+                    _ => SNumber::from(NativeRational::new(
+                        NativeBigInt::from(*l),
+                        NativeBigInt::from(*r),
+                    )),
+                },
                 SNumber::BigInt(r) => {
-                    SNumber::Rational(NativeRational::new(NativeBigInt::from(*l), r.clone()))
+                    SNumber::from(NativeRational::new(NativeBigInt::from(*l), r.clone()))
                 }
                 SNumber::Rational(r) => {
-                    SNumber::Rational(NativeRational::from(NativeBigInt::from(*l)) / r)
+                    SNumber::from(NativeRational::from(NativeBigInt::from(*l)) / r)
                 }
                 SNumber::Float(r) => SNumber::Float(*l as NativeFloat / r),
                 SNumber::Complex(c) => SNumber::Complex(NativeComplex::from(*l as NativeFloat) / c),
+                SNumber::ExactComplex(c) => SNumber::from(
+                    NativeExactComplex::from(NativeRational::from(NativeBigInt::from(*l))) / c,
+                ),
+                #[cfg(feature = "bigfloat")]
+                SNumber::BigFloat(r) => match self.to_big_float(r.precision()) {
+                    Some(l) => big_float::divide(&l, r),
+                    None => SNumber::Float(self.to_float() / r.to_float()),
+                },
             },
             SNumber::BigInt(l) => match other {
                 SNumber::Int(r) => {
-                    SNumber::Rational(NativeRational::new(l.clone(), NativeBigInt::from(*r)))
+                    SNumber::from(NativeRational::new(l.clone(), NativeBigInt::from(*r)))
                 }
-                SNumber::BigInt(r) => SNumber::Rational(NativeRational::new(l.clone(), r.clone())),
-                SNumber::Rational(r) => SNumber::Rational(NativeRational::from(l.clone()) / r),
-                SNumber::Float(r) => SNumber::Float(l.to_float().unwrap() / r),
-                SNumber::Complex(c) => {
-                    SNumber::Complex(NativeComplex::from(l.to_float().unwrap()) / c)
+                SNumber::BigInt(r) => SNumber::from(NativeRational::new(l.clone(), r.clone())),
+                SNumber::Rational(r) => SNumber::from(NativeRational::from(l.clone()) / r),
+                SNumber::Float(r) => SNumber::Float(l.to_float() / r),
+                SNumber::Complex(c) => SNumber::Complex(NativeComplex::from(l.to_float()) / c),
+                SNumber::ExactComplex(c) => {
+                    SNumber::from(NativeExactComplex::from(NativeRational::from(l.clone())) / c)
                 }
+                #[cfg(feature = "bigfloat")]
+                SNumber::BigFloat(r) => match self.to_big_float(r.precision()) {
+                    Some(l) => big_float::divide(&l, r),
+                    None => SNumber::Float(self.to_float() / r.to_float()),
+                },
             },
             SNumber::Rational(l) => match other {
-                SNumber::Int(r) => SNumber::Rational(l / NativeBigInt::from(*r)),
-                SNumber::BigInt(r) => SNumber::Rational(l / r),
-                SNumber::Rational(r) => SNumber::Rational(l / r),
-                SNumber::Float(r) => SNumber::Float(l.to_float().unwrap() / r),
+                SNumber::Int(r) => SNumber::from(l / NativeBigInt::from(*r)),
+                SNumber::BigInt(r) => SNumber::from(l / r),
+                SNumber::Rational(r) => SNumber::from(l / r),
+                SNumber::Float(r) => SNumber::Float(l.to_float() / r),
                 SNumber::Complex(c) => {
-                    SNumber::Complex(NativeComplex::from(l.to_float().unwrap() as NativeFloat) / c)
+                    SNumber::Complex(NativeComplex::from(l.to_float() as NativeFloat) / c)
                 }
+                SNumber::ExactComplex(c) => SNumber::from(NativeExactComplex::from(l.clone()) / c),
+                #[cfg(feature = "bigfloat")]
+                SNumber::BigFloat(r) => match self.to_big_float(r.precision()) {
+                    Some(l) => big_float::divide(&l, r),
+                    None => SNumber::Float(self.to_float() / r.to_float()),
+                },
             },
             SNumber::Float(l) => match other {
                 SNumber::Int(r) => SNumber::Float(l / *r as NativeFloat),
-                SNumber::BigInt(r) => SNumber::Float(l / r.to_float().unwrap()),
-                SNumber::Rational(r) => SNumber::Float(l / r.to_float().unwrap()),
+                SNumber::BigInt(r) => SNumber::Float(l / r.to_float()),
+                SNumber::Rational(r) => SNumber::Float(l / r.to_float()),
                 SNumber::Float(r) => SNumber::Float(l / r),
                 SNumber::Complex(c) => SNumber::Complex(NativeComplex::from(*l) / c),
+                SNumber::ExactComplex(c) => {
+                    SNumber::Complex(NativeComplex::from(*l) / to_inexact(c))
+                }
+                #[cfg(feature = "bigfloat")]
+                SNumber::BigFloat(r) => match self.to_big_float(r.precision()) {
+                    Some(l) => big_float::divide(&l, r),
+                    None => SNumber::Float(self.to_float() / r.to_float()),
+                },
             },
             SNumber::Complex(l) => match other {
                 SNumber::Int(r) => SNumber::Complex(l / NativeComplex::new(*r as NativeFloat, 0.0)),
+                SNumber::BigInt(r) => SNumber::Complex(l / NativeComplex::new(r.to_float(), 0.0)),
+                SNumber::Rational(r) => SNumber::Complex(l / NativeComplex::new(r.to_float(), 0.0)),
+                SNumber::Float(r) => SNumber::Complex(l / NativeComplex::new(*r, 0.0)),
+                SNumber::Complex(r) => SNumber::Complex(l / r),
+                SNumber::ExactComplex(r) => SNumber::Complex(l / to_inexact(r)),
+                #[cfg(feature = "bigfloat")]
+                SNumber::BigFloat(r) => SNumber::Complex(l / NativeComplex::new(r.to_float(), 0.0)),
+            },
+            SNumber::ExactComplex(l) => match other {
+                SNumber::Int(r) => SNumber::from(
+                    l / NativeExactComplex::from(NativeRational::from(NativeBigInt::from(*r))),
+                ),
                 SNumber::BigInt(r) => {
-                    SNumber::Complex(l / NativeComplex::new(r.to_float().unwrap(), 0.0))
+                    SNumber::from(l / NativeExactComplex::from(NativeRational::from(r.clone())))
                 }
-                SNumber::Rational(r) => {
-                    SNumber::Complex(l / NativeComplex::new(r.to_float().unwrap(), 0.0))
+                SNumber::Rational(r) => SNumber::from(l / NativeExactComplex::from(r.clone())),
+                SNumber::Float(r) => SNumber::Complex(to_inexact(l) / NativeComplex::new(*r, 0.0)),
+                SNumber::Complex(r) => SNumber::Complex(to_inexact(l) / r),
+                SNumber::ExactComplex(r) => SNumber::from(l / r),
+                #[cfg(feature = "bigfloat")]
+                SNumber::BigFloat(r) => {
+                    SNumber::Complex(to_inexact(l) / NativeComplex::new(r.to_float(), 0.0))
                 }
-                SNumber::Float(r) => SNumber::Complex(l / NativeComplex::new(*r, 0.0)),
-                SNumber::Complex(r) => SNumber::Complex(l / r),
+            },
+            #[cfg(feature = "bigfloat")]
+            SNumber::BigFloat(l) => match other {
+                SNumber::Complex(c) => SNumber::Complex(NativeComplex::from(l.to_float()) / c),
+                SNumber::ExactComplex(c) => {
+                    SNumber::Complex(NativeComplex::from(l.to_float()) / to_inexact(c))
+                }
+                real => match real.to_big_float(l.precision()) {
+                    Some(r) => big_float::divide(l, &r),
+                    None => SNumber::Float(l.to_float() / real.to_float()),
+                },
             },
         }
     }
@@ -231,32 +425,61 @@ macro_rules! impl_partial_eq_ord_op {
                     SNumber::Rational(r) => NativeRational::new(NativeBigInt::from(*l), NativeBigInt::from(1 as NativeInt)) $op *r,
                     SNumber::Float(r) => (*l as NativeFloat) $op *r,
                     SNumber::Complex(_) => false,
+                    SNumber::ExactComplex(_) => false,
+                    #[cfg(feature = "bigfloat")]
+                    SNumber::BigFloat(_) => self.partial_cmp(other).is_some_and(|ordering| ordering $op std::cmp::Ordering::Equal),
                 },
                 SNumber::BigInt(l) => match other {
                     SNumber::Int(r) => *l $op NativeBigInt::from(*r),
                     SNumber::BigInt(r) => l $op r,
                     SNumber::Rational(r) => NativeRational::new(l.clone(), NativeBigInt::from(1 as NativeInt)) $op *r,
-                    SNumber::Float(r) => l.to_float().unwrap() $op *r,
+                    SNumber::Float(r) => l.to_float() $op *r,
                     SNumber::Complex(_) => false,
+                    SNumber::ExactComplex(_) => false,
+                    #[cfg(feature = "bigfloat")]
+                    SNumber::BigFloat(_) => self.partial_cmp(other).is_some_and(|ordering| ordering $op std::cmp::Ordering::Equal),
                 },
                 SNumber::Rational(l) => match other {
                     SNumber::Int(r) => *l $op NativeRational::new(NativeBigInt::from(*r), NativeBigInt::from(1 as NativeInt)),
                     SNumber::BigInt(r) => *l $op NativeRational::new(r.clone(), NativeBigInt::from(1 as NativeInt)),
                     SNumber::Rational(r) => l $op r,
-                    SNumber::Float(r) => l.to_float().unwrap() $op *r,
+                    SNumber::Float(r) => l.to_float() $op *r,
                     SNumber::Complex(_) => false,
+                    SNumber::ExactComplex(_) => false,
+                    #[cfg(feature = "bigfloat")]
+                    SNumber::BigFloat(_) => self.partial_cmp(other).is_some_and(|ordering| ordering $op std::cmp::Ordering::Equal),
                 },
                 SNumber::Float(l) => match other {
                     SNumber::Int(r) => *l $op *r as NativeFloat,
-                    SNumber::BigInt(r) => *l $op r.to_float().unwrap(),
-                    SNumber::Rational(r) => *l $op r.to_float().unwrap(),
+                    SNumber::BigInt(r) => *l $op r.to_float(),
+                    SNumber::Rational(r) => *l $op r.to_float(),
                     SNumber::Float(r) => l $op r,
                     SNumber::Complex(_) => false,
+                    SNumber::ExactComplex(_) => false,
+                    #[cfg(feature = "bigfloat")]
+                    SNumber::BigFloat(_) => self.partial_cmp(other).is_some_and(|ordering| ordering $op std::cmp::Ordering::Equal),
                 },
                 SNumber::Complex(l) => match other {
                     SNumber::Complex(r) => l.re $op r.re && l.im $op r.im,
+                    SNumber::ExactComplex(r) => {
+                        let r = to_inexact(r);
+                        l.re $op r.re && l.im $op r.im
+                    }
                     _ => false,
-                }
+                },
+                SNumber::ExactComplex(l) => match other {
+                    SNumber::Complex(r) => {
+                        let l = to_inexact(l);
+                        l.re $op r.re && l.im $op r.im
+                    }
+                    SNumber::ExactComplex(r) => l.re $op r.re && l.im $op r.im,
+                    _ => false,
+                },
+                #[cfg(feature = "bigfloat")]
+                SNumber::BigFloat(_) => match other {
+                    SNumber::Complex(_) | SNumber::ExactComplex(_) => false,
+                    _ => self.partial_cmp(other).is_some_and(|ordering| ordering $op std::cmp::Ordering::Equal),
+                },
             }
         }
     )*}
@@ -278,63 +501,14 @@ impl PartialOrd for SNumber {
     }
 
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        match self {
-            SNumber::Int(l) => match other {
-                SNumber::Int(r) => Some(l.cmp(r)),
-                SNumber::BigInt(r) => Some(NativeBigInt::from(*l).cmp(r)),
-                SNumber::Rational(r) => Some(
-                    NativeRational::new(NativeBigInt::from(*l), NativeBigInt::from(1 as NativeInt))
-                        .cmp(r),
-                ),
-                SNumber::Float(r) => Some(
-                    NativeRational::new(NativeBigInt::from(*l), NativeBigInt::from(1 as NativeInt))
-                        .cmp(&NativeRational::from_float(*r).unwrap()),
-                ),
-                SNumber::Complex(_) => None,
+        match (self.to_exact(), other.to_exact()) {
+            (Some(l), Some(r)) => Some(l.cmp(&r)),
+            // Infinities and NaN have no exact counterpart
+            _ => match (self, other) {
+                (SNumber::Complex(_) | SNumber::ExactComplex(_), _)
+                | (_, SNumber::Complex(_) | SNumber::ExactComplex(_)) => None,
+                _ => self.to_float().partial_cmp(&other.to_float()),
             },
-            SNumber::BigInt(l) => match other {
-                SNumber::Int(r) => Some((*l).cmp(&NativeBigInt::from(*r))),
-                SNumber::BigInt(r) => Some(l.cmp(r)),
-                SNumber::Rational(r) => {
-                    Some(NativeRational::new(l.clone(), NativeBigInt::from(1 as NativeInt)).cmp(r))
-                }
-                SNumber::Float(r) => Some(
-                    NativeRational::new(l.clone(), NativeBigInt::from(1 as NativeInt))
-                        .cmp(&NativeRational::from_float(*r).unwrap()),
-                ),
-                SNumber::Complex(_) => None,
-            },
-            SNumber::Rational(l) => match other {
-                SNumber::Int(r) => Some(l.cmp(&NativeRational::new(
-                    NativeBigInt::from(*r),
-                    NativeBigInt::from(1 as NativeInt),
-                ))),
-                SNumber::BigInt(r) => Some(
-                    (*l).cmp(&NativeRational::new(r.clone(), NativeBigInt::from(1 as NativeInt))),
-                ),
-                SNumber::Rational(r) => Some(l.cmp(r)),
-                SNumber::Float(r) => Some(l.cmp(&NativeRational::from_float(*r).unwrap())),
-                SNumber::Complex(_) => None,
-            },
-            SNumber::Float(l) => match other {
-                SNumber::Int(r) => {
-                    Some((NativeRational::from_float(*l).unwrap()).cmp(&NativeRational::new(
-                        NativeBigInt::from(*r),
-                        NativeBigInt::from(1 as NativeInt),
-                    )))
-                }
-                SNumber::BigInt(r) => Some(
-                    (NativeRational::from_float(*l).unwrap())
-                        .cmp(&NativeRational::new(r.clone(), NativeBigInt::from(1 as NativeInt))),
-                ),
-                SNumber::Rational(r) => Some((&NativeRational::from_float(*l).unwrap()).cmp(r)),
-                SNumber::Float(r) => Some(
-                    (NativeRational::from_float(*l).unwrap())
-                        .cmp(&NativeRational::from_float(*r).unwrap()),
-                ),
-                SNumber::Complex(_) => None,
-            },
-            SNumber::Complex(_) => None,
         }
     }
 }
@@ -377,6 +551,32 @@ impl_op_assign! {
     div_assign, DivAssign, div
 }
 
+/// Elementary functions of real arguments in their domain, computed on big floats when a float
+/// precision applies, that of a big float argument or else `precision`, and on native floats
+/// otherwise. Other arguments give complex results.
+macro_rules! impl_elementary_fn {
+    ($($vis:vis $fn:ident: $native:ident, $in_domain:expr;)*) => {
+    $(
+        #[cfg_attr(not(feature = "bigfloat"), allow(unused_variables))]
+        $vis fn $fn(&self, precision: Option<usize>) -> SNumber {
+            let in_domain: fn(&SNumber) -> bool = $in_domain;
+            // Complex numbers have native parts, whatever the precision of big floats
+            if !self.is_real() || !in_domain(self) {
+                return SNumber::Complex(self.to_complex().$native());
+            }
+
+            #[cfg(feature = "bigfloat")]
+            if let Some(x) = big_float::precision_of(&[self], precision)
+                .and_then(|precision| self.to_big_float(precision))
+            {
+                return SNumber::BigFloat(big_float::$native(&x));
+            }
+
+            SNumber::Float(self.to_float().$native())
+        }
+    )*}
+}
+
 impl SNumber {
     pub fn is_exact(&self) -> bool {
         match self {
@@ -384,16 +584,88 @@ impl SNumber {
             SNumber::BigInt(_) => true,
             SNumber::Rational(_) => true,
             SNumber::Float(_) => false,
-            SNumber::Complex(c) => {
-                SNumber::Float(c.re).is_exact() && SNumber::Float(c.im).is_exact()
+            SNumber::Complex(_) => false,
+            SNumber::ExactComplex(_) => true,
+            #[cfg(feature = "bigfloat")]
+            SNumber::BigFloat(_) => false,
+        }
+    }
+
+    /// The exact number nearest to this one, `None` for infinities and NaN.
+    pub fn exact(&self) -> Option<SNumber> {
+        match self {
+            SNumber::Float(f) => NativeRational::from_float(*f).map(SNumber::from),
+            SNumber::Complex(c) => Some(SNumber::from(NativeExactComplex::new(
+                NativeRational::from_float(c.re)?,
+                NativeRational::from_float(c.im)?,
+            ))),
+            #[cfg(feature = "bigfloat")]
+            SNumber::BigFloat(x) => Some(SNumber::from(big_float::to_rational(x))),
+            exact => Some(exact.clone()),
+        }
+    }
+
+    /// The inexact number nearest to this one, a big float of `precision` significant digits
+    /// for exact reals if one is given.
+    #[cfg_attr(not(feature = "bigfloat"), allow(unused_variables))]
+    pub fn inexact(&self, precision: Option<usize>) -> SNumber {
+        match self {
+            SNumber::ExactComplex(c) => SNumber::Complex(to_inexact(c)),
+            SNumber::Complex(c) => SNumber::Complex(*c),
+            #[cfg(feature = "bigfloat")]
+            SNumber::BigFloat(x) => SNumber::BigFloat(x.clone()),
+            #[cfg(feature = "bigfloat")]
+            exact if exact.is_exact() => {
+                match precision.and_then(|precision| exact.to_big_float(precision)) {
+                    Some(x) => SNumber::BigFloat(x),
+                    None => SNumber::Float(exact.to_float()),
+                }
             }
+            real => SNumber::Float(real.to_float()),
+        }
+    }
+
+    /// The exact value of a real number, `None` for complex numbers, infinities and NaN.
+    fn to_exact(&self) -> Option<NativeRational> {
+        match self {
+            SNumber::Int(n) => Some(NativeRational::from(NativeBigInt::from(*n))),
+            SNumber::BigInt(n) => Some(NativeRational::from(n.clone())),
+            SNumber::Rational(q) => Some(q.clone()),
+            SNumber::Float(f) => NativeRational::from_float(*f),
+            SNumber::Complex(_) | SNumber::ExactComplex(_) => None,
+            #[cfg(feature = "bigfloat")]
+            SNumber::BigFloat(x) => Some(big_float::to_rational(x)),
+        }
+    }
+
+    #[cfg(feature = "bigfloat")]
+    fn to_big_float(&self, precision: usize) -> Option<NativeBigFloat> {
+        match self {
+            SNumber::BigFloat(x) => Some(x.clone()),
+            number => number.to_exact().map(|q| big_float::from_rational(&q, precision)),
         }
     }
 
-    pub fn to_int(&self) -> Result<NativeInt, String> {
+    fn to_float(&self) -> NativeFloat {
+        match self {
+            SNumber::Int(n) => *n as NativeFloat,
+            SNumber::BigInt(n) => n.to_float(),
+            SNumber::Rational(q) => q.to_float(),
+            SNumber::Float(f) => *f,
+            SNumber::Complex(c) => c.re,
+            SNumber::ExactComplex(c) => c.re.to_float(),
+            #[cfg(feature = "bigfloat")]
+            SNumber::BigFloat(x) => x.to_float(),
+        }
+    }
+
+    pub fn to_int(&self) -> Result<NativeInt, SchemeError> {
         match self {
             SNumber::Int(internal) => Ok(*internal),
-            other => Err(format!("Exception: {} is not a proper integer", other)),
+            other => Err(SchemeError::new(
+                ErrorKind::WrongType,
+                format!("{} is not a proper integer", other),
+            )),
         }
     }
 
@@ -410,6 +682,9 @@ impl SNumber {
                     false
                 }
             }
+            SNumber::ExactComplex(c) => c.im.is_zero() && c.re.denom().is_one(),
+            #[cfg(feature = "bigfloat")]
+            SNumber::BigFloat(x) => big_float::to_rational(x).denom().is_one(),
         }
     }
 
@@ -426,6 +701,9 @@ impl SNumber {
                     false
                 }
             }
+            SNumber::ExactComplex(c) => c.im.is_zero(),
+            #[cfg(feature = "bigfloat")]
+            SNumber::BigFloat(_) => true,
         }
     }
 
@@ -440,6 +718,9 @@ impl SNumber {
                 _ => true,
             },
             SNumber::Complex(_) => false,
+            SNumber::ExactComplex(c) => c.im.is_zero(),
+            #[cfg(feature = "bigfloat")]
+            SNumber::BigFloat(_) => true,
         }
     }
 
@@ -450,6 +731,9 @@ impl SNumber {
             SNumber::Rational(_) => true,
             SNumber::Float(_) => true,
             SNumber::Complex(_) => true,
+            SNumber::ExactComplex(_) => true,
+            #[cfg(feature = "bigfloat")]
+            SNumber::BigFloat(_) => true,
         }
     }
 
@@ -461,6 +745,9 @@ impl SNumber {
             SNumber::Rational(number) => number.is_zero(),
             SNumber::Float(number) => *number == 0.0,
             SNumber::Complex(number) => number == &Complex::zero(),
+            SNumber::ExactComplex(number) => number.is_zero(),
+            #[cfg(feature = "bigfloat")]
+            SNumber::BigFloat(number) => number.repr().is_zero(),
         }
     }
 
@@ -477,14 +764,83 @@ impl SNumber {
             _ => false,
         }
     }
+
+    fn to_complex(&self) -> NativeComplex {
+        match self {
+            SNumber::Complex(c) => *c,
+            SNumber::ExactComplex(c) => to_inexact(c),
+            real => NativeComplex::from(real.to_float()),
+        }
+    }
+
+    impl_elementary_fn! {
+        pub exp: exp, |_| true;
+        ln: ln, |x| x.is_nan() || x >= &SNumber::Int(0);
+        pub sin: sin, |_| true;
+        pub cos: cos, |_| true;
+        pub tan: tan, |_| true;
+        pub asin: asin, |x| !(x < &SNumber::Int(-1) || x > &SNumber::Int(1));
+        pub acos: acos, |x| !(x < &SNumber::Int(-1) || x > &SNumber::Int(1));
+        pub atan: atan, |_| true;
+        inexact_sqrt: sqrt, |x| x.is_nan() || x >= &SNumber::Int(0);
+    }
+
+    /// The natural logarithm, negative infinity at zero.
+    pub fn log(&self, precision: Option<usize>) -> SNumber {
+        match self.is_real() && self.is_zero() {
+            true => SNumber::Float(NativeFloat::NEG_INFINITY),
+            false => self.ln(precision),
+        }
+    }
+
+    /// The angle of the point (`x`, `self`) for real numbers.
+    #[cfg_attr(not(feature = "bigfloat"), allow(unused_variables))]
+    pub fn atan2(&self, x: &SNumber, precision: Option<usize>) -> SNumber {
+        #[cfg(feature = "bigfloat")]
+        if let Some(precision) = big_float::precision_of(&[self, x], precision) {
+            if let (Some(y), Some(x)) = (self.to_big_float(precision), x.to_big_float(precision)) {
+                return SNumber::BigFloat(big_float::atan2(&y, &x));
+            }
+        }
+
+        SNumber::Float(self.to_float().atan2(x.to_float()))
+    }
+
+    /// The principal square root, exact for exact squares.
+    pub fn sqrt(&self, precision: Option<usize>) -> SNumber {
+        self.exact_sqrt().unwrap_or_else(|| self.inexact_sqrt(precision))
+    }
+
+    fn exact_sqrt(&self) -> Option<SNumber> {
+        let q = match self {
+            SNumber::Int(_) | SNumber::BigInt(_) | SNumber::Rational(_) => self.to_exact()?,
+            _ => return None,
+        };
+        let root = |n: NativeBigInt| {
+            let root = n.sqrt();
+            (&root * &root == n).then_some(root)
+        };
+
+        let magnitude = NativeRational::new(
+            root(NativeBigInt::from(q.numer().magnitude().clone()))?,
+            root(q.denom().clone())?,
+        );
+        match q.numer().sign() {
+            num::bigint::Sign::Minus => {
+                Some(SNumber::from(NativeExactComplex::new(NativeRational::zero(), magnitude)))
+            }
+            _ => Some(SNumber::from(magnitude)),
+        }
+    }
 }
 
 #[cfg(test)]
 pub mod tests {
-    use num::bigint::ToBigInt;
+    use num::{bigint::ToBigInt, One};
+    use proptest::prelude::*;
 
     use crate::core::s_expression::s_number::{
-        NativeBigInt, NativeComplex, NativeFloat, NativeInt, NativeRational,
+        NativeBigInt, NativeComplex, NativeExactComplex, NativeFloat, NativeInt, NativeRational,
     };
 
     use super::SNumber;
@@ -955,6 +1311,30 @@ pub mod tests {
             rhs: SNumber::Complex(NativeComplex::new(2.0, 1.0)),
             expected: true
         };
+        snumber_op_int_exact_complex_mul: {
+            operator: *,
+            lhs: SNumber::Int(2),
+            rhs: SNumber::ExactComplex("3+4i".parse::<NativeExactComplex>().unwrap()),
+            expected: SNumber::ExactComplex("6+8i".parse::<NativeExactComplex>().unwrap())
+        };
+        snumber_op_exact_complex_rational_div: {
+            operator: /,
+            lhs: SNumber::ExactComplex("1+2i".parse::<NativeExactComplex>().unwrap()),
+            rhs: SNumber::Rational("1/2".parse::<NativeRational>().unwrap()),
+            expected: SNumber::ExactComplex("2+4i".parse::<NativeExactComplex>().unwrap())
+        };
+        snumber_op_exact_complex_float_add: {
+            operator: +,
+            lhs: SNumber::ExactComplex("1/2+2i".parse::<NativeExactComplex>().unwrap()),
+            rhs: SNumber::Float(0.5),
+            expected: SNumber::Complex(NativeComplex::new(1.0, 2.0))
+        };
+        snumber_op_exact_complex_complex_eq: {
+            operator: ==,
+            lhs: SNumber::ExactComplex("1/2+2i".parse::<NativeExactComplex>().unwrap()),
+            rhs: SNumber::Complex(NativeComplex::new(0.5, 2.0)),
+            expected: true
+        };
         snumber_op_int_complex_gt: { // TODO: Handle differently
             operator: >,
             lhs: SNumber::Complex(1.0.into()),
@@ -993,4 +1373,94 @@ pub mod tests {
 
         assert_eq!(res, SNumber::BigInt(NativeBigInt::from(max) * NativeBigInt::from(max)))
     }
+
+    #[test]
+    fn snumber_demotion_exact_complex() {
+        let lhs = SNumber::ExactComplex("1+2i".parse::<NativeExactComplex>().unwrap());
+        let rhs = SNumber::ExactComplex("1-2i".parse::<NativeExactComplex>().unwrap());
+
+        assert!(matches!(lhs * rhs, SNumber::Int(5)))
+    }
+
+    #[test]
+    fn snumber_promotion_int_div() {
+        let min = NativeInt::MIN;
+        let res = SNumber::Int(min) / SNumber::Int(-1);
+
+        assert!(matches!(res, SNumber::BigInt(n) if n == -NativeBigInt::from(min)))
+    }
+
+    #[test]
+    fn snumber_promotion_mixed_sign() {
+        let res = SNumber::Int(NativeInt::MIN) + SNumber::Int(NativeInt::MAX);
+        assert!(matches!(res, SNumber::Int(-1)));
+
+        let res = SNumber::Int(NativeInt::MIN) * SNumber::Int(1);
+        assert!(matches!(res, SNumber::Int(NativeInt::MIN)));
+
+        let res = SNumber::Int(NativeInt::MIN) - SNumber::Int(1);
+        assert!(matches!(res, SNumber::BigInt(_)));
+    }
+
+    /// Exact results are kept in the narrowest representation holding their value.
+    fn is_normalized(number: &SNumber) -> bool {
+        match number {
+            SNumber::BigInt(n) => NativeInt::try_from(n).is_err(),
+            SNumber::Rational(q) => !q.denom().is_one(),
+            _ => true,
+        }
+    }
+
+    fn big(n: NativeInt) -> NativeBigInt {
+        NativeBigInt::from(n)
+    }
+
+    proptest! {
+        #[test]
+        fn snumber_checked_add(l: NativeInt, r: NativeInt) {
+            let res = SNumber::Int(l) + SNumber::Int(r);
+
+            prop_assert_eq!(res.to_exact(), Some(NativeRational::from(big(l) + big(r))));
+            prop_assert!(is_normalized(&res));
+        }
+
+        #[test]
+        fn snumber_checked_sub(l: NativeInt, r: NativeInt) {
+            let res = SNumber::Int(l) - SNumber::Int(r);
+
+            prop_assert_eq!(res.to_exact(), Some(NativeRational::from(big(l) - big(r))));
+            prop_assert!(is_normalized(&res));
+        }
+
+        #[test]
+        fn snumber_checked_mul(l: NativeInt, r: NativeInt) {
+            let res = SNumber::Int(l) * SNumber::Int(r);
+
+            prop_assert_eq!(res.to_exact(), Some(NativeRational::from(big(l) * big(r))));
+            prop_assert!(is_normalized(&res));
+        }
+
+        #[test]
+        fn snumber_checked_div(l: NativeInt, r in any::<NativeInt>().prop_filter("nonzero", |r| *r != 0)) {
+            let res = SNumber::Int(l) / SNumber::Int(r);
+
+            prop_assert_eq!(res.to_exact(), Some(NativeRational::new(big(l), big(r))));
+            prop_assert!(is_normalized(&res));
+        }
+
+        #[test]
+        fn snumber_demotion_bigint(l: NativeInt, r: NativeInt) {
+            let promoted = SNumber::Int(l) * SNumber::Int(NativeInt::MAX) + SNumber::Int(r);
+            let res = (promoted - SNumber::Int(r)) / SNumber::Int(NativeInt::MAX);
+
+            prop_assert!(matches!(res, SNumber::Int(n) if n == l));
+        }
+
+        #[test]
+        fn snumber_demotion_rational(l: NativeInt, r in any::<NativeInt>().prop_filter("nonzero", |r| *r != 0)) {
+            let res = SNumber::from(NativeRational::new(big(l), big(r))) * SNumber::Int(r);
+
+            prop_assert!(matches!(res, SNumber::Int(n) if n == l));
+        }
+    }
 }
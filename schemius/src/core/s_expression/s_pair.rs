@@ -0,0 +1,142 @@
+use std::sync::Arc;
+
+use crate::core::{accessor::Accessor, error::SchemeError, reader::Span};
+
+use super::{ListImplementation, SExpr, SchemePair};
+
+/// A mutable pair. Lists are chains of pairs ending with `SExpr::Null`, which share their tails
+/// and see mutations made through any alias.
+#[derive(Clone, Debug)]
+pub struct Cons {
+    pub car: SExpr,
+    pub cdr: SExpr,
+    /// Where the list starting with this pair was read from, if it was read.
+    pub span: Option<Arc<Span>>,
+}
+
+/// Iterates over the elements of a chain of pairs, stopping at the first cdr that is not a pair
+/// or that is being changed.
+pub struct Pairs {
+    next: SExpr,
+}
+
+impl Pairs {
+    /// What follows the elements iterated so far, which is `SExpr::Null` at the end of a list.
+    pub fn tail(&self) -> &SExpr {
+        &self.next
+    }
+}
+
+impl Iterator for Pairs {
+    type Item = SExpr;
+
+    fn next(&mut self) -> Option<SExpr> {
+        let (car, cdr) = match self.next {
+            SExpr::Pair(ref pair) => {
+                let cons = pair.access().ok()?;
+                (cons.car.clone(), cons.cdr.clone())
+            }
+            _ => return None,
+        };
+
+        self.next = cdr;
+        Some(car)
+    }
+}
+
+impl SExpr {
+    pub fn cons(car: SExpr, cdr: SExpr) -> SExpr {
+        SExpr::Pair(SchemePair::new(Cons { car, cdr, span: None }))
+    }
+
+    /// Where the list starting here was read from, if it was read.
+    pub fn span(&self) -> Option<Arc<Span>> {
+        match self {
+            SExpr::Pair(pair) => pair.access().ok()?.span.clone(),
+            _ => None,
+        }
+    }
+
+    /// Records that the list starting here was read from `span`, which is kept by the pair.
+    pub fn with_span(self, span: Option<Arc<Span>>) -> SExpr {
+        if let (SExpr::Pair(ref pair), Some(span)) = (&self, span) {
+            if let Ok(mut cons) = pair.access_mut() {
+                cons.span = Some(span);
+            }
+        }
+        self
+    }
+
+    /// A list of `items` ending with `tail` rather than with the empty list.
+    pub fn improper_list(items: impl IntoIterator<Item = SExpr>, tail: SExpr) -> SExpr {
+        let items: Vec<SExpr> = items.into_iter().collect();
+        items.into_iter().rev().fold(tail, |cdr, car| SExpr::cons(car, cdr))
+    }
+
+    pub fn list(items: impl IntoIterator<Item = SExpr>) -> SExpr {
+        SExpr::improper_list(items, SExpr::Null)
+    }
+
+    pub fn car(&self) -> Option<SExpr> {
+        match self {
+            SExpr::Pair(pair) => Some(pair.access().ok()?.car.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn cdr(&self) -> Option<SExpr> {
+        match self {
+            SExpr::Pair(pair) => Some(pair.access().ok()?.cdr.clone()),
+            _ => None,
+        }
+    }
+
+    /// The elements of the chain of pairs starting here, which never end if the chain is circular.
+    pub fn pairs(&self) -> Pairs {
+        Pairs { next: self.clone() }
+    }
+
+    /// The number of elements of a proper list, or `None` for improper and circular lists.
+    pub fn list_length(&self) -> Option<usize> {
+        let mut length = 0;
+        let mut fast = self.clone();
+        let mut slow = self.clone();
+
+        loop {
+            match fast {
+                SExpr::Null => return Some(length),
+                SExpr::Pair(ref pair) => {
+                    let cdr = pair.access().ok()?.cdr.clone();
+                    fast = cdr;
+                    length += 1;
+                }
+                _ => return None,
+            }
+
+            if length % 2 == 0 {
+                slow = slow.cdr().unwrap_or(SExpr::Null);
+                if let (SExpr::Pair(fast), SExpr::Pair(slow)) = (&fast, &slow) {
+                    if fast.ptr_eq(slow) {
+                        return None;
+                    }
+                }
+            }
+        }
+    }
+
+    /// The elements of a proper list.
+    pub fn as_list(&self) -> Result<ListImplementation, SchemeError> {
+        match self.list_length() {
+            Some(_) => Ok(self.pairs().collect()),
+            None => Err(self.type_error("a list")),
+        }
+    }
+
+    /// Whether both values are the same pair, as told by `eq?` and `eqv?`.
+    pub fn is_same_pair(&self, other: &SExpr) -> bool {
+        match (self, other) {
+            (SExpr::Pair(a), SExpr::Pair(b)) => a.ptr_eq(b),
+            _ => false,
+        }
+    }
+}
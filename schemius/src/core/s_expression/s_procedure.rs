@@ -1,18 +1,71 @@
-use crate::core::environment::{EnvAccessor, Environment};
+use std::sync::OnceLock;
 
-use super::{ListImplementation, SExpr};
+use crate::core::{
+    accessor::Shared,
+    compiler::Code,
+    environment::{EnvAccessor, Environment},
+    error::SchemeError,
+    heap,
+};
+
+#[cfg(feature = "jit")]
+use crate::core::jit::JitState;
+
+use super::{ListImplementation, SExpr, Symbol};
 
 pub type ProcedureArgs = ListImplementation;
 pub type ProcedureEnv = EnvAccessor<Environment>;
-pub type ProcedureOutput = Result<SExpr, String>;
+pub type ProcedureOutput = Result<SExpr, SchemeError>;
 pub type ProcedureSignature = fn(ProcedureArgs, ProcedureEnv) -> ProcedureOutput;
 
-pub type SpecialFormOutput = Result<SExpr, String>;
+pub type SpecialFormOutput = Result<SExpr, SchemeError>;
 pub type SpecialFormSignature = fn(ProcedureArgs, ProcedureEnv) -> SpecialFormOutput;
 
 #[derive(Clone, Debug)]
 pub enum Procedure {
-    SpecialForm(SpecialFormSignature),
     Primitive(ProcedureSignature),
-    Compound(Vec<String>, ListImplementation, ProcedureEnv),
+    Compound(Shared<Lambda>, ProcedureEnv),
+}
+
+/// The parameters and body of a compound procedure, shared by every closure created from the
+/// same `lambda` expression.
+#[derive(Debug)]
+pub struct Lambda {
+    pub params: Vec<Symbol>,
+    pub body: ListImplementation,
+    /// The body compiled to bytecode. Procedures not created by compiled code have their body
+    /// compiled when first called.
+    pub code: OnceLock<Code>,
+    /// How often the lambda was called and the native code it was compiled to, if any.
+    #[cfg(feature = "jit")]
+    pub jit: JitState,
+}
+
+impl Lambda {
+    pub fn new(params: Vec<Symbol>, body: ListImplementation) -> Shared<Self> {
+        Self::allocate(Self {
+            params,
+            body,
+            code: OnceLock::new(),
+            #[cfg(feature = "jit")]
+            jit: JitState::default(),
+        })
+    }
+
+    /// A lambda whose body was already compiled to `code`.
+    pub fn compiled(params: Vec<Symbol>, body: ListImplementation, code: Code) -> Shared<Self> {
+        Self::allocate(Self {
+            params,
+            body,
+            code: OnceLock::from(code),
+            #[cfg(feature = "jit")]
+            jit: JitState::default(),
+        })
+    }
+
+    fn allocate(lambda: Self) -> Shared<Self> {
+        let lambda = Shared::new(lambda);
+        heap::track(Shared::downgrade(&lambda) as _);
+        lambda
+    }
 }
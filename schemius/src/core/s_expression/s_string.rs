@@ -0,0 +1,48 @@
+use std::{
+    fmt,
+    ops::{Deref, DerefMut},
+};
+
+/// The characters of a string, along with whether they may be changed. Strings given by
+/// `symbol->string` are immutable, so that the name of a symbol reads the same everywhere.
+#[derive(Clone, Debug, Default)]
+pub struct SString {
+    value: String,
+    mutable: bool,
+}
+
+impl SString {
+    pub fn immutable(value: impl Into<String>) -> Self {
+        Self { value: value.into(), mutable: false }
+    }
+
+    pub fn is_mutable(&self) -> bool {
+        self.mutable
+    }
+}
+
+impl From<String> for SString {
+    fn from(value: String) -> Self {
+        Self { value, mutable: true }
+    }
+}
+
+impl Deref for SString {
+    type Target = String;
+
+    fn deref(&self) -> &String {
+        &self.value
+    }
+}
+
+impl DerefMut for SString {
+    fn deref_mut(&mut self) -> &mut String {
+        &mut self.value
+    }
+}
+
+impl fmt::Display for SString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.value)
+    }
+}
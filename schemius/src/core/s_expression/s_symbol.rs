@@ -0,0 +1,178 @@
+use std::{
+    collections::HashSet,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Deref,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, LazyLock, RwLock,
+    },
+};
+
+/// The names of every interned symbol. Each name is allocated once and shared by all the
+/// symbols spelled that way, so the address of the name identifies the symbol. Symbols already
+/// interned are looked up under a shared lock.
+static SYMBOLS: LazyLock<RwLock<Interned>> = LazyLock::new(Default::default);
+
+/// How many names the table holds before it is first swept.
+const MIN_SWEPT: usize = 1024;
+
+#[derive(Default)]
+struct Interned {
+    names: HashSet<Arc<str>>,
+    /// How many names were left by the last sweep. Names only held by the table are dropped
+    /// once it has grown to twice as many, so that it does not keep every name ever interned.
+    swept: usize,
+}
+
+/// Counts the symbols made by `gensym`, so that their names differ.
+static GENERATED: AtomicUsize = AtomicUsize::new(0);
+
+/// A symbol, compared by identity rather than by name. Symbols read or made with
+/// `string->symbol` are interned, so two of them are the same symbol exactly when they are
+/// spelled the same, while uninterned symbols are only equal to themselves.
+#[derive(Clone)]
+pub struct Symbol(Arc<str>);
+
+impl Symbol {
+    /// The interned symbol named `name`.
+    pub fn new(name: &str) -> Self {
+        if let Some(interned) = SYMBOLS.read().unwrap().names.get(name) {
+            return Symbol(interned.clone());
+        }
+
+        let mut symbols = SYMBOLS.write().unwrap();
+        if let Some(interned) = symbols.names.get(name) {
+            return Symbol(interned.clone());
+        }
+
+        // Symbols are only cloned from one another or under the lock, so a name no symbol
+        // holds cannot be taken up again while the table is swept
+        if symbols.names.len() >= 2 * symbols.swept.max(MIN_SWEPT) {
+            symbols.names.retain(|name| Arc::strong_count(name) > 1);
+            symbols.swept = symbols.names.len();
+        }
+
+        let interned: Arc<str> = Arc::from(name);
+        symbols.names.insert(interned.clone());
+        Symbol(interned)
+    }
+
+    /// A symbol named `name` that differs from every other symbol, interned or not.
+    pub fn uninterned(name: &str) -> Self {
+        Symbol(Arc::from(name))
+    }
+
+    /// An uninterned symbol with a name not yet given by this function, made of `prefix` and a
+    /// counter.
+    pub fn generate(prefix: &str) -> Self {
+        let count = GENERATED.fetch_add(1, Ordering::Relaxed) + 1;
+        Symbol::uninterned(&format!("{}{}", prefix, count))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_interned(&self) -> bool {
+        let symbols = SYMBOLS.read().unwrap();
+        symbols.names.get(self.as_str()).is_some_and(|name| Arc::ptr_eq(name, &self.0))
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Symbol {}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.0).cast::<u8>().hash(state)
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Self {
+        Symbol::new(name)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symbol_interned() {
+        let a = Symbol::new("interned");
+        let b = Symbol::from("interned");
+
+        assert_eq!(a, b);
+        assert!(Arc::ptr_eq(&a.0, &b.0));
+        assert!(a.is_interned());
+        assert_ne!(a, Symbol::new("other"));
+    }
+
+    #[test]
+    fn symbol_uninterned() {
+        let interned = Symbol::new("uninterned");
+        let a = Symbol::uninterned("uninterned");
+        let b = Symbol::uninterned("uninterned");
+
+        assert_eq!(a, a.clone());
+        assert_ne!(a, b);
+        assert_ne!(a, interned);
+        assert!(a == "uninterned");
+        assert!(!a.is_interned());
+
+        let generated = Symbol::generate("g");
+        assert_ne!(generated, Symbol::new(generated.as_str()));
+        assert_ne!(generated.as_str(), Symbol::generate("g").as_str());
+    }
+
+    #[test]
+    fn symbol_table_sweep() {
+        let kept = Symbol::new("kept");
+        for count in 0..10 * MIN_SWEPT {
+            Symbol::new(&format!("dropped-{}", count));
+        }
+
+        assert!(SYMBOLS.read().unwrap().names.len() < 10 * MIN_SWEPT);
+        assert_eq!(kept, Symbol::new("kept"));
+        assert!(kept.is_interned());
+    }
+}
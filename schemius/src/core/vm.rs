@@ -0,0 +1,358 @@
+use std::ptr::fn_addr_eq;
+
+#[cfg(feature = "jit")]
+use super::jit;
+use super::{
+    accessor::Accessor,
+    builtins::{apply_arguments, Primitive},
+    compiler::{compile, compile_lambda, Code, Instruction},
+    environment::{Environment, SchemeEnvironment},
+    error::{Frame, SchemeError},
+    evaluator::{check_arity, is_true, recursion_limit, variable_value, EvalOutput, Settings},
+    s_expression::*,
+};
+
+/// Compiles `expression` and runs it in `env`.
+pub fn eval(expression: &SExpr, env: ProcedureEnv, settings: Settings) -> EvalOutput {
+    let code = compile(expression, &env);
+    Vm::new(code, env, settings).run()
+}
+
+/// Calls `procedure` with arguments that are already evaluated.
+pub fn apply(
+    procedure: &SExpr, args: ListImplementation, env: ProcedureEnv, settings: Settings,
+) -> EvalOutput {
+    let mut vm = Vm::new(Code::default(), env, settings);
+
+    match vm.call(procedure.clone(), args, None, "apply", true) {
+        Ok(Some(value)) => Ok(value),
+        Ok(None) => vm.run(),
+        Err(e) => Err(vm.unwind(e)),
+    }
+}
+
+/// The code being run by a call along with where it stands.
+struct CallFrame {
+    code: Code,
+    pc: usize,
+    env: ProcedureEnv,
+    /// The compound procedure being run, if any. Tail calls replace the frame rather than
+    /// pushing one, so only their count is kept.
+    frame: Option<Frame>,
+    /// Where the values of the call start on the stack.
+    stack_base: usize,
+    /// How many scopes were entered before the call.
+    scope_base: usize,
+}
+
+/// Runs compiled code. Calls in tail position replace the current frame, so loops run in
+/// constant space, while other calls push it on a heap allocated stack of at most `max_depth`
+/// frames.
+struct Vm {
+    current: CallFrame,
+    frames: Vec<CallFrame>,
+    stack: Vec<SExpr>,
+    /// The environments enclosing the scopes entered by the code being run.
+    scopes: Vec<ProcedureEnv>,
+    settings: Settings,
+}
+
+impl Vm {
+    fn new(code: Code, env: ProcedureEnv, settings: Settings) -> Self {
+        let current = CallFrame { code, pc: 0, env, frame: None, stack_base: 0, scope_base: 0 };
+        Self { current, frames: vec![], stack: vec![], scopes: vec![], settings }
+    }
+
+    fn run(mut self) -> EvalOutput {
+        match self.execute() {
+            Ok(value) => Ok(value),
+            Err(e) => Err(self.unwind(e)),
+        }
+    }
+
+    /// Records on `error` the frames of the procedures still waiting for a value.
+    fn unwind(self, error: SchemeError) -> SchemeError {
+        let pending = self.frames.into_iter().rev().filter_map(|call| call.frame);
+        self.current.frame.into_iter().chain(pending).fold(error, SchemeError::with_frame)
+    }
+
+    fn pop(&mut self) -> SExpr {
+        self.stack.pop().expect("compiled code never pops an empty stack")
+    }
+
+    fn top(&self) -> &SExpr {
+        self.stack.last().expect("compiled code never peeks at an empty stack")
+    }
+
+    fn name(&self, index: u32) -> &Symbol {
+        &self.current.code.names[index as usize]
+    }
+
+    fn execute(&mut self) -> EvalOutput {
+        loop {
+            let instruction = self.current.code.instructions[self.current.pc];
+            self.current.pc += 1;
+
+            match instruction {
+                Instruction::Constant(index) => {
+                    self.stack.push(self.current.code.constants[index as usize].clone())
+                }
+                Instruction::Local(depth, index) => {
+                    let value =
+                        self.current.env.access()?.get_local(depth as usize, index as usize);
+                    self.stack.push(value?);
+                }
+                Instruction::Free(name, depth) => {
+                    let name = self.name(name);
+                    let binding = self.current.env.access()?.get_free(name, depth as usize);
+                    let value = variable_value(name, binding)?;
+                    self.stack.push(value);
+                }
+                Instruction::Define(name) => {
+                    let value = self.pop();
+                    let name = self.name(name);
+                    if self.current.env.access_mut()?.define(name, &value).is_err() {
+                        let message = format!("error defining {}", name);
+                        return Err(SchemeError::syntax("define", message));
+                    }
+                    self.stack.push(SExpr::Ok);
+                }
+                Instruction::SetLocal(depth, index) => {
+                    let value = self.pop();
+                    self.current.env.access_mut()?.set_local(
+                        depth as usize,
+                        index as usize,
+                        value,
+                    )?;
+                    self.stack.push(SExpr::Ok);
+                }
+                Instruction::Set(name) => {
+                    let value = self.pop();
+                    self.current.env.access_mut()?.set(self.name(name), &value)?;
+                    self.stack.push(SExpr::Ok);
+                }
+                Instruction::Bind(index) => {
+                    let value = self.pop();
+                    self.current.env.access_mut()?.set_local(0, index as usize, value)?;
+                }
+                Instruction::Closure(lambda) => {
+                    let lambda = self.current.code.lambdas[lambda as usize].clone();
+                    let procedure = Procedure::Compound(lambda, self.current.env.clone());
+                    self.stack.push(SExpr::Procedure(procedure));
+                }
+                Instruction::Pop => _ = self.pop(),
+                Instruction::Dup => self.stack.push(self.top().clone()),
+                Instruction::Insert(depth) => {
+                    let value = self.pop();
+                    self.stack.insert(self.stack.len() - depth as usize, value);
+                }
+                Instruction::Jump(target) => self.current.pc = target as usize,
+                Instruction::JumpIfFalse(target) => {
+                    if !is_true(&self.pop()) {
+                        self.current.pc = target as usize;
+                    }
+                }
+                Instruction::JumpIfTrue(target) => {
+                    if is_true(&self.pop()) {
+                        self.current.pc = target as usize;
+                    }
+                }
+                Instruction::JumpIfFalseOrPop(target) => match is_true(self.top()) {
+                    true => _ = self.pop(),
+                    false => self.current.pc = target as usize,
+                },
+                Instruction::JumpIfTrueOrPop(target) => match is_true(self.top()) {
+                    true => self.current.pc = target as usize,
+                    false => _ = self.pop(),
+                },
+                Instruction::JumpUnlessMember(data, target) => {
+                    let key = self.top();
+                    let data = &self.current.code.constants[data as usize];
+                    if !data.pairs().any(|datum| datum.is_eqv(key)) {
+                        self.current.pc = target as usize;
+                    }
+                }
+                Instruction::Call(argc, name) => {
+                    self.call_from_stack(argc, name, false)?;
+                }
+                Instruction::TailCall(argc, name) => {
+                    if let Some(value) = self.call_from_stack(argc, name, true)? {
+                        return Ok(value);
+                    }
+                }
+                Instruction::Return => {
+                    let value = self.pop();
+                    if let Some(value) = self.return_value(value) {
+                        return Ok(value);
+                    }
+                }
+                Instruction::EnterScope(scope) => {
+                    let names = self.current.code.scopes[scope as usize].clone();
+                    let env = Environment::new_frame(self.current.env.clone(), names, []);
+                    self.scopes.push(std::mem::replace(&mut self.current.env, env));
+                }
+                Instruction::LeaveScope(count) => {
+                    let outermost = self.scopes.len() - count as usize;
+                    self.current.env = self.scopes[outermost].clone();
+                    self.scopes.truncate(outermost);
+                }
+                Instruction::Form(form, operands) => {
+                    let form = self.current.code.forms[form as usize];
+                    let args = self.current.code.constants[operands as usize].as_list()?;
+                    let value = form(args, self.current.env.clone())?;
+                    self.stack.push(value);
+                }
+                Instruction::Evaluate => {
+                    let expression = self.pop();
+                    let code = compile(&expression, &self.current.env);
+                    self.enter(code, self.current.env.clone(), None, false)?;
+                }
+                Instruction::TailEvaluate => {
+                    let expression = self.pop();
+                    let code = compile(&expression, &self.current.env);
+                    let frame = self.current.frame.take();
+                    self.enter(code, self.current.env.clone(), frame, true)?;
+                }
+                Instruction::Raise(error) => {
+                    return Err(self.current.code.errors[error as usize].clone());
+                }
+                Instruction::Unchanged(rebound) => {
+                    let unchanged = self.current.env.access()?.rebound() == rebound as usize;
+                    self.stack.push(SExpr::Boolean(unchanged));
+                }
+            }
+        }
+    }
+
+    /// Calls the procedure found below the `argc` values on top of the stack with them.
+    fn call_from_stack(
+        &mut self, argc: u32, name: Option<u32>, tail: bool,
+    ) -> Result<Option<SExpr>, SchemeError> {
+        let args = self.stack.split_off(self.stack.len() - argc as usize).into_iter().collect();
+        let procedure = self.pop();
+        let name = name.map(|name| self.name(name).to_string());
+
+        self.call(procedure, args, name, "eval", tail)
+    }
+
+    /// Calls `procedure` with `args`, in place of the current frame if in `tail` position. The
+    /// value of the whole run is returned if the call ends it.
+    fn call(
+        &mut self, procedure: SExpr, args: ListImplementation, name: Option<String>, caller: &str,
+        tail: bool,
+    ) -> Result<Option<SExpr>, SchemeError> {
+        let procedure = match procedure {
+            SExpr::Procedure(procedure) => procedure,
+            other => return Err(SchemeError::wrong_type(caller, &other, "a procedure")),
+        };
+
+        match procedure {
+            Procedure::Primitive(primitive) => {
+                if fn_addr_eq(primitive, Primitive::APPLY) {
+                    let (procedure, args) = apply_arguments(args)?;
+                    return self.call(procedure, args, None, "apply", tail);
+                }
+
+                if fn_addr_eq(primitive, Primitive::EVAL) {
+                    let env = match args.s_cadr() {
+                        Some(SExpr::Environment(env)) => env.clone(),
+                        _ => self.current.env.clone(),
+                    };
+                    let code = compile(&primitive(args, env.clone())?, &env);
+                    let frame = if tail { self.current.frame.take() } else { None };
+                    self.enter(code, env, frame, tail)?;
+                    return Ok(None);
+                }
+
+                if fn_addr_eq(primitive, Primitive::DYNAMIC_WIND) {
+                    let code =
+                        compile(&primitive(args, self.current.env.clone())?, &self.current.env);
+                    let frame = if tail { self.current.frame.take() } else { None };
+                    self.enter(code, self.current.env.clone(), frame, tail)?;
+                    return Ok(None);
+                }
+
+                let value = primitive(args, self.current.env.clone())?;
+                match tail {
+                    true => Ok(self.return_value(value)),
+                    false => {
+                        self.stack.push(value);
+                        Ok(None)
+                    }
+                }
+            }
+            Procedure::Compound(ref lambda, ref closure_env) => {
+                check_arity(&procedure, lambda, args.s_len())?;
+
+                #[cfg(feature = "jit")]
+                if let Some(value) =
+                    jit::call(lambda, closure_env, &args, self.settings.jit_threshold)
+                {
+                    return match tail {
+                        true => Ok(self.return_value(value)),
+                        false => {
+                            self.stack.push(value);
+                            Ok(None)
+                        }
+                    };
+                }
+
+                let code = lambda.code.get_or_init(|| compile_lambda(lambda, closure_env)).clone();
+                let env = Environment::new_frame(closure_env.clone(), code.locals.clone(), args);
+
+                let name = name.unwrap_or_else(|| SExpr::Procedure(procedure.clone()).to_string());
+                let tail_calls = match self.current.frame {
+                    Some(ref frame) if tail => frame.tail_calls + 1,
+                    _ => 0,
+                };
+                let span =
+                    self.current.pc.checked_sub(1).and_then(|pc| self.current.code.call_span(pc));
+                let frame = Frame { span, tail_calls, ..Frame::new(name) };
+
+                self.enter(code, env, Some(frame), tail)?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Starts running `code`, in place of the current frame if in `tail` position.
+    fn enter(
+        &mut self, code: Code, env: ProcedureEnv, frame: Option<Frame>, tail: bool,
+    ) -> Result<(), SchemeError> {
+        if tail {
+            self.stack.truncate(self.current.stack_base);
+            self.scopes.truncate(self.current.scope_base);
+            self.current = CallFrame { code, pc: 0, env, frame, ..self.current };
+            return Ok(());
+        }
+
+        if self.frames.len() >= self.settings.max_depth {
+            return Err(recursion_limit(self.settings.max_depth));
+        }
+
+        let callee = CallFrame {
+            code,
+            pc: 0,
+            env,
+            frame,
+            stack_base: self.stack.len(),
+            scope_base: self.scopes.len(),
+        };
+        self.frames.push(std::mem::replace(&mut self.current, callee));
+        Ok(())
+    }
+
+    /// Returns `value` to the frame that called the current one, or hands it back if there is
+    /// none.
+    fn return_value(&mut self, value: SExpr) -> Option<SExpr> {
+        let Some(caller) = self.frames.pop() else {
+            return Some(value);
+        };
+
+        self.stack.truncate(self.current.stack_base);
+        self.scopes.truncate(self.current.scope_base);
+        self.current = caller;
+        self.stack.push(value);
+        None
+    }
+}
@@ -1,4 +1,10 @@
 mod core;
 mod scheme;
 
-pub use self::core::interpreter::Interpreter;
+pub use self::core::{
+    error::{ErrorKind, Frame, Position, SchemeError, Span, MAX_TRACE_FRAMES},
+    interpreter::{Interpreter, Strategy, DEFAULT_MAX_DEPTH},
+};
+
+#[cfg(feature = "jit")]
+pub use self::core::interpreter::DEFAULT_JIT_THRESHOLD;
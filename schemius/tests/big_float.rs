@@ -0,0 +1,117 @@
+#![cfg(feature = "bigfloat")]
+
+/// Evaluates each expression with inexact numbers carrying `digits` significant digits,
+/// returning the printed results.
+fn eval_with_precision(digits: usize, expressions: &[&str]) -> Vec<String> {
+    let mut interpreter = schemius::Interpreter::default();
+    interpreter.set_float_precision(Some(digits));
+
+    expressions
+        .iter()
+        .map(|expression| interpreter.eval_expression_and_format(expression.to_string()).unwrap())
+        .collect()
+}
+
+#[test]
+fn big_float_literals() {
+    let results = eval_with_precision(
+        30,
+        &[
+            "3.14159265358979323846264338327950288",
+            "#i1/3",
+            "(inexact 2/3)",
+            "1.5e-20",
+            "-2.5e40",
+            "100.0",
+            "(exact 0.1)",
+            "(exact? 0.1)",
+        ],
+    );
+
+    assert_eq!(
+        results,
+        [
+            "3.14159265358979323846264338328",
+            "0.333333333333333333333333333333",
+            "0.666666666666666666666666666667",
+            "1.5e-20",
+            "-2.5e40",
+            "100.0",
+            "1/10",
+            "#f",
+        ]
+    );
+}
+
+#[test]
+fn big_float_arithmetic() {
+    let results = eval_with_precision(
+        25,
+        &[
+            "(+ 0.1 0.2)",
+            "(= (+ 0.1 0.2) 0.3)",
+            "(* 1/4 3.0)",
+            "(- 1.0 1)",
+            "(/ 1 3.0)",
+            "(/ 1.0 0)",
+            "(< 0.5 2/3 1.0)",
+            "(integer? 4.0)",
+            "(+ 0.5 1+2i)",
+        ],
+    );
+
+    assert_eq!(
+        results,
+        ["0.3", "#t", "0.75", "0.0", "0.3333333333333333333333333", "inf", "#t", "#t", "1.5+2.0i"]
+    );
+}
+
+#[test]
+fn big_float_elementary_functions() {
+    let results = eval_with_precision(
+        50,
+        &[
+            "(* 4 (atan 1))",
+            "(exp 1)",
+            "(log 2)",
+            "(sqrt 2)",
+            "(sin 1)",
+            "(cos 1)",
+            "(atan -1 -1)",
+            "(sqrt 16)",
+            "(sqrt -2.0)",
+        ],
+    );
+
+    assert_eq!(
+        results,
+        [
+            "3.1415926535897932384626433832795028841971693993751",
+            "2.7182818284590452353602874713526624977572470937",
+            "0.69314718055994530941723212145817656807550013436026",
+            "1.4142135623730950488016887242096980785696718753769",
+            "0.84147098480789650665250232163029899962256306079837",
+            "0.54030230586813971740093660744297660373231042061792",
+            "-2.3561944901923449288469825374596271631478770495313",
+            "4",
+            "0.0+1.4142135623730951i",
+        ]
+    );
+}
+
+#[test]
+fn big_float_precision_per_interpreter() {
+    let path = std::env::temp_dir().join("schemius_big_float_third.scm");
+    std::fs::write(&path, "(define third 0.333333333333333333333333333333)").unwrap();
+
+    let mut big = schemius::Interpreter::default();
+    big.set_float_precision(Some(30));
+    let mut native = schemius::Interpreter::default();
+
+    let load = format!("(load {:?}) third", path.display().to_string());
+    for expression in ["(/ 1 3.0)", "(inexact 1/3)", "(exp 1)", &load] {
+        let big = big.eval_expression_and_format(expression.to_string()).unwrap();
+        let native = native.eval_expression_and_format(expression.to_string()).unwrap();
+        assert_ne!(big, native, "{}", expression);
+    }
+}
@@ -0,0 +1,369 @@
+use std::panic::{self, AssertUnwindSafe};
+
+use schemius::Interpreter;
+
+/// Number of programs generated by each test, overridable through `SCHEMIUS_FUZZ_ITERATIONS`.
+fn iterations() -> usize {
+    std::env::var("SCHEMIUS_FUZZ_ITERATIONS").ok().and_then(|n| n.parse().ok()).unwrap_or(2000)
+}
+
+/// A xorshift generator, so that every run explores the same programs.
+struct Rng(u64);
+
+impl Rng {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % n as u64) as usize
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len())]
+    }
+}
+
+const FRAGMENTS: &[&str] = &[
+    "(",
+    ")",
+    "[",
+    "]",
+    "#(",
+    "'",
+    "`",
+    ",",
+    ",@",
+    "#;",
+    "#|",
+    "|#",
+    "\"",
+    "\\",
+    "|",
+    "#\\",
+    ".",
+    " ",
+    "\n",
+    ";",
+    "x",
+    "λ",
+    "1",
+    "-2",
+    "3.5",
+    "1/2",
+    "1/0",
+    "0/0",
+    "1e400",
+    "+i",
+    "1@2",
+    "#t",
+    "#false",
+    "#e",
+    "#i",
+    "#x",
+    "#b1",
+    "#\\space",
+    "#\\x41;",
+    "\\x3bb;",
+    "#!fold-case",
+    "#!",
+    "+",
+    "car",
+    "quote",
+    "lambda",
+    "define",
+    "if",
+    "+nan.0",
+    "-inf.0",
+    "...",
+    "#λ",
+    "@",
+];
+
+/// Procedures and special forms that cannot stop the process, touch files or block on input.
+/// `define` and `set!` are left out, as rebinding a builtin can make a program recurse forever.
+const PROCEDURES: &[&str] = &[
+    "+",
+    "-",
+    "*",
+    "/",
+    "=",
+    "<",
+    ">=",
+    "car",
+    "cdr",
+    "cons",
+    "list",
+    "set-car!",
+    "append",
+    "length",
+    "list-ref",
+    "list-splice",
+    "list-tail",
+    "reverse",
+    "apply",
+    "eval",
+    "display",
+    "string",
+    "make-string",
+    "string-append",
+    "string-length",
+    "string-ref",
+    "string-set!",
+    "string-upcase",
+    "null?",
+    "pair?",
+    "list?",
+    "number?",
+    "integer?",
+    "exact?",
+    "exact",
+    "inexact",
+    "sqrt",
+    "exp",
+    "log",
+    "atan",
+    "nan?",
+    "zero?",
+    "procedure?",
+    "not",
+    "and",
+    "or",
+    "if",
+    "begin",
+    "quote",
+    "quasiquote",
+    "let",
+    "let*",
+    "cond",
+    "environment",
+    "interaction-environment",
+    "null-environment",
+    "scheme-report-environment",
+    "dynamic-wind",
+    "features",
+    "get-environment-variable",
+];
+
+const ATOMS: &[&str] = &[
+    "0",
+    "1",
+    "-1",
+    "2.5",
+    "1/3",
+    "+nan.0",
+    "+inf.0",
+    "1+2i",
+    "#t",
+    "#f",
+    "#\\a",
+    "\"str\"",
+    "'sym",
+    "'()",
+    "'(1 2)",
+    "'(1 . 2)",
+    "#(1 2)",
+    "x",
+    "y",
+    "v",
+    "'(scheme base)",
+    "5",
+    "9223372036854775807",
+    "(lambda (x) x)",
+    "(lambda x x)",
+    "car",
+    "`(1 ,x ,@y)",
+    ",x",
+    "'",
+];
+
+fn expression(rng: &mut Rng, depth: usize) -> String {
+    if depth == 0 || rng.below(3) == 0 {
+        return rng.pick(ATOMS).to_string();
+    }
+
+    let mut parts = vec![rng.pick(PROCEDURES).to_string()];
+    for _ in 0..rng.below(5) {
+        parts.push(expression(rng, depth - 1));
+    }
+
+    // Lambda bodies only call builtins, so generated programs always terminate
+    match rng.below(8) {
+        0 => {
+            format!("((lambda (x y) {}) {} {})", parts.join(" "), rng.pick(ATOMS), rng.pick(ATOMS))
+        }
+        1 => format!("(define v ({}))", parts.join(" ")),
+        _ => format!("({})", parts.join(" ")),
+    }
+}
+
+/// Inputs evaluated by the same interpreter before it is replaced by a fresh one, so that what
+/// earlier inputs defined or redefined only affects the next few.
+const INPUTS_PER_INTERPRETER: usize = 100;
+
+fn new_interpreter() -> Interpreter {
+    let mut interpreter = Interpreter::default();
+    let _ = interpreter.eval_expression("(define x '(1 2 3)) (define y \"abc\")".to_string());
+    interpreter
+}
+
+/// Evaluates every source, asserting that none of them panics.
+fn check(sources: impl Iterator<Item = String>) {
+    panic::set_hook(Box::new(|_| {}));
+
+    let mut interpreter = new_interpreter();
+    let mut failures = vec![];
+
+    for (i, source) in sources.enumerate() {
+        if i % INPUTS_PER_INTERPRETER == 0 {
+            interpreter = new_interpreter();
+        }
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let _ = interpreter.eval_expression(source.clone());
+        }));
+
+        if let Err(payload) = result {
+            let message = match payload.downcast_ref::<String>() {
+                Some(message) => message.clone(),
+                None => payload.downcast_ref::<&str>().unwrap_or(&"").to_string(),
+            };
+            failures.push(format!("{}\n  => {}", source, message));
+            interpreter = new_interpreter();
+        }
+    }
+
+    let _ = panic::take_hook();
+
+    assert!(failures.is_empty(), "{} inputs panicked:\n{}", failures.len(), failures.join("\n"));
+}
+
+#[test]
+fn fuzz_reader() {
+    let mut rng = Rng(0x5eed_0001);
+    check((0..iterations()).map(|_| {
+        let length = rng.below(12) + 1;
+        (0..length).map(|_| rng.pick(FRAGMENTS)).collect()
+    }));
+}
+
+#[test]
+fn fuzz_evaluator() {
+    let mut rng = Rng(0x5eed_0002);
+    check((0..iterations()).map(|_| expression(&mut rng, 4)));
+}
+
+#[test]
+fn fuzz_known_panics() {
+    check(
+        [
+            "(set-car! x x)",
+            "(define z (list 1 2)) (set-car! z z) (car z)",
+            "`(1 ,@'() ,@(list))",
+            "`(,@5)",
+            "``,,x",
+            "(car '())",
+            "(list-ref '(1 2) -1)",
+            "(string-ref \"abc\" -1)",
+            "(make-string -1 #\\a)",
+            "(/ 1 0)",
+            "(exact->inexact 1/0)",
+            "(apply + 1)",
+            "(eval '(+ 1 2) 5)",
+            "(quote)",
+            "(lambda)",
+            "(let ((x)) x)",
+            "(cond (else))",
+            "(if)",
+            "(#t)",
+            "(())",
+            "(1 . 2)",
+            "#(1 . 2)",
+            "'(1 . 2 . 3)",
+            "((lambda () (environment-bindings)))",
+            "(define s (make-string 3 #\\λ)) (string-set! s 1 #\\a) (string-ref s 2)",
+            "(list-splice '(1 2) 1 5 '(1 2 3 4))",
+            "(list-splice x 0 0 x)",
+            "(< 1 +nan.0 +inf.0 1/2)",
+            "#e+inf.0",
+            "#λx",
+            "1/0@1",
+        ]
+        .into_iter()
+        .map(String::from),
+    );
+}
+
+/// Source is read and evaluated however deeply it is nested.
+#[test]
+fn fuzz_deep_nesting() {
+    let depth = 5_000;
+    let sources = [
+        format!("{}0{}", "(+ 1 ".repeat(depth), ")".repeat(depth)),
+        format!("'{}{}", "(".repeat(depth), ")".repeat(depth)),
+        format!("{}0{}", "(let ((a ".repeat(depth / 3), ")) a)".repeat(depth / 3)),
+        format!("{}0{}", "((lambda () ".repeat(depth / 2), "))".repeat(depth / 2)),
+        format!("{}0{}", "(let ((a 1)) (set! a 2) ".repeat(depth / 2), ")".repeat(depth / 2)),
+    ];
+    check(sources.clone().into_iter());
+
+    for strategy in [schemius::Strategy::Bytecode, schemius::Strategy::TreeWalking] {
+        for optimize in [false, true] {
+            let mut interpreter = Interpreter::default();
+            interpreter.set_strategy(strategy);
+            interpreter.set_optimize(optimize);
+
+            let values: Vec<String> = sources
+                .iter()
+                .map(|source| interpreter.eval_expression_and_format(source.clone()).unwrap())
+                .collect();
+            assert_eq!(values[0], depth.to_string(), "with {:?}", strategy);
+            assert_eq!(&values[2..], ["0", "0", "0"], "with {:?}", strategy);
+        }
+    }
+}
+
+/// Chains of closures capturing one another are freed without recursing through the chain.
+#[test]
+fn fuzz_long_closure_chains() {
+    let make = "(define (mk n e) (if (= n 0) e (mk (- n 1) (let ((x e)) (lambda () x)))))";
+
+    check(
+        [
+            "(define (mk n e) (if (= n 0) 'done (mk (- n 1) (let ((x e)) (lambda () x))))) \
+             (mk 100000 #f)"
+                .to_string(),
+            format!("{} (define keep (mk 100000 #f)) (set! keep #f)", make),
+            format!("{} (define keep (mk 100000 (list 1 2))) (set! keep (keep))", make),
+        ]
+        .into_iter(),
+    );
+}
+
+/// Data built at runtime are not bounded by the reader, yet are printed and evaluated.
+#[test]
+fn fuzz_deep_runtime_data() {
+    let nest = "(define (nest n acc) (if (= n 0) acc (nest (- n 1) (list acc))))";
+    let build = "(define (build n acc) (if (= n 0) acc (build (- n 1) (list '+ 1 acc))))";
+
+    check(
+        [
+            format!("{} (display (nest 200000 '()))", nest),
+            format!("{} (eval (build 100000 0))", build),
+        ]
+        .into_iter(),
+    );
+
+    for strategy in [schemius::Strategy::Bytecode, schemius::Strategy::TreeWalking] {
+        let mut interpreter = Interpreter::default();
+        interpreter.set_strategy(strategy);
+
+        let printed = interpreter.eval_expression_and_format(format!("{} (nest 3 '())", nest));
+        assert_eq!(printed.unwrap(), "(((())))");
+
+        let evaluated =
+            interpreter.eval_expression_and_format(format!("{} (eval (build 100000 0))", build));
+        assert_eq!(evaluated.unwrap(), "100000", "with {:?}", strategy);
+        let evaluated = interpreter.eval_expression_and_format("(eval (build 200 0))".to_string());
+        assert_eq!(evaluated.unwrap(), "200");
+    }
+}
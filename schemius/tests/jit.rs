@@ -0,0 +1,105 @@
+#![cfg(feature = "jit")]
+
+/// Procedures are compiled to native code once called this many times.
+const THRESHOLD: usize = 3;
+
+/// Evaluates `program` once interpreted and once compiled to native code, which must agree,
+/// returning the results.
+fn assert_same_results(program: &str, calls: &[&str]) -> Vec<String> {
+    let results = [None, Some(THRESHOLD)].map(|threshold| {
+        let mut interpreter = schemius::Interpreter::default();
+        interpreter.set_jit_threshold(threshold);
+        interpreter.eval_expression(program.to_string()).unwrap();
+
+        let results: Vec<String> = calls
+            .iter()
+            .map(|call| match interpreter.eval_expression(call.to_string()) {
+                Ok(value) => value.to_string(),
+                Err(e) => format!("error: {}", e.kind() as u8),
+            })
+            .collect();
+
+        results
+    });
+
+    assert_eq!(results[0], results[1], "{}", program);
+    results[1].clone()
+}
+
+#[test]
+fn jit_fixnum_recursion() {
+    let results = assert_same_results(
+        "(define (fib n) (if (< n 2) n (+ (fib (- n 1)) (fib (- n 2)))))",
+        &["(fib 0)", "(fib 1)", "(fib 10)", "(fib 20)"],
+    );
+    assert_eq!(results, ["0", "1", "55", "6765"]);
+    assert_same_results(
+        "(define (tak x y z) (if (not (< y x)) z (tak (tak (- x 1) y z) (tak (- y 1) z x) (tak (- z 1) x y))))",
+        &["(tak 12 8 4)", "(tak 18 12 6)"],
+    );
+}
+
+#[test]
+fn jit_flonum_arithmetic() {
+    assert_same_results(
+        "(define (poly x) (- (* 3 x x) (* 2.5 x) (- 1)))",
+        &["(poly 0.0)", "(poly 1.5)", "(poly -0.0)", "(poly 2)", "(poly 1e300)", "(poly 4)"],
+    );
+    assert_same_results(
+        "(define (compare a b) (list (= a b) (< a b) (<= a b 2.0) (> a b) (>= a b)))",
+        &["(compare 1 1.0)", "(compare 1.5 1)", "(compare 2 3)", "(compare 0.1 0.2)"],
+    );
+}
+
+#[test]
+fn jit_loops() {
+    let results = assert_same_results(
+        "(define (sum-to n) (let loop ((k 0) (acc 0)) (if (> k n) acc (loop (+ k 1) (+ acc k)))))",
+        &["(sum-to 10)", "(sum-to 100000)"],
+    );
+    assert_eq!(results, ["55", "5000050000"]);
+    assert_same_results(
+        "(define (harmonic n) (let loop ((k 1) (acc 0.0)) (if (> k n) acc (loop (+ k 1) (+ acc (* 1.0 k))))))",
+        &["(harmonic 10)", "(harmonic 1000)"],
+    );
+    assert_same_results(
+        "(define (even-odd n) (let loop ((n n) (even #t)) (if (= n 0) even (loop (- n 1) (not even)))))",
+        &["(even-odd 7)", "(even-odd 10)", "(even-odd 10000)"],
+    );
+}
+
+#[test]
+fn jit_deoptimization() {
+    // Overflowing fixnums are promoted by the interpreter
+    assert_same_results(
+        "(define (fact n) (if (= n 0) 1 (* n (fact (- n 1)))))",
+        &["(fact 5)", "(fact 20)", "(fact 21)", "(fact 30)", "(fact 10)"],
+    );
+    // Recursion too deep for the native stack goes back to the interpreter
+    let results = assert_same_results(
+        "(define (count n) (if (= n 0) 0 (+ 1 (count (- n 1)))))",
+        &["(count 10)", "(count 100000)", "(count 20)"],
+    );
+    assert_eq!(results, ["10", "100000", "20"]);
+    // Arguments of other kinds fail the guards of the compiled code
+    assert_same_results(
+        "(define (twice x) (+ x x))",
+        &["(twice 1)", "(twice 2)", "(twice 3)", "(twice 4)", "(twice 1.5)", "(twice 'a)"],
+    );
+}
+
+#[test]
+fn jit_redefinitions() {
+    assert_same_results(
+        "(define k 10) (define (scale x) (* k x))",
+        &["(scale 1)", "(scale 2)", "(scale 3)", "(scale 4)", "(define k 0.5)", "(scale 4)"],
+    );
+    assert_same_results(
+        "(define (add x) (+ x 1))",
+        &["(add 1)", "(add 2)", "(add 3)", "(add 4)", "(define + -)", "(add 5)"],
+    );
+    assert_same_results(
+        "(define (down n) (if (= n 0) 'done (down (- n 1))))",
+        &["(down 1)", "(down 2)", "(down 3)", "(down 4)", "(down 1000)"],
+    );
+}
@@ -108,6 +108,116 @@ fn interpreter_factorial_bigint() {
     }
 }
 
+#[test]
+fn interpreter_exact_normalization() {
+    integration_subtest_eval_to! {
+        { expression: "(- (* 99999999999999999999 2) 199999999999999999990)", expected: "8" };
+        { expression: "(exact-integer? (/ (* 99999999999999999999 3) 99999999999999999999))", expected: "#t" };
+        { expression: "(* 1/2 4)", expected: "2" };
+        { expression: "(+ 1/3 2/3)", expected: "1" };
+        { expression: "6/2", expected: "3" };
+    }
+}
+
+#[test]
+fn interpreter_exact_complex() {
+    integration_subtest_is_err! {
+        expression: "(exact +inf.0)";
+    }
+
+    integration_subtest_eval_to! {
+        { expression: "(exact? 1+2i)", expected: "#t" };
+        { expression: "(* 2 3+4i)", expected: "6+8i" };
+        { expression: "(exact? (* 2 3+4i))", expected: "#t" };
+        { expression: "(* 1+2i 1-2i)", expected: "5" };
+        { expression: "(exact-integer? (* 1+2i 1-2i))", expected: "#t" };
+        { expression: "(/ 1+2i 2)", expected: "1/2+1i" };
+        { expression: "(- 1/2+3i 1/2+3i)", expected: "0" };
+        { expression: "(exact? (+ 1+2i 0.5))", expected: "#f" };
+        { expression: "(= 1+2i 1.0+2.0i)", expected: "#t" };
+        { expression: "(exact? #i1+2i)", expected: "#f" };
+        { expression: "(exact? #e1.5+2i)", expected: "#t" };
+        { expression: "(inexact 1/2+3i)", expected: "0.5+3.0i" };
+        { expression: "(exact 0.5+2.5i)", expected: "1/2+5/2i" };
+        { expression: "(exact 2.0+0.0i)", expected: "2" };
+        { expression: "(exact 1.5)", expected: "3/2" };
+        { expression: "(inexact 1/4)", expected: "0.25" };
+        { expression: "(inexact->exact (exact->inexact 1/4))", expected: "1/4" };
+    }
+}
+
+#[test]
+fn interpreter_inexact_complex() {
+    integration_subtest_is_err! {
+        expression: "(< 1+2i 2)";
+        expression: "(> 3 2 1+2i)";
+        expression: "(<= 1 2.0+0.5i)";
+    }
+
+    integration_subtest_eval_to! {
+        { expression: "(exact->inexact 1+2i)", expected: "1.0+2.0i" };
+        { expression: "(+ 1.5 1+2i)", expected: "2.5+2.0i" };
+        { expression: "(- 1.0-2i 3)", expected: "-2.0-2.0i" };
+        { expression: "(/ 1+2i 0.0)", expected: "+nan.0+nan.0i" };
+        { expression: "(inexact +inf.0-inf.0i)", expected: "+inf.0-inf.0i" };
+        { expression: "(= 1+2i 1+2i 1.0+2.0i)", expected: "#t" };
+    }
+}
+
+/// Inexact complex numbers are printed in a notation the reader gives them back from.
+#[test]
+fn interpreter_inexact_complex_round_trip() {
+    let mut interpreter = schemius::Interpreter::default();
+
+    for expression in [
+        "(exact->inexact 1+2i)",
+        "(+ 1.5 1+2i)",
+        "(* 0.1 1-3i)",
+        "(- 0.0-0.0i)",
+        "(/ 1+2i 0.0)",
+        "(inexact +inf.0-inf.0i)",
+        "(inexact -inf.0+nan.0i)",
+        "(exact->inexact +2i)",
+    ] {
+        let printed = interpreter.eval_expression_and_format(expression.to_string()).unwrap();
+        let read = interpreter.eval_expression_and_format(printed.clone()).unwrap();
+        assert_eq!(read, printed, "{} does not read back as itself", printed);
+    }
+}
+
+#[test]
+fn interpreter_elementary_functions() {
+    integration_subtest_is_err! {
+        expression: "(sqrt 'x)";
+        expression: "(log)";
+        expression: "(atan 1+i 1)";
+    }
+
+    integration_subtest_eval_to! {
+        { expression: "(sqrt 16)", expected: "4" };
+        { expression: "(sqrt 9/4)", expected: "3/2" };
+        { expression: "(sqrt -4)", expected: "0+2i" };
+        { expression: "(exact? (sqrt -4))", expected: "#t" };
+        { expression: "(sqrt 16.0)", expected: "4.0" };
+        { expression: "(exp 0)", expected: "1.0" };
+        { expression: "(log 1)", expected: "0.0" };
+        { expression: "(log 0)", expected: "-inf" };
+        { expression: "(log 100 10)", expected: "2.0" };
+        { expression: "(sin 0)", expected: "0.0" };
+        { expression: "(cos 0)", expected: "1.0" };
+        { expression: "(real? (asin 2))", expected: "#f" };
+        { expression: "(real? (log -1))", expected: "#f" };
+    }
+
+    // Single precision flonums print fewer digits
+    #[cfg(not(feature = "f32"))]
+    integration_subtest_eval_to! {
+        { expression: "(sqrt 2)", expected: "1.4142135623730951" };
+        { expression: "(atan 1 1)", expected: "0.7853981633974483" };
+        { expression: "(* 4 (atan 1))", expected: "3.141592653589793" };
+    }
+}
+
 #[test]
 fn interpreter_apply() {
     integration_subtest_eval_to! {
@@ -162,9 +272,33 @@ fn interpreter_quasiquotation() {
         { expression: "`(1 2 ,@(list 1 2 3))", expected: "(1 2 1 2 3)" };
         { expression: "`(,@x ,x)", expected: "(1 2 3 (1 2 3))" };
         { expression: "`(,@x ,x ,@x ,x ,@x)", expected: "(1 2 3 (1 2 3) 1 2 3 (1 2 3) 1 2 3)" };
+        { expression: "`#(0 ,@x ,(car x))", expected: "#(0 1 2 3 1)" };
         { expression: r#"(define x "unquoted")"#, expected: "ok" };
         { expression: r#"`(1 2 ,x (+ 3 ,x))"#, expected: r#"(1 2 "unquoted" (+ 3 "unquoted"))"# };
         { expression: "`(1 2 ,x `(1 2 ,x (+ 3 ,x)))", expected: r#"(1 2 "unquoted" (quasiquote (1 2 (unquote x) (+ 3 (unquote x)))))"# };
+        { expression: "`(1 . ,(+ 1 1))", expected: "(1 . 2)" };
+        { expression: "`(1 2 . ,(list 3 4))", expected: "(1 2 3 4)" };
+        { expression: "`((a . ,(+ 1 1)) . ,x)", expected: r#"((a . 2) . "unquoted")"# };
+        { expression: "`(1 unquote 2 3)", expected: "(1 unquote 2 3)" };
+    }
+}
+
+#[test]
+fn interpreter_quasiquotation_deep() {
+    let programs = [
+        ("(define (f n) (if (= n 0) '() `(,n ,@(f (- n 1))))) (length (f 10000))", "10000"),
+        ("(define (g n) (if (= n 0) #() `#(,(g (- n 1))))) (vector? (g 10000))", "#t"),
+        ("(define (h n) (if (= n 0) 0 `(1 . ,(h (- n 1))))) (list-tail (h 10000) 9999)", "(1 . 0)"),
+    ];
+
+    for strategy in [schemius::Strategy::Bytecode, schemius::Strategy::TreeWalking] {
+        let mut interpreter = schemius::Interpreter::default();
+        interpreter.set_strategy(strategy);
+
+        for (program, expected) in programs {
+            let result = interpreter.eval_expression_and_format(program.to_string());
+            assert_eq!(result.unwrap(), expected, "{} with {:?}", program, strategy);
+        }
     }
 }
 
@@ -381,7 +515,7 @@ fn interpreter_pair_list_procs() {
 fn interpreter_pair_list_procs_2() {
     integration_subtest_eval_to! {
         { expression: "(cons 1 2)", expected: "(1 . 2)" };
-        // { expression: "(cons 1 (cons 2 3))", expected: "(1 2 . 3)" };
+        { expression: "(cons 1 (cons 2 3))", expected: "(1 2 . 3)" };
         { expression: "(list 1 2 3)", expected: "(1 2 3)" };
         { expression: "(list-ref '(1 2 3) 1)", expected: "2" };
         { expression: "(list-tail '(1 2 3) 1)", expected: "(2 3)" };
@@ -394,6 +528,38 @@ fn interpreter_pair_list_procs_2() {
     }
 }
 
+#[test]
+fn interpreter_shared_pairs() {
+    integration_subtest_eval_to! {
+        { expression: "(define tail (list 2 3)) (define l (cons 1 tail)) (set-car! tail 20) l", expected: "(1 20 3)" };
+        { expression: "(set-cdr! tail '(30 . 40)) l", expected: "(1 20 30 . 40)" };
+        { expression: "(set-car! (list-tail l 2) 300) tail", expected: "(20 300 . 40)" };
+        { expression: "(define a (append '(0) l)) (set-car! l 10) a", expected: "(0 10 20 300 . 40)" };
+        { expression: "(set-car! a 5) l", expected: "(10 20 300 . 40)" };
+        { expression: "(set-cdr! (cddr l) l) (list? l)", expected: "#f" };
+        { expression: "(set-cdr! (list-tail a 3) a) (car (list-tail a 10))", expected: "20" };
+        { expression: "(append)", expected: "()" };
+    }
+
+    integration_subtest_is_err! {
+        expression: "(set-cdr! '() 1)";
+        expression: "(length '(1 2 . 3))";
+        expression: "(append '(1 . 2) '(3))";
+        expression: "(+ . 1)";
+    }
+}
+
+#[test]
+fn interpreter_long_lists() {
+    integration_subtest_eval_to! {
+        { expression: "(define l (do ((n 0 (+ n 1)) (acc '() (cons n acc))) ((= n 100000) acc))) (length l)", expected: "100000" };
+        { expression: "(car (list-tail l 99999))", expected: "0" };
+        { expression: "(set! l '()) l", expected: "()" };
+        { expression: "(define (nest n acc) (if (= n 0) acc (nest (- n 1) (list acc))))", expected: "ok" };
+        { expression: "(define d (nest 100000 '())) (set! d 0) d", expected: "0" };
+    }
+}
+
 #[test]
 fn interpreter_list_splicing() {
     integration_subtest_eval_to! {
@@ -432,3 +598,535 @@ fn interpreter_strings() {
         expression: "(string-set! \"hello\" 5 #\\e)";
     }
 }
+
+#[test]
+fn interpreter_load_include() {
+    let dir = std::env::temp_dir().join("schemius_load_include");
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    std::fs::write(dir.join("main.scm"), "(define a 1)\n; (comment\n(include \"sub/inner.scm\")\n")
+        .unwrap();
+    std::fs::write(dir.join("sub/inner.scm"), "(define b\n  (+ a 1))\n(include-ci \"upper.scm\")")
+        .unwrap();
+    std::fs::write(dir.join("sub/upper.scm"), "(DEFINE C 'Hello)").unwrap();
+    std::fs::write(dir.join("broken.scm"), "(define d 1)\n(car '())").unwrap();
+
+    let mut interpreter = schemius::Interpreter::default();
+    let load = format!("(load \"{}\")", dir.join("main.scm").display());
+    assert!(interpreter.eval_expression(load).is_ok());
+    assert_eq!(
+        interpreter.eval_expression_and_format("(list a b c)".to_string()).unwrap(),
+        "(1 2 hello)"
+    );
+
+    let load = format!("(load \"{}\")", dir.join("broken.scm").display());
+    let err = interpreter.eval_expression(load).unwrap_err();
+    assert!(err.span().is_some_and(|span| span.to_string().contains("broken.scm:2:1")));
+
+    let load = format!(
+        "(define env (environment '(scheme base))) (load \"{}\" env) (eval '(list a b c) env)",
+        dir.join("main.scm").display()
+    );
+    assert_eq!(interpreter.eval_expression_and_format(load).unwrap(), "(1 2 hello)");
+
+    let load = format!("(load \"{}\" 'env)", dir.join("main.scm").display());
+    assert!(interpreter.eval_expression(load).is_err());
+
+    std::fs::write(dir.join("body.scm"), "(define y (* x 2))\n(include \"sub/inner.scm\")")
+        .unwrap();
+    let include = format!(
+        "(define (f x) (include \"{}\") (list x y b c)) (f 3)",
+        dir.join("body.scm").display()
+    );
+    assert_eq!(interpreter.eval_expression_and_format(include).unwrap(), "(3 6 2 hello)");
+    assert!(interpreter.eval_expression("y".to_string()).is_err());
+
+    let load = format!("(load \"{}\")", dir.join("missing.scm").display());
+    let err = interpreter.eval_expression(load).unwrap_err();
+    assert_eq!(err.kind(), schemius::ErrorKind::Io);
+    assert!(err.to_string().contains("missing.scm"));
+}
+
+#[test]
+fn interpreter_cond_expand() {
+    integration_subtest_eval_to! {
+        { expression: "(cond-expand ((and r7rs schemius ratios) 'yes) (else 'no))", expected: "yes" };
+        { expression: "(cond-expand ((not r7rs) 'no) ((or nonexistent exact-closed) 'yes))", expected: "yes" };
+        { expression: "(cond-expand ((or i32 i64 i128) 'int) (else 'none))", expected: "int" };
+        { expression: "(cond-expand ((and f32 f64) 'both) ((or f32 f64) 'one))", expected: "one" };
+        { expression: "(cond-expand ((library (scheme base)) 'base) (else 'none))", expected: "base" };
+        { expression: "(cond-expand ((library (srfi 0)) 'srfi) (else 'none))", expected: "none" };
+        { expression: "(begin (cond-expand (full-unicode (define x 1) (define y 2))) (+ x y))", expected: "3" };
+    }
+
+    integration_subtest_is_err! {
+        expression: "(cond-expand ((xor r7rs) 'no))";
+        expression: "(features 1)";
+    }
+}
+
+#[test]
+fn interpreter_libraries() {
+    integration_subtest_eval_to! {
+        { expression: "(define-library (stack) (export make push (rename top peek)) (import (scheme base)) (begin (define (make) '()) (define (push s x) (cons x s)) (define (top s) (car s))))", expected: "\n" };
+        { expression: "(begin (import (stack)) (peek (push (push (make) 1) 2)))", expected: "2" };
+        { expression: "(begin (import (prefix (only (stack) push) s:) (rename (except (stack) make) (peek first))) (first (s:push '() 3)))", expected: "3" };
+        { expression: "(define-library (util 1) (import (only (scheme base) define +) (stack)) (export twice) (cond-expand (r7rs (begin (define (twice x) (push (push (make) x) x)))) (else)))", expected: "\n" };
+        { expression: "(begin (import (util 1)) (length (twice 5)))", expected: "2" };
+        { expression: "(begin (import (rename (scheme base) (car head))) (head '(1 2)))", expected: "1" };
+    }
+
+    integration_subtest_is_err! {
+        expression: "(import (nonexistent library))";
+        expression: "(import (only (scheme base) no-such-binding))";
+        expression: "(define-library (lib) (export x) (unknown-declaration))";
+        expression: "(define-library (hidden) (begin (define (f) 1)))\n(f)";
+        expression: "(define-library (needs-import) (begin (define x (+ 1 2))))";
+        expression: "(define-library \"name\")";
+    }
+}
+
+#[test]
+fn interpreter_process_context() {
+    integration_subtest_eval_to! {
+        { expression: "(list? (command-line))", expected: "#t" };
+        { expression: "(string? (car (command-line)))", expected: "#t" };
+        { expression: r#"(get-environment-variable "SCHEMIUS_SURELY_UNSET_VARIABLE")"#, expected: "#f" };
+        { expression: "(list? (get-environment-variables))", expected: "#t" };
+        { expression: "(define x 0)", expected: "ok" };
+        { expression: "(dynamic-wind (lambda () (set! x 1)) (lambda () (set! x (+ x 1)) 'done) (lambda () (set! x (* x 10))))", expected: "done" };
+        { expression: "x", expected: "20" };
+    }
+
+    integration_subtest_is_err! {
+        expression: "(get-environment-variable 'HOME)";
+        expression: "(dynamic-wind 1 2 3)";
+        expression: "(exit 'error)";
+        expression: "(exit 1 2)";
+    }
+}
+
+#[test]
+fn interpreter_command_line_per_interpreter() {
+    let mut program = schemius::Interpreter::default();
+    program.set_command_line(vec![String::from("program"), String::from("--flag")]);
+    let mut other = schemius::Interpreter::default();
+
+    let command_line = String::from("(command-line)");
+    let program_line = program.eval_expression_and_format(command_line.clone()).unwrap();
+    let other_line = other.eval_expression_and_format(command_line).unwrap();
+    assert_eq!(program_line, r#"("program" "--flag")"#);
+    assert_ne!(other_line, program_line);
+}
+
+#[test]
+fn interpreter_time() {
+    integration_subtest_eval_to! {
+        { expression: "(time (+ 1 2))", expected: "3" };
+        { expression: "(time '(1 2))", expected: "(1 2)" };
+        { expression: "(real? (current-second))", expected: "#t" };
+        { expression: "(> (current-second) 1700000000)", expected: "#t" };
+        { expression: "(exact-integer? (current-jiffy))", expected: "#t" };
+        { expression: "(jiffies-per-second)", expected: "1000000" };
+        { expression: "(define start (current-jiffy))", expected: "ok" };
+        { expression: "(>= (current-jiffy) start)", expected: "#t" };
+    }
+
+    integration_subtest_is_err! {
+        expression: "(time)";
+        expression: "(time undefined-variable)";
+        expression: "(current-second 1)";
+    }
+}
+
+#[test]
+fn interpreter_eval_environments() {
+    integration_subtest_eval_to! {
+        { expression: "(eval '(* 7 3) (environment '(scheme base)))", expected: "21" };
+        { expression: "(eval '(caddr '(1 2 3)) (environment '(scheme base) '(scheme cxr)))", expected: "3" };
+        { expression: "(define x 10)", expected: "ok" };
+        { expression: "(eval 'x (interaction-environment))", expected: "10" };
+        { expression: "(eval '(+ x 1))", expected: "11" };
+        { expression: "(let ((x 1)) (eval 'x (interaction-environment)))", expected: "10" };
+        { expression: "(eval '(if #t 'a 'b) (null-environment 5))", expected: "a" };
+        { expression: "(eval '(string-upcase \"abc\") (scheme-report-environment 5))", expected: r#""ABC""# };
+        { expression: "(eval '(begin (define y 2) (* y y)) (environment '(scheme base)))", expected: "4" };
+        { expression: "(environment)", expected: "#<environment>" };
+    }
+
+    integration_subtest_is_err! {
+        expression: "(eval 'x (environment '(scheme base)))";
+        expression: "(eval '(exit) (environment '(scheme base)))";
+        expression: "(eval '(+ 1 2) (null-environment 5))";
+        expression: "(eval '(+ 1 2) 'env)";
+        expression: "(environment '(scheme unknown))";
+        expression: "(scheme-report-environment 7)";
+    }
+}
+
+#[test]
+fn interpreter_lexical_syntax() {
+    integration_subtest_eval_to! {
+        { expression: "(+ 1 #| two #| nested |# |# 2 #;(3))", expected: "3" };
+        { expression: "(quote |hello world|)", expected: "hello world" };
+        { expression: "(string-ref \"a\\x62;c\" 1)", expected: "#\\b" };
+        { expression: "(string-length \"a\\\"c\")", expected: "3" };
+        { expression: "(quote (ABC #!fold-case ABC))", expected: "(ABC abc)" };
+        { expression: "(make-string 2 #\\x41)", expected: "\"AA\"" };
+    }
+    integration_subtest_is_err! {
+        expression: "(+ 1 #| unterminated 2)";
+        expression: "#\\unknown";
+    }
+}
+
+#[test]
+fn interpreter_multiple_expressions() {
+    integration_subtest_eval_to! {
+        { expression: "(define x 1) (define y 2) (+ x y)", expected: "3" };
+        { expression: "(define s \"(\") ; unbalanced ( in a comment\n(string-length s)", expected: "1" };
+        { expression: "; only a comment", expected: "\n" };
+    }
+
+    integration_subtest_is_err! {
+        expression: "(define z 1) (car z)";
+        expression: "(+ 1 2) (+ 3";
+        expression: "(+ 1 2))";
+    }
+}
+
+#[test]
+fn interpreter_execute_file() {
+    let path = std::env::temp_dir().join("schemius_execute_file.scm");
+    std::fs::write(
+        &path,
+        "; leading comment (\n(define (f x)\n  ; inner comment )\n  (string-append x \")\"))\n(define s (f \"(\"))",
+    )
+    .unwrap();
+
+    let mut interpreter = schemius::Interpreter::default();
+    assert!(interpreter.execute_file(path.display().to_string()).is_ok());
+    assert_eq!(interpreter.eval_expression_and_format("s".to_string()).unwrap(), "\"()\"");
+}
+
+#[test]
+fn interpreter_error_kinds() {
+    use schemius::ErrorKind;
+
+    let mut interpreter = schemius::Interpreter::default();
+    let mut kind_of =
+        |expression: &str| interpreter.eval_expression(expression.to_string()).unwrap_err().kind();
+
+    assert_eq!(kind_of("undefined-variable"), ErrorKind::UnboundVariable);
+    assert_eq!(kind_of("(car 5)"), ErrorKind::WrongType);
+    assert_eq!(kind_of("(car '(1) '(2))"), ErrorKind::Arity);
+    assert_eq!(kind_of("(string-ref \"abc\" 10)"), ErrorKind::OutOfRange);
+    assert_eq!(kind_of("(+ 1 2"), ErrorKind::Syntax);
+    assert_eq!(kind_of("(error \"message\")"), ErrorKind::User);
+    assert_eq!(kind_of("(error 'message)"), ErrorKind::WrongType);
+
+    let error = interpreter.eval_expression("(vector-ref 1 2 3)".to_string()).unwrap_err();
+    assert!(std::error::Error::source(&error).is_none());
+
+    let error = interpreter.eval_expression("(string-length 'abc)".to_string()).unwrap_err();
+    assert_eq!(error.procedure(), Some("string-length"));
+    assert_eq!(error.irritants().len(), 1);
+    assert_eq!(error.to_string(), "Exception in string-length: abc is not a string");
+
+    let error = interpreter.eval_expression(r#"(error "bad:" 'x "y" 3)"#.to_string()).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::User);
+    assert_eq!(error.irritants().len(), 3);
+    assert_eq!(error.to_string(), r#"Exception: bad: x "y" 3"#);
+}
+
+#[test]
+fn interpreter_keywords() {
+    integration_subtest_eval_to! {
+        { expression: "(apply not '(#f))", expected: "#t" };
+        { expression: "(procedure? not)", expected: "#t" };
+        { expression: "(not 3)", expected: "#f" };
+        { expression: "(let ((if (lambda (a b c) 'shadowed))) (if #f 1 2))", expected: "shadowed" };
+        { expression: "(define (f list) (list 1)) (f (lambda (x) (* x 2)))", expected: "2" };
+        { expression: "(let ((quote -)) '5)", expected: "-5" };
+        { expression: "(eval '(if #t 1 2) (null-environment 5))", expected: "1" };
+    }
+
+    let mut interpreter = schemius::Interpreter::default();
+    for expression in ["if", "(procedure? if)", "(define x lambda)", "(apply quote '(1))"] {
+        let error = interpreter.eval_expression(expression.to_string()).unwrap_err();
+        assert_eq!(error.kind(), schemius::ErrorKind::Syntax, "{}", expression);
+    }
+}
+
+#[test]
+fn interpreter_error_trace() {
+    for strategy in [schemius::Strategy::Bytecode, schemius::Strategy::TreeWalking] {
+        for optimize in [false, true] {
+            let mut interpreter = schemius::Interpreter::default();
+            interpreter.set_strategy(strategy);
+            interpreter.set_optimize(optimize);
+            let mut trace_of = |expression: &str| {
+                let error = interpreter.eval_expression(expression.to_string()).unwrap_err();
+                error.trace().iter().map(|frame| frame.to_string()).collect::<Vec<String>>()
+            };
+
+            let trace = trace_of(
+                "(define (inner x) (car x))
+                 (define (outer x) (+ 1 (inner x)))
+                 (outer 5)",
+            );
+            let expected =
+                vec!["in inner (at line 2, column 41)", "in outer (at line 3, column 18)"];
+            assert_eq!(trace, expected, "{:?}, optimize: {}", strategy, optimize);
+
+            let trace = trace_of(
+                "(define (count-down n) (if (= n 0) (car n) (count-down (- n 1))))
+                 (count-down 1000)",
+            );
+            let expected = vec!["in count-down (at line 1, column 44) [1000 tail calls elided]"];
+            assert_eq!(trace, expected, "{:?}, optimize: {}", strategy, optimize);
+        }
+    }
+
+    let mut interpreter = schemius::Interpreter::default();
+    let error = interpreter
+        .eval_expression(
+            "(define (deep n) (if (= n 0) undefined-thing (+ 1 (deep (- n 1))))) (deep 100)"
+                .to_string(),
+        )
+        .unwrap_err();
+    assert_eq!(error.trace().len(), schemius::MAX_TRACE_FRAMES);
+    assert_eq!(error.elided_frames(), 101 - schemius::MAX_TRACE_FRAMES);
+    assert!(error.report().ends_with(&format!("... {} more frames", error.elided_frames())));
+}
+
+#[test]
+fn interpreter_deep_recursion() {
+    let mut interpreter = schemius::Interpreter::default();
+
+    let result = interpreter.eval_expression("(sum-to 100000)".to_string());
+    assert_eq!(result.unwrap().to_string(), "5000050000");
+
+    interpreter.set_max_depth(1000);
+    let error = interpreter.eval_expression("(evil 10)".to_string()).unwrap_err();
+    assert_eq!(error.kind(), schemius::ErrorKind::RecursionLimit);
+    assert_eq!(error.trace()[0].procedure, "evil");
+
+    let result = interpreter.eval_expression("(sum-to 100)".to_string());
+    assert_eq!(result.unwrap().to_string(), "5050");
+
+    let error = interpreter.eval_expression("(define (f) (time (f))) (f)".to_string()).unwrap_err();
+    assert_eq!(error.kind(), schemius::ErrorKind::RecursionLimit);
+}
+
+#[test]
+fn interpreter_nested_dynamic_wind() {
+    let programs = [
+        ("(define (h n) (if (= n 0) 0 (dynamic-wind (lambda () #f) (lambda () (h (- n 1))) (lambda () #f)))) (h 10000)", "0"),
+        ("(define (t n) (if (= n 0) 0 (+ 1 (time (t (- n 1)))))) (t 100)", "100"),
+        ("(define log '()) (define (note x) (lambda () (set! log (cons x log)))) (dynamic-wind (note 'in) (lambda () (dynamic-wind (note 'inner) (note 'body) (note 'out))) (note 'after)) log", "(after out body inner in)"),
+    ];
+
+    for strategy in [schemius::Strategy::Bytecode, schemius::Strategy::TreeWalking] {
+        let mut interpreter = schemius::Interpreter::default();
+        interpreter.set_strategy(strategy);
+
+        for (program, expected) in programs {
+            let result = interpreter.eval_expression_and_format(program.to_string());
+            assert_eq!(result.unwrap(), expected, "{} with {:?}", program, strategy);
+        }
+
+        // Leaving the thunks with an error still runs the after thunks, innermost first
+        let program = "(set! log '()) (dynamic-wind (note 'in) (lambda () (dynamic-wind (note 'inner) (lambda () (car '())) (note 'out))) (note 'after))";
+        let error = interpreter.eval_expression(program.to_string()).unwrap_err();
+        assert_eq!(error.kind(), schemius::ErrorKind::WrongType);
+        let log = interpreter.eval_expression_and_format("log".to_string());
+        assert_eq!(log.unwrap(), "(after out inner in)", "with {:?}", strategy);
+    }
+}
+
+#[test]
+fn interpreter_strategies() {
+    let programs = [
+        ("(define (fib n) (if (< n 2) n (+ (fib (- n 1)) (fib (- n 2))))) (fib 15)", "610"),
+        ("(let loop ((n 0) (acc '())) (if (= n 3) acc (loop (+ n 1) (cons n acc))))", "(2 1 0)"),
+        ("(do ((k 0 (+ k 1)) (acc '() (cons k acc))) ((= k 3) acc))", "(2 1 0)"),
+        ("(cond (#f 'none) ((- 3 1) => (lambda (v) (* v v))))", "4"),
+        ("(case (* 2 3) ((2 3 5 7) 'prime) ((1 4 6 8 9) 'composite))", "composite"),
+        ("(let* ((x 1) (y (+ x 1))) (letrec ((z (lambda () y))) (list x (z))))", "(1 2)"),
+        ("(let ((if list)) (if 1 2 3))", "(1 2 3)"),
+        ("(define (f) (define x 1) (set! x (+ x 1)) x) (f)", "2"),
+        ("(apply (lambda (a b) (- a b)) '(5 3))", "2"),
+        ("(list (and 1 2) (or #f 3) (unless #f 4))", "(2 3 4)"),
+    ];
+
+    for strategy in [schemius::Strategy::Bytecode, schemius::Strategy::TreeWalking] {
+        let mut interpreter = schemius::Interpreter::default();
+        interpreter.set_strategy(strategy);
+
+        for (program, expected) in programs {
+            let result = interpreter.eval_expression_and_format(program.to_string());
+            assert_eq!(result.unwrap(), expected, "{} with {:?}", program, strategy);
+        }
+
+        let error = interpreter.eval_expression("(+ 1 (car '()))".to_string()).unwrap_err();
+        assert_eq!(error.kind(), schemius::ErrorKind::WrongType);
+    }
+}
+
+/// Each interpreter keeps its own settings, which evaluations started by builtins and those in
+/// the environments of libraries follow too.
+#[test]
+fn interpreter_settings() {
+    let count = "(define (count n) (if (= n 0) 0 (+ 1 (count (- n 1)))))";
+    let program = format!(
+        "{} (define-library (counting) (export count) (import (scheme base)) (begin {})) (import (prefix (counting) lib-))",
+        count, count
+    );
+    let calls = [
+        "(count 1000)",
+        "(eval '(count 1000) (interaction-environment))",
+        "(apply count '(1000))",
+        "(lib-count 1000)",
+        "(eval '((lambda (f) (f f 1000)) (lambda (f n) (if (= n 0) 0 (+ 1 (f f (- n 1)))))) (environment '(scheme base)))",
+    ];
+
+    let mut limited = schemius::Interpreter::default();
+    limited.set_max_depth(100);
+    limited.set_strategy(schemius::Strategy::TreeWalking);
+    let mut unlimited = schemius::Interpreter::default();
+
+    for interpreter in [&mut limited, &mut unlimited] {
+        interpreter.eval_expression(program.clone()).unwrap();
+    }
+
+    for call in calls {
+        let error = limited.eval_expression(call.to_string()).unwrap_err();
+        assert_eq!(error.kind(), schemius::ErrorKind::RecursionLimit, "{}", call);
+
+        let result = unlimited.eval_expression_and_format(call.to_string());
+        assert_eq!(result.unwrap(), "1000", "{}", call);
+    }
+}
+
+#[test]
+fn interpreter_optimize() {
+    let programs = [
+        ("(define (f n) (if (< 1 2) (* n (+ 1 2)) (car '()))) (f 2)", "6"),
+        ("(let loop ((n 0) (acc '())) (if (= n 3) acc (loop (+ n 1) (cons n acc))))", "(2 1 0)"),
+        ("(do ((k 0 (+ k 1)) (acc '() (cons k acc))) ((= k (- 4 1)) acc))", "(2 1 0)"),
+        ("(cond ((> 1 2) 'none) ((- 3 1) => (lambda (v) (* v v))))", "4"),
+        ("(case (* 2 3) ((2 3 5 7) 'prime) ((1 4 6 8 9) 'composite))", "composite"),
+        ("(let ((+ *)) (+ 2 3))", "6"),
+        ("(define (g car) (car 1)) (g (lambda (x) (- x)))", "-1"),
+        ("(begin (define - +) (- 1 2))", "3"),
+    ];
+
+    for strategy in [schemius::Strategy::Bytecode, schemius::Strategy::TreeWalking] {
+        let mut interpreter = schemius::Interpreter::default();
+        interpreter.set_strategy(strategy);
+        interpreter.set_optimize(true);
+
+        for (program, expected) in programs {
+            let result = interpreter.eval_expression_and_format(program.to_string());
+            assert_eq!(result.unwrap(), expected, "{} with {:?}", program, strategy);
+        }
+
+        let error = interpreter.eval_expression("(/ 1 0)".to_string()).unwrap_err();
+        assert_eq!(error.kind(), schemius::ErrorKind::OutOfRange);
+    }
+}
+
+#[test]
+fn interpreter_optimize_rebound_primitive() {
+    let programs = [
+        ("(define (f n) (* n (+ 1 2))) (f 2)", "6"),
+        ("(define (swap!) (set! + -)) (begin (swap!) (f 2))", "-2"),
+        ("(f 3)", "-3"),
+        ("(define (first l) (car l)) (first '(1 2))", "1"),
+        ("(define car cdr) (first '(1 2))", "(2)"),
+    ];
+
+    for strategy in [schemius::Strategy::Bytecode, schemius::Strategy::TreeWalking] {
+        let mut interpreter = schemius::Interpreter::default();
+        interpreter.set_strategy(strategy);
+        interpreter.set_optimize(true);
+
+        for (program, expected) in programs {
+            let result = interpreter.eval_expression_and_format(program.to_string());
+            assert_eq!(result.unwrap(), expected, "{} with {:?}", program, strategy);
+        }
+    }
+}
+
+#[test]
+fn interpreter_lexical_scope() {
+    integration_subtest_eval_to! {
+        {expression: "(define (counter) (let ((n 0)) (lambda () (set! n (+ n 1)) n)))", expected: "ok"};
+        {expression: "(define c1 (counter))", expected: "ok"};
+        {expression: "(define c2 (counter))", expected: "ok"};
+        {expression: "(list (c1) (c1) (c2))", expected: "(1 2 1)"};
+        {expression: "(define procs '())", expected: "ok"};
+        {expression: "(do ((k 0 (+ k 1))) ((= k 3) 'done) (set! procs (cons (lambda () k) procs)))", expected: "done"};
+        {expression: "(list ((car procs)) ((cadr procs)))", expected: "(2 1)"};
+        {expression: "(define x 'global)", expected: "ok"};
+        {expression: "(define (shadow x) (let ((x (list x))) (let* ((y x) (x (cons 0 y))) x)))", expected: "ok"};
+        {expression: "(list (shadow 1) x)", expected: "((0 1) global)"};
+        {expression: "(define (parity n) (define (ev? n) (if (= n 0) #t (od? (- n 1)))) (define (od? n) (if (= n 0) #f (ev? (- n 1)))) (ev? n))", expected: "ok"};
+        {expression: "(parity 10)", expected: "#t"};
+        {expression: "(define (late) (later))", expected: "ok"};
+        {expression: "(define (later) 1)", expected: "ok"};
+        {expression: "(late)", expected: "1"};
+        {expression: "(define (later) 2)", expected: "ok"};
+        {expression: "(late)", expected: "2"};
+        {expression: "(define (quoted a) (let ((b 2)) `(,a ,b ,x)))", expected: "ok"};
+        {expression: "(quoted 1)", expected: "(1 2 global)"};
+    }
+}
+
+#[test]
+fn interpreter_symbols() {
+    integration_subtest_eval_to! {
+        {expression: "(eq? 'abc 'abc)", expected: "#t"};
+        {expression: "(eq? 'abc 'abd)", expected: "#f"};
+        {expression: "(eq? (string->symbol \"abc\") 'abc)", expected: "#t"};
+        {expression: "(symbol->string 'abc)", expected: "\"abc\""};
+        {expression: "(symbol=? 'a 'a (string->symbol \"a\"))", expected: "#t"};
+        {expression: "(define g (gensym))", expected: "ok"};
+        {expression: "(list (symbol? g) (eq? g g) (eqv? g (string->symbol (symbol->string g))))", expected: "(#t #t #f)"};
+        {expression: "(eq? (generate-uninterned-symbol 'x) (generate-uninterned-symbol 'x))", expected: "#f"};
+        {expression: "(case (string->symbol \"b\") ((a) 1) ((b) 2) (else 3))", expected: "2"};
+        {expression: "(let ((s \"str\")) (list (eq? s s) (eqv? s \"str\")))", expected: "(#t #f)"};
+    }
+}
+
+#[test]
+fn interpreter_garbage_collection() {
+    integration_subtest_eval_to! {
+        {expression: "(define (live kind) (cdr (car (list-tail (heap-statistics) kind))))", expected: "ok"};
+        {expression: "(define (cycle) (let ((p (list 1 2))) (set-cdr! (cdr p) p) p))", expected: "ok"};
+        {expression: "(define (closure) (define (self) self) self)", expected: "ok"};
+        {expression: "(define (repeat f n) (if (> n 0) (begin (f) (repeat f (- n 1))) 'done))", expected: "ok"};
+        {expression: "(define before (begin (gc) (list (live 0) (live 4))))", expected: "ok"};
+        {expression: "(repeat cycle 1000)", expected: "done"};
+        {expression: "(repeat closure 1000)", expected: "done"};
+        {expression: "(>= (gc) 3000)", expected: "#t"};
+        {expression: "(define after (begin (gc) (list (live 0) (live 4))))", expected: "ok"};
+        // Only the two pairs of `before` were kept
+        {expression: "(list (- (car after) (car before)) (- (cadr after) (cadr before)))", expected: "(2 0)"};
+        {expression: "(repeat cycle 20000)", expected: "done"};
+        {expression: "(< (- (live 0) (car before)) 40000)", expected: "#t"};
+    }
+}
+
+#[cfg(feature = "thread_safe")]
+#[test]
+fn interpreter_across_threads() {
+    let mut interpreter = schemius::Interpreter::default();
+    interpreter.eval_expression("(define (square x) (* x x))".to_string()).unwrap();
+
+    let handle = std::thread::spawn(move || {
+        interpreter.eval_expression_and_format("(square 12)".to_string()).unwrap()
+    });
+
+    assert_eq!(handle.join().unwrap(), "144");
+}
@@ -0,0 +1,111 @@
+/// Iterations of each loop run by default, far more than the continuations the interpreter is
+/// allowed to keep.
+const ITERATIONS: usize = 10_000;
+
+/// Iterations of each loop run by the tests ignored by default, which `cargo test -- --ignored`
+/// runs.
+const LONG_ITERATIONS: usize = 1_000_000;
+
+/// Continuations allowed while running the loops, so that any call in tail position which
+/// pushes one fails rather than only using more memory.
+const MAX_DEPTH: usize = 100;
+
+/// Defines `(loop n)` with `definition`, which must count `n` down to zero, and runs it for
+/// `iterations` with each strategy. Loops without an `expected` result return the number of
+/// iterations.
+fn assert_loops(definition: &str, expected: Option<&str>, iterations: usize) {
+    let expected = expected.map_or_else(|| iterations.to_string(), str::to_string);
+
+    for strategy in [schemius::Strategy::Bytecode, schemius::Strategy::TreeWalking] {
+        let mut interpreter = schemius::Interpreter::default();
+        interpreter.set_strategy(strategy);
+        interpreter.set_max_depth(MAX_DEPTH);
+
+        let program = format!("{} (loop {})", definition, iterations);
+        match interpreter.eval_expression_and_format(program) {
+            Ok(result) => assert_eq!(result, expected, "{:?}", strategy),
+            Err(err) => panic!("Error with {:?}: {}", strategy, err),
+        }
+    }
+}
+
+/// Defines a module for each loop, testing it with `ITERATIONS` and, when ignored tests are
+/// run, with `LONG_ITERATIONS`.
+macro_rules! loops {
+    ($($name:ident: $definition:expr => $expected:expr;)*) => {
+        $(
+            mod $name {
+                use super::*;
+
+                #[test]
+                fn short() {
+                    assert_loops($definition, $expected, ITERATIONS);
+                }
+
+                #[test]
+                #[ignore = "runs a million iterations"]
+                fn long() {
+                    assert_loops($definition, $expected, LONG_ITERATIONS);
+                }
+            }
+        )*
+    };
+}
+
+loops! {
+    tail_call_in_body_if_and_begin:
+        "(define (loop n) (display \"\") (if (= n 0) 'done (begin n (loop (- n 1)))))"
+        => Some("done");
+
+    tail_call_in_let_and_let_star:
+        "(define (loop n)
+           (let ((m (- n 1)))
+             (let* ((k m) (l k))
+               (if (< l 0) 'done (loop l)))))"
+        => Some("done");
+
+    tail_call_in_letrec:
+        "(define (loop n)
+           (letrec ((even? (lambda (k) (if (= k 0) #t (odd? (- k 1)))))
+                    (odd? (lambda (k) (if (= k 0) #f (even? (- k 1))))))
+             (even? n)))"
+        => Some("#t");
+
+    tail_call_in_named_let:
+        "(define (loop n) (let down ((k n) (acc 0)) (if (= k 0) acc (down (- k 1) (+ acc 1)))))"
+        => None;
+
+    tail_call_in_cond:
+        "(define (loop n)
+           (cond ((= n 0) 'done)
+                 ((- n 1) => (lambda (m) (cond ((= m 0) 'done) (else (loop (- m 1))))))))"
+        => Some("done");
+
+    tail_call_in_case:
+        "(define (loop n)
+           (case n
+             ((0) 'done)
+             ((1 3 5 7 9) (loop (- n 1)))
+             (else => (lambda (k) (loop (- k 1))))))"
+        => Some("done");
+
+    tail_call_in_when_and_unless:
+        "(define (loop n) (if (= n 0) 'done (when (> n 0) (unless (< n 0) n (loop (- n 1))))))"
+        => Some("done");
+
+    tail_call_in_and_and_or:
+        "(define (loop n) (or (= n 0) (and #t (loop (- n 1)))))"
+        => Some("#t");
+
+    tail_call_in_do_result:
+        "(define (loop n) (do () (#t (if (= n 0) 'done (loop (- n 1))))))"
+        => Some("done");
+
+    tail_call_through_apply:
+        "(define (loop n) (if (= n 0) 'done (apply loop (- n 1) '())))"
+        => Some("done");
+
+    do_loop_runs_in_constant_space:
+        "(define (loop n) (do ((k n (- k 1)) (acc 0 (+ acc 1))) ((= k 0) acc)))"
+        => None;
+}